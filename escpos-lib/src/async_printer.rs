@@ -0,0 +1,175 @@
+use tokio::sync::{mpsc, oneshot};
+
+use std::{
+    future::Future,
+    io::{self, ErrorKind},
+    pin::Pin,
+    task::{Context, Poll},
+    thread,
+    time::Duration,
+};
+
+use super::{Document, Error, PrintJob, Printer, PrinterProfile, Result, Transport};
+
+/// Time to wait for a job to be printed if nothing else is configured.
+pub const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(60);
+
+type Job<T> = Box<dyn FnOnce(&mut Printer<T>) + Send>;
+
+/// Handle to a [`Printer`] running on its own thread.
+///
+/// Writing to a serial printer blocks for as long as the data takes to
+/// transmit, which stalls an async runtime. The printer is moved to a
+/// dedicated thread instead, jobs are queued and run one after another.
+/// Once the queue is full, submitting waits for a free slot.
+///
+/// After every job the printer is [drained](Printer::drain), so awaiting
+/// a job means its output has actually been processed by the printer.
+/// Flow control is configured on the transport, i.e. with
+/// `serialport::SerialPortBuilder::flow_control`.
+///
+/// Cloning the handle shares the queue. The thread stops once all
+/// handles are dropped and the queue is empty.
+pub struct AsyncPrinter<T: Transport> {
+    jobs: mpsc::Sender<Job<T>>,
+    profile: PrinterProfile,
+    drain_timeout: Duration,
+}
+
+/// Completion of a submitted job.
+///
+/// Resolves to the result of the job once its output drained.
+pub struct Drained<R> {
+    result: oneshot::Receiver<Result<R>>,
+}
+
+impl<T> AsyncPrinter<T>
+where
+    T: Transport + Send + 'static,
+{
+    /// Move `printer` to a new thread, queueing at most `queue` jobs.
+    pub fn spawn(printer: Printer<T>, queue: usize) -> Result<Self> {
+        let profile = printer.profile().clone();
+        let (jobs, mut rx) = mpsc::channel::<Job<T>>(queue.max(1));
+        thread::Builder::new()
+            .name(String::from("printer"))
+            .spawn(move || {
+                let mut printer = printer;
+                while let Some(job) = rx.blocking_recv() {
+                    job(&mut printer);
+                }
+            })?;
+        Ok(AsyncPrinter {
+            jobs,
+            profile,
+            drain_timeout: DEFAULT_DRAIN_TIMEOUT,
+        })
+    }
+
+    /// Capabilities of the printer.
+    pub fn profile(&self) -> &PrinterProfile {
+        &self.profile
+    }
+
+    /// Create an empty document fitting the paper.
+    pub fn document(&self) -> Document {
+        Document::for_profile(&self.profile)
+    }
+
+    /// Set how long to wait for the printer to process a job.
+    pub fn set_drain_timeout(&mut self, timeout: Duration) {
+        self.drain_timeout = timeout;
+    }
+
+    /// Queue `job`, waiting while the queue is full.
+    ///
+    /// The returned [`Drained`] resolves once the job ran and the
+    /// printer processed its output. Dropping it does not cancel the job.
+    pub async fn submit<F, R>(&self, job: F) -> Result<Drained<R>>
+    where
+        F: FnOnce(&mut Printer<T>) -> Result<R> + Send + 'static,
+        R: Send + 'static,
+    {
+        let (tx, result) = oneshot::channel();
+        let timeout = self.drain_timeout;
+        let job: Job<T> = Box::new(move |printer| {
+            let res = job(printer).and_then(|res| printer.drain(timeout).map(|_| res));
+            // The submitter may not be interested in the result
+            let _ = tx.send(res);
+        });
+        self.jobs.send(job).await.map_err(|_| stopped())?;
+        Ok(Drained { result })
+    }
+
+    /// Run `job` and wait until its output drained.
+    pub async fn run<F, R>(&self, job: F) -> Result<R>
+    where
+        F: FnOnce(&mut Printer<T>) -> Result<R> + Send + 'static,
+        R: Send + 'static,
+    {
+        self.submit(job).await?.await
+    }
+
+    pub async fn print_document(&self, document: Document) -> Result<()> {
+        self.run(move |printer| printer.print_document(&document))
+            .await
+    }
+
+    pub async fn print_job(&self, job: PrintJob) -> Result<()> {
+        self.run(move |printer| printer.print_job(&job)).await
+    }
+
+    pub async fn write_and_cut<S: Into<String>>(&self, text: S) -> Result<()> {
+        let text = text.into();
+        self.run(move |printer| printer.write_and_cut(text)).await
+    }
+
+    /// Number of jobs that can be submitted without waiting.
+    pub fn free_slots(&self) -> usize {
+        self.jobs.capacity()
+    }
+}
+
+impl<T: Transport> Clone for AsyncPrinter<T> {
+    fn clone(&self) -> Self {
+        AsyncPrinter {
+            jobs: self.jobs.clone(),
+            profile: self.profile.clone(),
+            drain_timeout: self.drain_timeout,
+        }
+    }
+}
+
+impl<R> Future for Drained<R> {
+    type Output = Result<R>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.result)
+            .poll(cx)
+            .map(|res| res.unwrap_or_else(|_| Err(stopped())))
+    }
+}
+
+/// The printer thread panicked.
+fn stopped() -> Error {
+    Error::Io(io::Error::new(
+        ErrorKind::BrokenPipe,
+        "printer thread stopped",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryTransport;
+
+    #[tokio::test]
+    async fn job_without_response_resolves_ok() {
+        // Printers with only the receive line wired never answer
+        let printer = Printer::new(MemoryTransport::new()).unwrap();
+        let mut printer = AsyncPrinter::spawn(printer, 1).unwrap();
+        printer.set_drain_timeout(Duration::from_millis(10));
+        printer.run(|printer| printer.check_ready()).await.unwrap();
+        printer.write_and_cut("Hello").await.unwrap();
+    }
+}
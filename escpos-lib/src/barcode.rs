@@ -0,0 +1,689 @@
+use std::{error, fmt};
+
+use super::chars;
+
+/// Supported bar code symbologies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BarCodeSystem {
+    UpcA,
+    UpcE,
+    Ean13,
+    Ean8,
+    Code39,
+    Itf,
+    Codabar,
+    Code93,
+    Code128,
+}
+
+/// Where to print the human readable interpretation (HRI) characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HriPosition {
+    None,
+    Above,
+    Below,
+    Both,
+}
+
+/// Font used for the human readable interpretation (HRI) characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HriFont {
+    A,
+    B,
+}
+
+/// Bar code with validated data, ready to print.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BarCode {
+    system: BarCodeSystem,
+    data: Vec<u8>,
+}
+
+/// Appearance of a printed bar code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BarCodeStyle {
+    /// Height in dots, `1..=255`.
+    pub height: u8,
+    /// Width of a single module in dots, `2..=6`.
+    pub module_width: u8,
+    pub hri_position: HriPosition,
+    pub hri_font: HriFont,
+}
+
+/// Reasons a bar code cannot be printed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BarCodeError {
+    /// The data has an unsupported number of characters.
+    InvalidLength {
+        system: BarCodeSystem,
+        len: usize,
+    },
+    /// The data contains a character not encodable with the symbology.
+    InvalidChar {
+        system: BarCodeSystem,
+        pos: usize,
+        c: char,
+    },
+    /// The given check digit does not match the data.
+    InvalidCheckDigit {
+        expected: u8,
+        found: u8,
+    },
+    /// Start/stop characters are missing or misplaced.
+    InvalidStartStop(BarCodeSystem),
+    /// UPC-E data must start with the number system `0`.
+    InvalidNumberSystem,
+    /// UPC-A data given for UPC-E cannot be compressed.
+    NotCompressible,
+    /// CODE128 data does not start with a code set selection or
+    /// contains an unknown special character after `{`.
+    InvalidCode128Escape {
+        pos: usize,
+    },
+    InvalidHeight(u8),
+    InvalidModuleWidth(u8),
+}
+
+impl BarCode {
+    /// Validate `data` for the given symbology.
+    ///
+    /// CODE128 data must begin with a code set selection (`{A`, `{B` or `{C`),
+    /// see [`BarCode::code128`] for a convenient alternative.
+    pub fn new(system: BarCodeSystem, data: &str) -> Result<Self, BarCodeError> {
+        validate(system, data)?;
+        Ok(BarCode {
+            system,
+            data: data.as_bytes().to_vec(),
+        })
+    }
+
+    /// Create a CODE128 bar code from plain text using code set B.
+    ///
+    /// Braces are escaped as necessary.
+    pub fn code128(text: &str) -> Result<Self, BarCodeError> {
+        let data = format!("{{B{}", text.replace('{', "{{"));
+        Self::new(BarCodeSystem::Code128, &data)
+    }
+
+    pub fn system(&self) -> BarCodeSystem {
+        self.system
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Encode the style setup and print command.
+    pub(crate) fn encode(&self, style: &BarCodeStyle) -> Result<Vec<u8>, BarCodeError> {
+        style.validate()?;
+        let gs = chars::GS as u8;
+        let hri_position = match style.hri_position {
+            HriPosition::None => 0,
+            HriPosition::Above => 1,
+            HriPosition::Below => 2,
+            HriPosition::Both => 3,
+        };
+        let hri_font = match style.hri_font {
+            HriFont::A => 0,
+            HriFont::B => 1,
+        };
+        let mut bytes = vec![
+            gs,
+            b'h',
+            style.height,
+            gs,
+            b'w',
+            style.module_width,
+            gs,
+            b'H',
+            hri_position,
+            gs,
+            b'f',
+            hri_font,
+            gs,
+            b'k',
+            self.system.code(),
+            self.data.len() as u8,
+        ];
+        bytes.extend_from_slice(&self.data);
+        Ok(bytes)
+    }
+}
+
+impl BarCodeSystem {
+    /// Parameter `m` of `GS k` (function B).
+    fn code(self) -> u8 {
+        match self {
+            BarCodeSystem::UpcA => 65,
+            BarCodeSystem::UpcE => 66,
+            BarCodeSystem::Ean13 => 67,
+            BarCodeSystem::Ean8 => 68,
+            BarCodeSystem::Code39 => 69,
+            BarCodeSystem::Itf => 70,
+            BarCodeSystem::Codabar => 71,
+            BarCodeSystem::Code93 => 72,
+            BarCodeSystem::Code128 => 73,
+        }
+    }
+
+    /// Parse parameter `m` of `GS k`, accepting both function A and B.
+    #[cfg(feature = "preview")]
+    pub(crate) fn from_code(code: u8) -> Option<Self> {
+        let system = match code {
+            0 | 65 => BarCodeSystem::UpcA,
+            1 | 66 => BarCodeSystem::UpcE,
+            2 | 67 => BarCodeSystem::Ean13,
+            3 | 68 => BarCodeSystem::Ean8,
+            4 | 69 => BarCodeSystem::Code39,
+            5 | 70 => BarCodeSystem::Itf,
+            6 | 71 => BarCodeSystem::Codabar,
+            72 => BarCodeSystem::Code93,
+            73 => BarCodeSystem::Code128,
+            _ => return None,
+        };
+        Some(system)
+    }
+}
+
+impl BarCodeStyle {
+    pub fn validate(&self) -> Result<(), BarCodeError> {
+        if self.height == 0 {
+            return Err(BarCodeError::InvalidHeight(self.height));
+        }
+        if !(2..=6).contains(&self.module_width) {
+            return Err(BarCodeError::InvalidModuleWidth(self.module_width));
+        }
+        Ok(())
+    }
+}
+
+impl Default for BarCodeStyle {
+    fn default() -> Self {
+        BarCodeStyle {
+            height: 162,
+            module_width: 3,
+            hri_position: HriPosition::None,
+            hri_font: HriFont::A,
+        }
+    }
+}
+
+fn validate(system: BarCodeSystem, data: &str) -> Result<(), BarCodeError> {
+    use BarCodeSystem::*;
+    let len = data.chars().count();
+    let invalid_length = Err(BarCodeError::InvalidLength { system, len });
+    // Every symbology is limited by the single length byte
+    if len == 0 || len > 255 {
+        return invalid_length;
+    }
+    match system {
+        UpcA => {
+            if !(11..=12).contains(&len) {
+                return invalid_length;
+            }
+            check_chars(system, data, |c| c.is_ascii_digit())?;
+            check_digit(data, 12)
+        }
+        UpcE => {
+            if !matches!(len, 6 | 7 | 8 | 11 | 12) {
+                return invalid_length;
+            }
+            check_chars(system, data, |c| c.is_ascii_digit())?;
+            if len != 6 && !data.starts_with('0') {
+                return Err(BarCodeError::InvalidNumberSystem);
+            }
+            // Six and seven digits carry no check digit, eleven and twelve are UPC-A
+            let digits: Vec<u8> = data.bytes().map(|b| b - b'0').collect();
+            match len {
+                8 => check_digit_of(&upc_e_expand(&digits[1..7]), digits[7]),
+                11 | 12 => {
+                    if upc_e_compress(&digits[..11]).is_none() {
+                        return Err(BarCodeError::NotCompressible);
+                    }
+                    check_digit(data, 12)
+                }
+                _ => Ok(()),
+            }
+        }
+        Ean13 => {
+            if !(12..=13).contains(&len) {
+                return invalid_length;
+            }
+            check_chars(system, data, |c| c.is_ascii_digit())?;
+            check_digit(data, 13)
+        }
+        Ean8 => {
+            if !(7..=8).contains(&len) {
+                return invalid_length;
+            }
+            check_chars(system, data, |c| c.is_ascii_digit())?;
+            check_digit(data, 8)
+        }
+        Code39 => {
+            check_chars(system, data, |c| {
+                c.is_ascii_digit() || c.is_ascii_uppercase() || " $%*+-./".contains(c)
+            })?;
+            // '*' is only allowed as start and stop character
+            let inner = data.trim_start_matches('*').trim_end_matches('*');
+            let starts = data.starts_with('*');
+            let ends = len > 1 && data.ends_with('*');
+            if inner.contains('*') || starts != ends || data.len() - inner.len() > 2 {
+                return Err(BarCodeError::InvalidStartStop(system));
+            }
+            Ok(())
+        }
+        Itf => {
+            if len < 2 || len % 2 == 1 {
+                return invalid_length;
+            }
+            check_chars(system, data, |c| c.is_ascii_digit())
+        }
+        Codabar => {
+            if len < 2 {
+                return invalid_length;
+            }
+            let is_start_stop = |c: char| "ABCDabcd".contains(c);
+            check_chars(system, data, |c| {
+                c.is_ascii_digit() || is_start_stop(c) || "$+-./:".contains(c)
+            })?;
+            let bytes = data.as_bytes();
+            let inner = &bytes[1..len - 1];
+            if !is_start_stop(bytes[0] as char)
+                || !is_start_stop(bytes[len - 1] as char)
+                || inner.iter().any(|&b| is_start_stop(b as char))
+            {
+                return Err(BarCodeError::InvalidStartStop(system));
+            }
+            Ok(())
+        }
+        Code93 => check_chars(system, data, |c| c.is_ascii()),
+        Code128 => {
+            if len < 2 {
+                return invalid_length;
+            }
+            check_chars(system, data, |c| c.is_ascii())?;
+            let bytes = data.as_bytes();
+            if bytes[0] != b'{' || !matches!(bytes[1], b'A' | b'B' | b'C') {
+                return Err(BarCodeError::InvalidCode128Escape { pos: 0 });
+            }
+            let mut set = bytes[1];
+            // `{S` switches between code set A and B for the next character
+            let mut shift = false;
+            let mut pos = 2;
+            while pos < len {
+                let current = match (shift, set) {
+                    (true, b'A') => b'B',
+                    (true, b'B') => b'A',
+                    _ => set,
+                };
+                if bytes[pos] == b'{' {
+                    match bytes.get(pos + 1) {
+                        Some(&code @ (b'A' | b'B' | b'C')) if !shift => set = code,
+                        Some(b'1' | b'2' | b'3' | b'4') if !shift => {}
+                        Some(b'S') if !shift && set != b'C' => {
+                            shift = true;
+                            pos += 2;
+                            continue;
+                        }
+                        Some(b'{') if current == b'B' => {}
+                        _ => return Err(BarCodeError::InvalidCode128Escape { pos }),
+                    }
+                    pos += 2;
+                } else {
+                    if !code128_set_contains(current, bytes[pos]) {
+                        let c = bytes[pos] as char;
+                        return Err(BarCodeError::InvalidChar { system, pos, c });
+                    }
+                    pos += 1;
+                }
+                shift = false;
+            }
+            if shift {
+                return Err(BarCodeError::InvalidCode128Escape { pos: len - 2 });
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Whether CODE128 code set `A`, `B` or `C` can encode `byte`.
+///
+/// Code set C takes pairs of digits as single bytes `0..=99`.
+fn code128_set_contains(set: u8, byte: u8) -> bool {
+    match set {
+        b'A' => byte < 0x60,
+        b'B' => byte >= 0x20,
+        _ => byte <= 99,
+    }
+}
+
+/// Make sure all characters satisfy `valid`.
+fn check_chars<F>(system: BarCodeSystem, data: &str, valid: F) -> Result<(), BarCodeError>
+where
+    F: Fn(char) -> bool,
+{
+    match data.chars().enumerate().find(|(_, c)| !valid(*c)) {
+        Some((pos, c)) => Err(BarCodeError::InvalidChar { system, pos, c }),
+        None => Ok(()),
+    }
+}
+
+/// Verify the UPC/EAN check digit if `data` has the `full_len` including it.
+///
+/// Expects `data` to only contain ASCII digits.
+fn check_digit(data: &str, full_len: usize) -> Result<(), BarCodeError> {
+    if data.len() != full_len {
+        return Ok(());
+    }
+    let digits: Vec<u8> = data.bytes().map(|b| b - b'0').collect();
+    let (payload, found) = digits.split_at(full_len - 1);
+    check_digit_of(payload, found[0])
+}
+
+/// Verify that `found` is the UPC/EAN check digit of `payload`.
+fn check_digit_of(payload: &[u8], found: u8) -> Result<(), BarCodeError> {
+    let expected = upc_check_digit(payload);
+    if expected == found {
+        Ok(())
+    } else {
+        Err(BarCodeError::InvalidCheckDigit { expected, found })
+    }
+}
+
+/// Expand the six digits of a UPC-E code to the eleven digits of the
+/// UPC-A code it stands for, with number system `0`.
+fn upc_e_expand(body: &[u8]) -> Vec<u8> {
+    let (d1, d2, d3, d4, d5, d6) = (body[0], body[1], body[2], body[3], body[4], body[5]);
+    match d6 {
+        0..=2 => vec![0, d1, d2, d6, 0, 0, 0, 0, d3, d4, d5],
+        3 => vec![0, d1, d2, d3, 0, 0, 0, 0, 0, d4, d5],
+        4 => vec![0, d1, d2, d3, d4, 0, 0, 0, 0, 0, d5],
+        _ => vec![0, d1, d2, d3, d4, d5, 0, 0, 0, 0, d6],
+    }
+}
+
+/// Compress the first eleven digits of a UPC-A code to the six digits of a
+/// UPC-E code, if the manufacturer and product number allow it.
+fn upc_e_compress(upc_a: &[u8]) -> Option<Vec<u8>> {
+    let (system, m, p) = (upc_a[0], &upc_a[1..6], &upc_a[6..11]);
+    if system != 0 {
+        return None;
+    }
+    let body = if m[2] <= 2 && m[3..] == [0, 0] && p[..2] == [0, 0] {
+        vec![m[0], m[1], p[2], p[3], p[4], m[2]]
+    } else if m[3..] == [0, 0] && p[..3] == [0, 0, 0] {
+        vec![m[0], m[1], m[2], p[3], p[4], 3]
+    } else if m[4] == 0 && p[..4] == [0, 0, 0, 0] {
+        vec![m[0], m[1], m[2], m[3], p[4], 4]
+    } else if p[..4] == [0, 0, 0, 0] && p[4] >= 5 {
+        vec![m[0], m[1], m[2], m[3], m[4], p[4]]
+    } else {
+        return None;
+    };
+    Some(body)
+}
+
+/// Check digit of UPC and EAN codes for the digit values in `payload`.
+pub(crate) fn upc_check_digit(payload: &[u8]) -> u8 {
+    // Weights alternate 3, 1, 3, ... starting from the rightmost payload digit
+    let sum: u32 = payload
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(idx, &d)| if idx % 2 == 0 { 3 * d as u32 } else { d as u32 })
+        .sum();
+    ((10 - sum % 10) % 10) as u8
+}
+
+impl fmt::Display for BarCodeSystem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            BarCodeSystem::UpcA => "UPC-A",
+            BarCodeSystem::UpcE => "UPC-E",
+            BarCodeSystem::Ean13 => "EAN13",
+            BarCodeSystem::Ean8 => "EAN8",
+            BarCodeSystem::Code39 => "CODE39",
+            BarCodeSystem::Itf => "ITF",
+            BarCodeSystem::Codabar => "CODABAR",
+            BarCodeSystem::Code93 => "CODE93",
+            BarCodeSystem::Code128 => "CODE128",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl fmt::Display for BarCodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BarCodeError::InvalidLength { system, len } => {
+                write!(f, "{} cannot encode {} characters", system, len)
+            }
+            BarCodeError::InvalidChar { system, pos, c } => {
+                write!(f, "{} cannot encode {:?} at position {}", system, c, pos)
+            }
+            BarCodeError::InvalidCheckDigit { expected, found } => {
+                write!(f, "check digit should be {}, found {}", expected, found)
+            }
+            BarCodeError::InvalidStartStop(system) => {
+                write!(f, "invalid start/stop characters for {}", system)
+            }
+            BarCodeError::InvalidNumberSystem => {
+                write!(f, "UPC-E data must start with number system 0")
+            }
+            BarCodeError::NotCompressible => {
+                write!(f, "UPC-A data cannot be compressed to UPC-E")
+            }
+            BarCodeError::InvalidCode128Escape { pos } => {
+                write!(f, "invalid CODE128 special character at position {}", pos)
+            }
+            BarCodeError::InvalidHeight(height) => {
+                write!(f, "invalid bar code height {}", height)
+            }
+            BarCodeError::InvalidModuleWidth(width) => {
+                write!(f, "invalid bar code module width {}, expected 2-6", width)
+            }
+        }
+    }
+}
+
+impl error::Error for BarCodeError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use BarCodeSystem::*;
+
+    fn accepts(system: BarCodeSystem, data: &str) {
+        assert_eq!(validate(system, data), Ok(()), "{} {:?}", system, data);
+    }
+
+    fn rejects(system: BarCodeSystem, data: &str, error: BarCodeError) {
+        assert_eq!(validate(system, data), Err(error), "{} {:?}", system, data);
+    }
+
+    #[test]
+    fn check_digit() {
+        assert_eq!(upc_check_digit(&[0, 3, 6, 0, 0, 0, 2, 9, 1, 4, 5]), 2);
+        assert_eq!(upc_check_digit(&[9, 6, 3, 8, 5, 0, 7]), 4);
+        assert_eq!(upc_check_digit(&[4, 0, 0, 6, 3, 8, 1, 3, 3, 3, 9, 3]), 1);
+        assert_eq!(upc_check_digit(&[0; 11]), 0);
+    }
+
+    #[test]
+    fn upc_a() {
+        accepts(UpcA, "03600029145");
+        accepts(UpcA, "036000291452");
+        let len = |len| BarCodeError::InvalidLength { system: UpcA, len };
+        rejects(UpcA, "0360002914", len(10));
+        rejects(UpcA, "0360002914520", len(13));
+        let c = BarCodeError::InvalidChar {
+            system: UpcA,
+            pos: 10,
+            c: 'a',
+        };
+        rejects(UpcA, "0360002914a", c);
+        let check = BarCodeError::InvalidCheckDigit {
+            expected: 2,
+            found: 3,
+        };
+        rejects(UpcA, "036000291453", check);
+    }
+
+    #[test]
+    fn upc_e() {
+        accepts(UpcE, "425261");
+        accepts(UpcE, "0425261");
+        accepts(UpcE, "04252614");
+        accepts(UpcE, "04210000526");
+        accepts(UpcE, "042100005264");
+        rejects(
+            UpcE,
+            "42526",
+            BarCodeError::InvalidLength {
+                system: UpcE,
+                len: 5,
+            },
+        );
+        rejects(UpcE, "1425261", BarCodeError::InvalidNumberSystem);
+        rejects(UpcE, "14252614", BarCodeError::InvalidNumberSystem);
+        let check = BarCodeError::InvalidCheckDigit {
+            expected: 4,
+            found: 5,
+        };
+        rejects(UpcE, "04252615", check.clone());
+        rejects(UpcE, "042100005265", check);
+        rejects(UpcE, "03600029145", BarCodeError::NotCompressible);
+        rejects(UpcE, "036000291452", BarCodeError::NotCompressible);
+    }
+
+    #[test]
+    fn upc_e_compression_reverses_expansion() {
+        for last in 0..10 {
+            let body = [1, 2, 3, 4, 5, last];
+            assert_eq!(upc_e_compress(&upc_e_expand(&body)), Some(body.to_vec()));
+        }
+        assert_eq!(upc_e_compress(&[1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 5]), None);
+    }
+
+    #[test]
+    fn ean() {
+        accepts(Ean13, "400638133393");
+        accepts(Ean13, "4006381333931");
+        accepts(Ean8, "9638507");
+        accepts(Ean8, "96385074");
+        let len = BarCodeError::InvalidLength {
+            system: Ean13,
+            len: 11,
+        };
+        rejects(Ean13, "40063813339", len);
+        let check = BarCodeError::InvalidCheckDigit {
+            expected: 1,
+            found: 2,
+        };
+        rejects(Ean13, "4006381333932", check);
+        let check = BarCodeError::InvalidCheckDigit {
+            expected: 4,
+            found: 5,
+        };
+        rejects(Ean8, "96385075", check);
+        let c = BarCodeError::InvalidChar {
+            system: Ean8,
+            pos: 0,
+            c: '-',
+        };
+        rejects(Ean8, "-638507", c);
+    }
+
+    #[test]
+    fn code39() {
+        accepts(Code39, "ABC-123");
+        accepts(Code39, "*ABC 1.5$*");
+        let c = BarCodeError::InvalidChar {
+            system: Code39,
+            pos: 0,
+            c: 'a',
+        };
+        rejects(Code39, "abc", c);
+        rejects(Code39, "*ABC", BarCodeError::InvalidStartStop(Code39));
+        rejects(Code39, "A*B", BarCodeError::InvalidStartStop(Code39));
+        rejects(Code39, "**A**", BarCodeError::InvalidStartStop(Code39));
+    }
+
+    #[test]
+    fn itf() {
+        accepts(Itf, "1234");
+        rejects(
+            Itf,
+            "123",
+            BarCodeError::InvalidLength {
+                system: Itf,
+                len: 3,
+            },
+        );
+        let c = BarCodeError::InvalidChar {
+            system: Itf,
+            pos: 2,
+            c: 'a',
+        };
+        rejects(Itf, "12a4", c);
+    }
+
+    #[test]
+    fn codabar() {
+        accepts(Codabar, "A1234B");
+        accepts(Codabar, "c$+-./:d");
+        rejects(
+            Codabar,
+            "A",
+            BarCodeError::InvalidLength {
+                system: Codabar,
+                len: 1,
+            },
+        );
+        rejects(Codabar, "1234", BarCodeError::InvalidStartStop(Codabar));
+        rejects(Codabar, "A12B34B", BarCodeError::InvalidStartStop(Codabar));
+    }
+
+    #[test]
+    fn code93() {
+        accepts(Code93, "Hello, World!");
+        let c = BarCodeError::InvalidChar {
+            system: Code93,
+            pos: 1,
+            c: 'é',
+        };
+        rejects(Code93, "Té", c);
+    }
+
+    #[test]
+    fn code128() {
+        accepts(Code128, "{BHello");
+        accepts(Code128, "{A\x01ABC{Sa");
+        accepts(Code128, "{C\x0c\x22{BNo{{1{1");
+        accepts(Code128, "{B\x7f");
+        let escape = |pos| BarCodeError::InvalidCode128Escape { pos };
+        rejects(Code128, "Hello", escape(0));
+        rejects(Code128, "{DHello", escape(0));
+        rejects(Code128, "{B{X", escape(2));
+        rejects(Code128, "{A{{", escape(2));
+        rejects(Code128, "{C{S\x01", escape(2));
+        rejects(Code128, "{Bab{S", escape(4));
+        let c = |pos, c| BarCodeError::InvalidChar {
+            system: Code128,
+            pos,
+            c,
+        };
+        rejects(Code128, "{BHe\x01llo", c(4, '\x01'));
+        rejects(Code128, "{B\x7f{S\x7f", c(5, '\x7f'));
+        rejects(Code128, "{Aa", c(2, 'a'));
+        rejects(Code128, "{C\x64", c(2, 'd'));
+    }
+
+    #[test]
+    fn code128_from_text() {
+        let code = BarCode::code128("a{b").unwrap();
+        assert_eq!(code.data(), b"{Ba{{b");
+        assert!(BarCode::code128("tab\there").is_err());
+    }
+}
@@ -1,36 +1,77 @@
 use bitflags::bitflags;
+use bytes::BufMut;
 
-use std::fmt;
+use std::{borrow::Cow, convert::TryFrom};
 
-use super::chars;
+use super::{chars, DecodedCmd};
 
+/// Serialization to the raw bytes sent to the printer.
+pub trait Encode {
+    /// Append the encoded bytes to `buf`.
+    fn encode_to<B: BufMut>(&self, buf: &mut B);
+
+    fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.encode_to(&mut bytes);
+        bytes
+    }
+}
+
+/// A single command the printer understands.
+///
+/// Text is borrowed where possible, use [`EscPosCmd::into_owned`] to
+/// keep a command around. Every command can be converted back from the
+/// [`DecodedCmd`] its encoding is parsed into.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum EscPosCmd<'s> {
     InitializePrinter,
     PrintAndLineFeed,
     SelectPrintMode(PrintMode),
+    /// Width and height multipliers, 1 to 8.
+    SelectCharacterSize(u8, u8),
+    SelectDefaultLineSpacing,
+    SetLineSpacing(u8),
+    SetRightSideSpacing(u8),
+    /// Left margin in dots, takes effect at the beginning of a line.
+    SetLeftMargin(u16),
+    /// Width of the printable area right of the left margin in dots,
+    /// takes effect at the beginning of a line.
+    SetPrintAreaWidth(u16),
+    SelectUpsideDown(bool),
+    SelectRotation(bool),
     SelectUnderlineMode(UnderlineMode),
     SelectEmphasized(bool),
     SelectDoubleStrike(bool),
     SelectFont(Font),
     SelectJustification(Justification),
-    SelectPaperSensorMode(PaperSensorMode),
+    SelectPaperSensorsOutput(PaperSensors),
+    SelectPaperSensorsStop(PaperSensors),
+    EnablePanelButtons(bool),
     PrintAndFeedLines(u8),
     PrintAndReverseFeedLines(u8),
-    GeneratePulse(bool),
+    /// Print the buffer and feed the paper by the given motion units.
+    PrintAndFeedDots(u8),
     SelectPrintColor(bool),
     SelectCharCodeTable(CharCodeTable),
     SelectReversePrinting(bool),
     CutPaper(CutMode),
+    /// Feed to the cutting position plus the given motion units, then cut.
+    FeedAndCutPaper(CutMode, u8),
     SelectBarCodeHeight(u8),
-    PrintBarCode(u8),
-    Text(&'s str),
+    /// Printable characters, already encoded for the active code table.
+    Text(Cow<'s, [u8]>),
 }
 
+/// How to cut the paper.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CutMode {
     Partial,
     Full,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CharCodeTable {
     // USA: Standard Europe
     PC437,
@@ -61,29 +102,40 @@ pub enum CharCodeTable {
     UserDefined2,
 }
 
-pub struct PaperSensorMode {
-    _todo: u8,
-}
-
+/// Horizontal alignment of text.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub enum Justification {
+    #[default]
     Left,
     Center,
     Right,
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub enum Font {
+    #[default]
     A,
     B,
-    C, // TODO: Does this work with tm88iii?
+    /// Not available on all printers, see [`PrinterProfile::supports_font`](crate::PrinterProfile::supports_font).
+    C,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub enum UnderlineMode {
+    #[default]
     Off,
     OneDot,
     TwoDot,
 }
 
+bitflags! {
+    /// Roll paper sensors, see `ESC c 3` and `ESC c 4`.
+    pub struct PaperSensors: u8 {
+        const NEAR_END = 0b0000_0011;
+        const END = 0b0000_1100;
+    }
+}
+
 bitflags! {
     pub struct PrintMode: u8 {
         const FONT_B = 0b0000_0001;
@@ -100,113 +152,432 @@ impl Default for PrintMode {
     }
 }
 
-impl Default for UnderlineMode {
-    fn default() -> Self {
-        UnderlineMode::Off
+impl CharCodeTable {
+    /// Look up the table selected by `ESC t n`.
+    pub fn from_code(code: u8) -> Option<Self> {
+        use CharCodeTable::*;
+        [
+            PC437,
+            Katakana,
+            PC850,
+            PC860,
+            PC863,
+            PC865,
+            WPC1252,
+            PC866,
+            PC852,
+            PC858,
+            ThaiCharCode42,
+            ThaiCharCode11,
+            ThaiCharCode13,
+            ThaiCharCode14,
+            ThaiCharCode16,
+            ThaiCharCode17,
+            ThaiCharCode18,
+            UserDefined1,
+            UserDefined2,
+        ]
+        .iter()
+        .copied()
+        .find(|table| table.code() == code)
+    }
+
+    /// Parameter `n` of `ESC t`.
+    pub fn code(self) -> u8 {
+        match self {
+            CharCodeTable::PC437 => 0,
+            CharCodeTable::Katakana => 1,
+            CharCodeTable::PC850 => 2,
+            CharCodeTable::PC860 => 3,
+            CharCodeTable::PC863 => 4,
+            CharCodeTable::PC865 => 5,
+            CharCodeTable::WPC1252 => 16,
+            CharCodeTable::PC866 => 17,
+            CharCodeTable::PC852 => 18,
+            CharCodeTable::PC858 => 19,
+            CharCodeTable::ThaiCharCode42 => 20,
+            CharCodeTable::ThaiCharCode11 => 21,
+            CharCodeTable::ThaiCharCode13 => 22,
+            CharCodeTable::ThaiCharCode14 => 23,
+            CharCodeTable::ThaiCharCode16 => 24,
+            CharCodeTable::ThaiCharCode17 => 25,
+            CharCodeTable::ThaiCharCode18 => 26,
+            CharCodeTable::UserDefined1 => 254,
+            CharCodeTable::UserDefined2 => 255,
+        }
+    }
+}
+
+impl EscPosCmd<'_> {
+    /// Copy borrowed text, so the command can outlive it.
+    pub fn into_owned(self) -> EscPosCmd<'static> {
+        use EscPosCmd::*;
+        match self {
+            InitializePrinter => InitializePrinter,
+            PrintAndLineFeed => PrintAndLineFeed,
+            SelectPrintMode(mode) => SelectPrintMode(mode),
+            SelectCharacterSize(width, height) => SelectCharacterSize(width, height),
+            SelectDefaultLineSpacing => SelectDefaultLineSpacing,
+            SetLineSpacing(dots) => SetLineSpacing(dots),
+            SetRightSideSpacing(dots) => SetRightSideSpacing(dots),
+            SetLeftMargin(dots) => SetLeftMargin(dots),
+            SetPrintAreaWidth(dots) => SetPrintAreaWidth(dots),
+            SelectUpsideDown(enable) => SelectUpsideDown(enable),
+            SelectRotation(enable) => SelectRotation(enable),
+            SelectUnderlineMode(mode) => SelectUnderlineMode(mode),
+            SelectEmphasized(enable) => SelectEmphasized(enable),
+            SelectDoubleStrike(enable) => SelectDoubleStrike(enable),
+            SelectFont(font) => SelectFont(font),
+            SelectJustification(justification) => SelectJustification(justification),
+            SelectPaperSensorsOutput(sensors) => SelectPaperSensorsOutput(sensors),
+            SelectPaperSensorsStop(sensors) => SelectPaperSensorsStop(sensors),
+            EnablePanelButtons(enable) => EnablePanelButtons(enable),
+            PrintAndFeedLines(lines) => PrintAndFeedLines(lines),
+            PrintAndReverseFeedLines(lines) => PrintAndReverseFeedLines(lines),
+            PrintAndFeedDots(dots) => PrintAndFeedDots(dots),
+            SelectPrintColor(second_color) => SelectPrintColor(second_color),
+            SelectCharCodeTable(table) => SelectCharCodeTable(table),
+            SelectReversePrinting(enable) => SelectReversePrinting(enable),
+            CutPaper(mode) => CutPaper(mode),
+            FeedAndCutPaper(mode, feed) => FeedAndCutPaper(mode, feed),
+            SelectBarCodeHeight(height) => SelectBarCodeHeight(height),
+            Text(text) => Text(Cow::Owned(text.into_owned())),
+        }
     }
 }
 
-impl fmt::Display for EscPosCmd<'_> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        use chars::{ESC, GS, INITIALIZE_PRINTER, LF};
+impl Encode for EscPosCmd<'_> {
+    fn encode_to<B: BufMut>(&self, buf: &mut B) {
+        const ESC: u8 = chars::ESC as u8;
+        const GS: u8 = chars::GS as u8;
+        let flag = |enable: bool| if enable { 1 } else { 0 };
         match self {
-            EscPosCmd::InitializePrinter => {
-                write!(f, "{}{}", ESC, INITIALIZE_PRINTER)
+            EscPosCmd::InitializePrinter => buf.put_slice(&[ESC, chars::INITIALIZE_PRINTER as u8]),
+            EscPosCmd::PrintAndLineFeed => buf.put_u8(chars::LF as u8),
+            EscPosCmd::SelectPrintMode(mode) => buf.put_slice(&[ESC, b'!', mode.bits()]),
+            EscPosCmd::SelectCharacterSize(width, height) => {
+                let width = width.clamp(&1, &8) - 1;
+                let height = height.clamp(&1, &8) - 1;
+                buf.put_slice(&[GS, b'!', (width << 4) | height])
             }
-            EscPosCmd::PrintAndLineFeed => {
-                write!(f, "{}", LF)
+            EscPosCmd::SelectDefaultLineSpacing => buf.put_slice(&[ESC, b'2']),
+            EscPosCmd::SetLineSpacing(dots) => buf.put_slice(&[ESC, b'3', *dots]),
+            EscPosCmd::SetRightSideSpacing(dots) => buf.put_slice(&[ESC, b' ', *dots]),
+            EscPosCmd::SetLeftMargin(dots) => {
+                buf.put_slice(&[GS, b'L']);
+                buf.put_u16_le(*dots)
             }
-            EscPosCmd::SelectPrintMode(mode) => {
-                write!(f, "{}!{}", ESC, mode.bits() as char)
+            EscPosCmd::SetPrintAreaWidth(dots) => {
+                buf.put_slice(&[GS, b'W']);
+                buf.put_u16_le(*dots)
             }
+            EscPosCmd::SelectUpsideDown(enable) => buf.put_slice(&[ESC, b'{', flag(*enable)]),
+            EscPosCmd::SelectRotation(enable) => buf.put_slice(&[ESC, b'V', flag(*enable)]),
             EscPosCmd::SelectUnderlineMode(mode) => {
                 let param = match mode {
-                    UnderlineMode::Off => '\x00',
-                    UnderlineMode::OneDot => '\x01',
-                    UnderlineMode::TwoDot => '\x02',
+                    UnderlineMode::Off => 0,
+                    UnderlineMode::OneDot => 1,
+                    UnderlineMode::TwoDot => 2,
                 };
-                write!(f, "{}-{}", ESC, param)
-            }
-            EscPosCmd::SelectEmphasized(enable) => {
-                write!(f, "{}E{}", ESC, if *enable { '1' } else { '0' })
-            }
-            EscPosCmd::SelectDoubleStrike(enable) => {
-                write!(f, "{}G{}", ESC, if *enable { '1' } else { '0' })
+                buf.put_slice(&[ESC, b'-', param])
             }
+            EscPosCmd::SelectEmphasized(enable) => buf.put_slice(&[ESC, b'E', flag(*enable)]),
+            EscPosCmd::SelectDoubleStrike(enable) => buf.put_slice(&[ESC, b'G', flag(*enable)]),
             EscPosCmd::SelectFont(font) => {
                 let param = match font {
-                    Font::A => '0',
-                    Font::B => '1',
-                    Font::C => '2',
+                    Font::A => 0,
+                    Font::B => 1,
+                    Font::C => 2,
                 };
-                write!(f, "{}M{}", ESC, param)
+                buf.put_slice(&[ESC, b'M', param])
             }
             EscPosCmd::SelectJustification(justification) => {
                 let param = match justification {
-                    Justification::Left => '0',
-                    Justification::Center => '1',
-                    Justification::Right => '2',
+                    Justification::Left => 0,
+                    Justification::Center => 1,
+                    Justification::Right => 2,
                 };
-                write!(f, "{}a{}", ESC, param)
+                buf.put_slice(&[ESC, b'a', param])
             }
-            EscPosCmd::SelectPaperSensorMode(_mode) => {
-                todo!()
+            EscPosCmd::SelectPaperSensorsOutput(sensors) => {
+                buf.put_slice(&[ESC, b'c', b'3', sensors.bits()])
             }
-            EscPosCmd::PrintAndFeedLines(lines) => {
-                write!(f, "{}d{}", ESC, *lines as char)
+            EscPosCmd::SelectPaperSensorsStop(sensors) => {
+                // The end sensor always stops printing
+                let sensors = *sensors & PaperSensors::NEAR_END;
+                buf.put_slice(&[ESC, b'c', b'4', sensors.bits()])
             }
-            EscPosCmd::PrintAndReverseFeedLines(lines) => {
-                write!(f, "{}e{}", ESC, *lines as char)
-            }
-            EscPosCmd::GeneratePulse(_) => {
-                todo!()
+            EscPosCmd::EnablePanelButtons(enable) => {
+                buf.put_slice(&[ESC, b'c', b'5', flag(!*enable)])
             }
+            EscPosCmd::PrintAndFeedLines(lines) => buf.put_slice(&[ESC, b'd', *lines]),
+            EscPosCmd::PrintAndReverseFeedLines(lines) => buf.put_slice(&[ESC, b'e', *lines]),
+            EscPosCmd::PrintAndFeedDots(dots) => buf.put_slice(&[ESC, b'J', *dots]),
             EscPosCmd::SelectPrintColor(second_color) => {
-                write!(f, "{}r{}", ESC, if *second_color { '1' } else { '0' })
-            }
-            EscPosCmd::SelectCharCodeTable(table) => {
-                let code = match table {
-                    CharCodeTable::PC437 => 0_u8,
-                    CharCodeTable::Katakana => 1,
-                    CharCodeTable::PC850 => 2,
-                    CharCodeTable::PC860 => 3,
-                    CharCodeTable::PC863 => 4,
-                    CharCodeTable::PC865 => 5,
-                    CharCodeTable::WPC1252 => 16,
-                    CharCodeTable::PC866 => 17,
-                    CharCodeTable::PC852 => 18,
-                    CharCodeTable::PC858 => 19,
-                    CharCodeTable::ThaiCharCode42 => 20,
-                    CharCodeTable::ThaiCharCode11 => 21,
-                    CharCodeTable::ThaiCharCode13 => 22,
-                    CharCodeTable::ThaiCharCode14 => 23,
-                    CharCodeTable::ThaiCharCode16 => 24,
-                    CharCodeTable::ThaiCharCode17 => 25,
-                    CharCodeTable::ThaiCharCode18 => 26,
-                    CharCodeTable::UserDefined1 => 254,
-                    CharCodeTable::UserDefined2 => 255,
-                };
-                write!(f, "{}t{}", ESC, code as char)
-            }
-            EscPosCmd::SelectReversePrinting(enable) => {
-                write!(f, "{}B{}", GS, if *enable { '1' } else { '0' })
+                buf.put_slice(&[ESC, b'r', flag(*second_color)])
             }
+            EscPosCmd::SelectCharCodeTable(table) => buf.put_slice(&[ESC, b't', table.code()]),
+            EscPosCmd::SelectReversePrinting(enable) => buf.put_slice(&[GS, b'B', flag(*enable)]),
             EscPosCmd::CutPaper(mode) => {
                 let param = match mode {
-                    CutMode::Full => '0',
-                    CutMode::Partial => '1',
+                    CutMode::Full => 0,
+                    CutMode::Partial => 1,
                 };
-                write!(f, "{}V{}", GS, param)
+                buf.put_slice(&[GS, b'V', param])
             }
-            EscPosCmd::SelectBarCodeHeight(height) => {
-                write!(f, "{}h{}", GS, height)
+            EscPosCmd::FeedAndCutPaper(mode, feed) => {
+                let param = match mode {
+                    CutMode::Full => 65,
+                    CutMode::Partial => 66,
+                };
+                buf.put_slice(&[GS, b'V', param, *feed])
             }
-            EscPosCmd::PrintBarCode(_bar_code) => {
-                todo!()
+            EscPosCmd::SelectBarCodeHeight(height) => buf.put_slice(&[GS, b'h', *height]),
+            EscPosCmd::Text(text) => buf.put_slice(text),
+        }
+    }
+}
+
+impl<'a> TryFrom<DecodedCmd<'a>> for EscPosCmd<'a> {
+    type Error = DecodedCmd<'a>;
+
+    /// Convert a decoded command back, failing for commands that cannot
+    /// be represented or have invalid parameters.
+    fn try_from(cmd: DecodedCmd<'a>) -> Result<Self, Self::Error> {
+        // Parameters accept both 0, 1, 2 and '0', '1', '2'
+        let digit = |n: u8| match n {
+            0..=2 => Some(n),
+            b'0'..=b'2' => Some(n - b'0'),
+            _ => None,
+        };
+        let converted = match cmd {
+            DecodedCmd::InitializePrinter => Some(EscPosCmd::InitializePrinter),
+            DecodedCmd::PrintAndLineFeed => Some(EscPosCmd::PrintAndLineFeed),
+            DecodedCmd::SelectPrintMode(n) => {
+                PrintMode::from_bits(n).map(EscPosCmd::SelectPrintMode)
+            }
+            DecodedCmd::SelectCharSize(n) if n & 0x88 == 0 => {
+                Some(EscPosCmd::SelectCharacterSize((n >> 4) + 1, (n & 0x07) + 1))
+            }
+            DecodedCmd::DefaultLineSpacing => Some(EscPosCmd::SelectDefaultLineSpacing),
+            DecodedCmd::LineSpacing(dots) => Some(EscPosCmd::SetLineSpacing(dots)),
+            DecodedCmd::RightSideSpacing(dots) => Some(EscPosCmd::SetRightSideSpacing(dots)),
+            DecodedCmd::SetLeftMargin(dots) => Some(EscPosCmd::SetLeftMargin(dots)),
+            DecodedCmd::SetPrintAreaWidth(dots) => Some(EscPosCmd::SetPrintAreaWidth(dots)),
+            DecodedCmd::SelectUpsideDown(enable) => Some(EscPosCmd::SelectUpsideDown(enable)),
+            DecodedCmd::SelectRotation(n @ (0 | 1 | b'0' | b'1')) => {
+                Some(EscPosCmd::SelectRotation(n & 1 == 1))
+            }
+            DecodedCmd::SelectUnderlineMode(n) => digit(n).map(|n| {
+                EscPosCmd::SelectUnderlineMode(match n {
+                    0 => UnderlineMode::Off,
+                    1 => UnderlineMode::OneDot,
+                    _ => UnderlineMode::TwoDot,
+                })
+            }),
+            DecodedCmd::SelectEmphasized(enable) => Some(EscPosCmd::SelectEmphasized(enable)),
+            DecodedCmd::SelectDoubleStrike(enable) => Some(EscPosCmd::SelectDoubleStrike(enable)),
+            DecodedCmd::SelectFont(n) => digit(n).map(|n| {
+                EscPosCmd::SelectFont(match n {
+                    0 => Font::A,
+                    1 => Font::B,
+                    _ => Font::C,
+                })
+            }),
+            DecodedCmd::SelectJustification(n) => digit(n).map(|n| {
+                EscPosCmd::SelectJustification(match n {
+                    0 => Justification::Left,
+                    1 => Justification::Center,
+                    _ => Justification::Right,
+                })
+            }),
+            DecodedCmd::SelectPaperSensorsOutput(n) => {
+                PaperSensors::from_bits(n).map(EscPosCmd::SelectPaperSensorsOutput)
+            }
+            DecodedCmd::SelectPaperSensorsStop(n) => {
+                PaperSensors::from_bits(n).map(EscPosCmd::SelectPaperSensorsStop)
+            }
+            DecodedCmd::EnablePanelButtons(enable) => Some(EscPosCmd::EnablePanelButtons(enable)),
+            DecodedCmd::PrintAndFeedLines(lines) => Some(EscPosCmd::PrintAndFeedLines(lines)),
+            DecodedCmd::PrintAndReverseFeedLines(lines) => {
+                Some(EscPosCmd::PrintAndReverseFeedLines(lines))
+            }
+            DecodedCmd::PrintAndFeedDots(dots) => Some(EscPosCmd::PrintAndFeedDots(dots)),
+            DecodedCmd::SelectPrintColor(n @ (0 | 1 | b'0' | b'1')) => {
+                Some(EscPosCmd::SelectPrintColor(n & 1 == 1))
             }
-            EscPosCmd::Text(text) => {
-                write!(f, "{}", text)
+            DecodedCmd::SelectCharCodeTable(code) => {
+                CharCodeTable::from_code(code).map(EscPosCmd::SelectCharCodeTable)
+            }
+            DecodedCmd::SelectReversePrinting(enable) => {
+                Some(EscPosCmd::SelectReversePrinting(enable))
+            }
+            DecodedCmd::CutPaper { mode, feed: None } => match mode {
+                0 | b'0' => Some(EscPosCmd::CutPaper(CutMode::Full)),
+                1 | b'1' => Some(EscPosCmd::CutPaper(CutMode::Partial)),
+                _ => None,
+            },
+            DecodedCmd::CutPaper {
+                mode,
+                feed: Some(feed),
+            } => match mode {
+                65 => Some(EscPosCmd::FeedAndCutPaper(CutMode::Full, feed)),
+                66 => Some(EscPosCmd::FeedAndCutPaper(CutMode::Partial, feed)),
+                _ => None,
+            },
+            DecodedCmd::SelectBarCodeHeight(height) => Some(EscPosCmd::SelectBarCodeHeight(height)),
+            DecodedCmd::Text(text) => Some(EscPosCmd::Text(Cow::Borrowed(text))),
+            _ => None,
+        };
+        converted.ok_or(cmd)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use std::{borrow::Cow, convert::TryFrom};
+
+    use super::*;
+    use crate::decode;
+
+    fn cut_mode() -> impl Strategy<Value = CutMode> {
+        prop_oneof![Just(CutMode::Full), Just(CutMode::Partial)]
+    }
+
+    fn code_table() -> impl Strategy<Value = CharCodeTable> {
+        (0..=255u8).prop_filter_map("unknown table", CharCodeTable::from_code)
+    }
+
+    /// Printable bytes, which the decoder keeps in a single text command.
+    fn text() -> impl Strategy<Value = Vec<u8>> {
+        prop::collection::vec(prop_oneof![0x20..=0x7eu8, 0x80..=0xffu8], 1..64)
+    }
+
+    fn cmd() -> impl Strategy<Value = EscPosCmd<'static>> {
+        use EscPosCmd::*;
+        prop_oneof![
+            Just(InitializePrinter),
+            Just(PrintAndLineFeed),
+            any::<u8>().prop_map(|n| SelectPrintMode(PrintMode::from_bits_truncate(n))),
+            (1..=8u8, 1..=8u8).prop_map(|(width, height)| SelectCharacterSize(width, height)),
+            Just(SelectDefaultLineSpacing),
+            any::<u8>().prop_map(SetLineSpacing),
+            any::<u8>().prop_map(SetRightSideSpacing),
+            any::<u16>().prop_map(SetLeftMargin),
+            any::<u16>().prop_map(SetPrintAreaWidth),
+            any::<bool>().prop_map(SelectUpsideDown),
+            any::<bool>().prop_map(SelectRotation),
+            prop_oneof![
+                Just(UnderlineMode::Off),
+                Just(UnderlineMode::OneDot),
+                Just(UnderlineMode::TwoDot)
+            ]
+            .prop_map(SelectUnderlineMode),
+            any::<bool>().prop_map(SelectEmphasized),
+            any::<bool>().prop_map(SelectDoubleStrike),
+            prop_oneof![Just(Font::A), Just(Font::B), Just(Font::C)].prop_map(SelectFont),
+            prop_oneof![
+                Just(Justification::Left),
+                Just(Justification::Center),
+                Just(Justification::Right)
+            ]
+            .prop_map(SelectJustification),
+            any::<u8>().prop_map(|n| SelectPaperSensorsOutput(PaperSensors::from_bits_truncate(n))),
+            any::<bool>().prop_map(|near_end| SelectPaperSensorsStop(if near_end {
+                PaperSensors::NEAR_END
+            } else {
+                PaperSensors::empty()
+            })),
+            any::<bool>().prop_map(EnablePanelButtons),
+            any::<u8>().prop_map(PrintAndFeedLines),
+            any::<u8>().prop_map(PrintAndReverseFeedLines),
+            any::<u8>().prop_map(PrintAndFeedDots),
+            any::<bool>().prop_map(SelectPrintColor),
+            code_table().prop_map(SelectCharCodeTable),
+            any::<bool>().prop_map(SelectReversePrinting),
+            cut_mode().prop_map(CutPaper),
+            (cut_mode(), any::<u8>()).prop_map(|(mode, feed)| FeedAndCutPaper(mode, feed)),
+            any::<u8>().prop_map(SelectBarCodeHeight),
+            text().prop_map(|text| Text(Cow::Owned(text))),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn commands_round_trip(cmd in cmd()) {
+            let bytes = cmd.encode();
+            let decoded: Vec<_> = decode(&bytes).collect();
+            prop_assert_eq!(decoded.len(), 1, "{:?} decoded to {:?}", cmd, decoded);
+            prop_assert_eq!(EscPosCmd::try_from(decoded[0]), Ok(cmd));
+        }
+
+        #[test]
+        fn commands_round_trip_in_sequence(cmds in prop::collection::vec(cmd(), 1..16)) {
+            let mut bytes = Vec::new();
+            for cmd in &cmds {
+                cmd.encode_to(&mut bytes);
             }
+            let decoded: Vec<_> = decode(&bytes)
+                .map(|cmd| EscPosCmd::try_from(cmd).map(EscPosCmd::into_owned))
+                .collect::<Result<_, _>>()
+                .expect("all commands decode");
+            // Adjacent text is decoded as one command
+            let merge = |cmds: Vec<EscPosCmd<'static>>| {
+                let mut merged: Vec<EscPosCmd<'static>> = Vec::new();
+                for cmd in cmds {
+                    match (merged.last_mut(), cmd) {
+                        (Some(EscPosCmd::Text(last)), EscPosCmd::Text(text)) => {
+                            last.to_mut().extend_from_slice(&text)
+                        }
+                        (_, cmd) => merged.push(cmd),
+                    }
+                }
+                merged
+            };
+            prop_assert_eq!(decoded, merge(cmds));
         }
     }
+
+    #[test]
+    fn parameters_above_0x7f_are_sent_as_is() {
+        assert_eq!(EscPosCmd::SetLineSpacing(200).encode(), [0x1b, b'3', 200]);
+        assert_eq!(
+            EscPosCmd::SetRightSideSpacing(255).encode(),
+            [0x1b, b' ', 255]
+        );
+        assert_eq!(
+            EscPosCmd::PrintAndFeedLines(0x80).encode(),
+            [0x1b, b'd', 0x80]
+        );
+        assert_eq!(
+            EscPosCmd::SelectCharCodeTable(CharCodeTable::UserDefined2).encode(),
+            [0x1b, b't', 255]
+        );
+        assert_eq!(
+            EscPosCmd::SelectPrintMode(PrintMode::UNDERLINE).encode(),
+            [0x1b, b'!', 0x80]
+        );
+    }
+
+    #[test]
+    fn margins_are_little_endian() {
+        assert_eq!(
+            EscPosCmd::SetLeftMargin(0x1234).encode(),
+            [0x1d, b'L', 0x34, 0x12]
+        );
+        assert_eq!(
+            EscPosCmd::SetPrintAreaWidth(512).encode(),
+            [0x1d, b'W', 0, 2]
+        );
+    }
+
+    #[test]
+    fn bar_code_height_is_a_single_byte() {
+        assert_eq!(
+            EscPosCmd::SelectBarCodeHeight(162).encode(),
+            [0x1d, b'h', 162]
+        );
+    }
 }
@@ -0,0 +1,113 @@
+use std::{io, str::FromStr, time::Duration};
+
+#[cfg(feature = "preview")]
+use super::PreviewTransport;
+use super::{BufferedTransport, Error, SerialTransport, Transport};
+#[cfg(feature = "toml-profiles")]
+use super::{PrinterProfile, ProfileError};
+
+/// Timeout of serial reads and writes.
+const SERIAL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Flow control of the serial connection.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum FlowControl {
+    #[default]
+    None,
+    /// RTS/CTS
+    Hardware,
+    /// XON/XOFF
+    Software,
+}
+
+/// How to reach the printer, e.g. as part of a settings file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PrinterConfig {
+    /// Serial port of the printer.
+    pub path: String,
+    pub baud_rate: u32,
+    /// Render receipts as PNG files into this directory instead of printing.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub preview: Option<String>,
+    /// Name of a built-in printer profile or path to a TOML profile.
+    /// Defaults to the TM-T88III.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub profile: Option<String>,
+    /// Flow control of the serial port, the printer has to use the same.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub flow_control: FlowControl,
+}
+
+impl PrinterConfig {
+    /// Print to the serial port `path` without flow control.
+    pub fn new(path: &str, baud_rate: u32) -> Self {
+        PrinterConfig {
+            path: path.to_string(),
+            baud_rate,
+            preview: None,
+            profile: None,
+            flow_control: FlowControl::None,
+        }
+    }
+
+    /// Load the configured profile, the TM-T88III if there is none.
+    #[cfg(feature = "toml-profiles")]
+    pub fn load_profile(&self) -> Result<PrinterProfile, ProfileError> {
+        match self.profile {
+            Some(ref name) => PrinterProfile::find(name),
+            None => Ok(PrinterProfile::tm_t88iii()),
+        }
+    }
+
+    /// Open the serial port.
+    ///
+    /// Renders to PNG files instead, if a preview directory is configured.
+    /// Fails with [`io::ErrorKind::Unsupported`] in that case, unless the
+    /// `preview` feature is enabled.
+    pub fn open_transport(&self) -> io::Result<Box<dyn Transport + Send>> {
+        if let Some(ref dir) = self.preview {
+            #[cfg(feature = "preview")]
+            return Ok(Box::new(PreviewTransport::new(dir)?));
+            #[cfg(not(feature = "preview"))]
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("cannot preview to {:?} without the preview feature", dir),
+            ));
+        }
+        let port = serialport::new(&self.path, self.baud_rate)
+            .timeout(SERIAL_TIMEOUT)
+            .flow_control(self.flow_control.into())
+            .open_native()?;
+        Ok(Box::new(BufferedTransport::new(SerialTransport::new(port))))
+    }
+}
+
+impl FromStr for FlowControl {
+    type Err = Error;
+
+    /// Parse `"none"`, `"hardware"` or `"software"`.
+    fn from_str(flow_control: &str) -> Result<Self, Self::Err> {
+        match flow_control {
+            "none" => Ok(FlowControl::None),
+            "hardware" => Ok(FlowControl::Hardware),
+            "software" => Ok(FlowControl::Software),
+            _ => Err(Error::InvalidInput(format!(
+                "unknown flow control {:?}, expected none, hardware or software",
+                flow_control
+            ))),
+        }
+    }
+}
+
+impl From<FlowControl> for serialport::FlowControl {
+    fn from(flow_control: FlowControl) -> Self {
+        match flow_control {
+            FlowControl::None => serialport::FlowControl::None,
+            FlowControl::Hardware => serialport::FlowControl::Hardware,
+            FlowControl::Software => serialport::FlowControl::Software,
+        }
+    }
+}
@@ -0,0 +1,878 @@
+use std::{
+    convert::TryFrom,
+    fmt::{self, Write as _},
+};
+
+use super::{
+    chars,
+    cmds::{CharCodeTable, PrintMode},
+    encoding,
+};
+
+/// Command found in an ESC/POS byte stream.
+///
+/// Parameters are kept as raw bytes, so every command can be
+/// represented, even if `escpos-lib` cannot emit it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodedCmd<'a> {
+    /// Printable characters, encoded in the active code table.
+    Text(&'a [u8]),
+    HorizontalTab,
+    PrintAndLineFeed,
+    FormFeed,
+    CarriageReturn,
+    Cancel,
+    /// `DLE EOT n`
+    RealTimeStatus(u8),
+    /// `DLE ENQ n`
+    RealTimeRequest(u8),
+    /// `DLE DC4 fn a b`
+    RealTimeCommand {
+        function: u8,
+        params: [u8; 2],
+    },
+    /// `ESC SP n`
+    RightSideSpacing(u8),
+    /// `ESC ! n`
+    SelectPrintMode(u8),
+    /// `ESC $ nL nH`
+    AbsolutePosition(u16),
+    /// `ESC %`
+    SelectUserDefinedChars(bool),
+    /// `ESC & y c1 c2 ...`
+    DefineUserChars {
+        height: u8,
+        first: u8,
+        last: u8,
+        data: &'a [u8],
+    },
+    /// `ESC * m nL nH d...`
+    BitImage {
+        mode: u8,
+        width: u16,
+        data: &'a [u8],
+    },
+    /// `ESC - n`
+    SelectUnderlineMode(u8),
+    /// `ESC 2`
+    DefaultLineSpacing,
+    /// `ESC 3 n`
+    LineSpacing(u8),
+    /// `ESC ? n`
+    CancelUserChar(u8),
+    InitializePrinter,
+    /// `ESC E n`
+    SelectEmphasized(bool),
+    /// `ESC G n`
+    SelectDoubleStrike(bool),
+    /// `ESC J n`
+    PrintAndFeedDots(u8),
+    /// `ESC L`
+    SelectPageMode,
+    /// `ESC M n`
+    SelectFont(u8),
+    /// `ESC R n`
+    SelectInternationalCharSet(u8),
+    /// `ESC S`
+    SelectStandardMode,
+    /// `ESC T n`
+    SelectPrintDirection(u8),
+    /// `ESC V n`
+    SelectRotation(u8),
+    /// `ESC W xL xH yL yH dxL dxH dyL dyH`
+    SetPrintArea([u16; 4]),
+    /// `ESC \ nL nH`
+    RelativePosition(i16),
+    /// `ESC a n`
+    SelectJustification(u8),
+    /// `ESC c 3 n`
+    SelectPaperSensorsOutput(u8),
+    /// `ESC c 4 n`
+    SelectPaperSensorsStop(u8),
+    /// `ESC c 5 n`
+    EnablePanelButtons(bool),
+    /// `ESC d n`
+    PrintAndFeedLines(u8),
+    /// `ESC e n`
+    PrintAndReverseFeedLines(u8),
+    /// `ESC p m t1 t2`
+    GeneratePulse {
+        pin: u8,
+        on: u8,
+        off: u8,
+    },
+    /// `ESC r n`
+    SelectPrintColor(u8),
+    /// `ESC t n`
+    SelectCharCodeTable(u8),
+    /// `ESC {`
+    SelectUpsideDown(bool),
+    /// `ESC FF`
+    PrintPage,
+    /// `FS p n m`
+    PrintNvBitImage {
+        number: u8,
+        mode: u8,
+    },
+    /// `FS q n [xL xH yL yH d...]...`
+    DefineNvBitImages {
+        count: u8,
+        data: &'a [u8],
+    },
+    /// `GS ! n`
+    SelectCharSize(u8),
+    /// `GS $ nL nH`
+    AbsoluteVerticalPosition(u16),
+    /// `GS ( fn pL pH d...`, i.e. 2D symbols or graphics. `GS 8 L` with
+    /// its four byte length is decoded as `GS ( L`.
+    Extended {
+        function: u8,
+        data: &'a [u8],
+    },
+    /// `GS B n`
+    SelectReversePrinting(bool),
+    /// `GS H n`
+    SelectHriPosition(u8),
+    /// `GS L nL nH`
+    SetLeftMargin(u16),
+    /// `GS V m [n]`
+    CutPaper {
+        mode: u8,
+        feed: Option<u8>,
+    },
+    /// `GS W nL nH`
+    SetPrintAreaWidth(u16),
+    /// `GS a n`
+    EnableAsb(u8),
+    /// `GS f n`
+    SelectHriFont(u8),
+    /// `GS h n`
+    SelectBarCodeHeight(u8),
+    /// `GS k m ...`
+    PrintBarCode {
+        system: u8,
+        data: &'a [u8],
+    },
+    /// `GS r n`
+    TransmitStatus(u8),
+    /// `GS v 0 m xL xH yL yH d...`
+    RasterImage {
+        mode: u8,
+        width_bytes: u16,
+        height: u16,
+        data: &'a [u8],
+    },
+    /// `GS w n`
+    SelectBarCodeWidth(u8),
+    /// Unknown command, the bytes include the prefix.
+    Unknown(&'a [u8]),
+    /// The stream ended in the middle of a command.
+    Incomplete(&'a [u8]),
+    /// The command declares more data than can be addressed, the bytes
+    /// are the rest of the stream.
+    Invalid(&'a [u8]),
+}
+
+/// Iterator over the commands in an ESC/POS byte stream.
+#[derive(Debug, Clone)]
+pub struct Decoder<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+/// Parse an ESC/POS byte stream into commands.
+pub fn decode(bytes: &[u8]) -> Decoder<'_> {
+    Decoder { bytes, pos: 0 }
+}
+
+/// Create a human readable listing of an ESC/POS byte stream.
+///
+/// Every line contains the offset, the raw bytes and the decoded command.
+pub fn dump(bytes: &[u8]) -> String {
+    let mut decoder = decode(bytes);
+    let mut table = CharCodeTable::PC437;
+    let mut out = String::new();
+    loop {
+        let start = decoder.position();
+        let cmd = match decoder.next() {
+            Some(cmd) => cmd,
+            None => break,
+        };
+        let raw = &bytes[start..decoder.position()];
+        let mut hex = raw
+            .iter()
+            .take(8)
+            .map(|b| format!("{:02x}", b))
+            .collect::<Vec<_>>()
+            .join(" ");
+        if raw.len() > 8 {
+            hex.push_str(" ..");
+        }
+        match cmd {
+            DecodedCmd::Text(text) => {
+                let text: String = text
+                    .iter()
+                    .map(|&b| encoding::decode_byte(table, b))
+                    .collect();
+                let _ = writeln!(out, "{:06x}  {:<26}  {:?}", start, hex, text);
+            }
+            cmd => {
+                match cmd {
+                    DecodedCmd::InitializePrinter => table = CharCodeTable::PC437,
+                    DecodedCmd::SelectCharCodeTable(code) => {
+                        table = CharCodeTable::from_code(code).unwrap_or(table)
+                    }
+                    _ => {}
+                }
+                let _ = writeln!(out, "{:06x}  {:<26}  {}", start, hex, cmd);
+            }
+        }
+    }
+    out
+}
+
+impl<'a> Decoder<'a> {
+    /// Offset of the next command in the stream.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// The bytes not yet decoded.
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.bytes[self.pos..]
+    }
+
+    /// Get `len` bytes after `skip` bytes of prefix, if available.
+    fn params(&self, skip: usize, len: usize) -> Option<&'a [u8]> {
+        let start = self.pos.checked_add(skip)?;
+        self.bytes.get(start..start.checked_add(len)?)
+    }
+
+    /// Parse the command starting at the current position and return
+    /// it together with its length, `None` if the stream is truncated.
+    fn parse(&self) -> Option<(DecodedCmd<'a>, usize)> {
+        use DecodedCmd::*;
+        let rest = self.remaining();
+        let p = |skip, len| self.params(skip, len);
+        let u16_at = |bytes: &[u8], idx: usize| bytes[idx] as u16 | (bytes[idx + 1] as u16) << 8;
+        let invalid = (Invalid(rest), rest.len());
+        let cmd = match rest[0] {
+            0x09 => (HorizontalTab, 1),
+            0x0a => (PrintAndLineFeed, 1),
+            0x0c => (FormFeed, 1),
+            0x0d => (CarriageReturn, 1),
+            0x18 => (Cancel, 1),
+            0x10 => match *rest.get(1)? {
+                0x04 => (RealTimeStatus(p(2, 1)?[0]), 3),
+                0x05 => (RealTimeRequest(p(2, 1)?[0]), 3),
+                0x14 => {
+                    let params = p(2, 3)?;
+                    let cmd = RealTimeCommand {
+                        function: params[0],
+                        params: [params[1], params[2]],
+                    };
+                    (cmd, 5)
+                }
+                _ => (Unknown(&rest[..1]), 1),
+            },
+            0x1b => {
+                let n = |skip| p(skip, 1).map(|n| n[0]);
+                match *rest.get(1)? {
+                    b' ' => (RightSideSpacing(n(2)?), 3),
+                    b'!' => (SelectPrintMode(n(2)?), 3),
+                    b'$' => (AbsolutePosition(u16_at(p(2, 2)?, 0)), 4),
+                    b'%' => (SelectUserDefinedChars(n(2)? & 1 == 1), 3),
+                    b'&' => {
+                        let head = p(2, 3)?;
+                        let (height, first, last) = (head[0], head[1], head[2]);
+                        let mut len = 5;
+                        for _ in first..=last {
+                            let width = n(len)? as usize;
+                            len += 1 + width * height as usize;
+                        }
+                        p(0, len)?;
+                        let data = &rest[5..len];
+                        let cmd = DefineUserChars {
+                            height,
+                            first,
+                            last,
+                            data,
+                        };
+                        (cmd, len)
+                    }
+                    b'*' => {
+                        let head = p(2, 3)?;
+                        let mode = head[0];
+                        let width = u16_at(head, 1);
+                        let columns = if mode >= 32 { 3 } else { 1 };
+                        let len = width as usize * columns;
+                        let data = p(5, len)?;
+                        (BitImage { mode, width, data }, 5 + len)
+                    }
+                    b'-' => (SelectUnderlineMode(n(2)?), 3),
+                    b'2' => (DefaultLineSpacing, 2),
+                    b'3' => (LineSpacing(n(2)?), 3),
+                    b'?' => (CancelUserChar(n(2)?), 3),
+                    b'@' => (InitializePrinter, 2),
+                    b'E' => (SelectEmphasized(n(2)? & 1 == 1), 3),
+                    b'G' => (SelectDoubleStrike(n(2)? & 1 == 1), 3),
+                    b'J' => (PrintAndFeedDots(n(2)?), 3),
+                    b'L' => (SelectPageMode, 2),
+                    b'M' => (SelectFont(n(2)?), 3),
+                    b'R' => (SelectInternationalCharSet(n(2)?), 3),
+                    b'S' => (SelectStandardMode, 2),
+                    b'T' => (SelectPrintDirection(n(2)?), 3),
+                    b'V' => (SelectRotation(n(2)?), 3),
+                    b'W' => {
+                        let params = p(2, 8)?;
+                        let area = [
+                            u16_at(params, 0),
+                            u16_at(params, 2),
+                            u16_at(params, 4),
+                            u16_at(params, 6),
+                        ];
+                        (SetPrintArea(area), 10)
+                    }
+                    b'\\' => (RelativePosition(u16_at(p(2, 2)?, 0) as i16), 4),
+                    b'a' => (SelectJustification(n(2)?), 3),
+                    b'c' => match *rest.get(2)? {
+                        b'3' => (SelectPaperSensorsOutput(n(3)?), 4),
+                        b'4' => (SelectPaperSensorsStop(n(3)?), 4),
+                        b'5' => (EnablePanelButtons(n(3)? & 1 == 0), 4),
+                        _ => (Unknown(&rest[..3]), 3),
+                    },
+                    b'd' => (PrintAndFeedLines(n(2)?), 3),
+                    b'e' => (PrintAndReverseFeedLines(n(2)?), 3),
+                    b'p' => {
+                        let params = p(2, 3)?;
+                        let cmd = GeneratePulse {
+                            pin: params[0],
+                            on: params[1],
+                            off: params[2],
+                        };
+                        (cmd, 5)
+                    }
+                    b'r' => (SelectPrintColor(n(2)?), 3),
+                    b't' => (SelectCharCodeTable(n(2)?), 3),
+                    b'{' => (SelectUpsideDown(n(2)? & 1 == 1), 3),
+                    0x0c => (PrintPage, 2),
+                    _ => (Unknown(&rest[..2]), 2),
+                }
+            }
+            0x1c => {
+                let n = |skip| p(skip, 1).map(|n| n[0]);
+                match *rest.get(1)? {
+                    b'p' => {
+                        let params = p(2, 2)?;
+                        let cmd = PrintNvBitImage {
+                            number: params[0],
+                            mode: params[1],
+                        };
+                        (cmd, 4)
+                    }
+                    b'q' => {
+                        let count = n(2)?;
+                        let mut len = 3;
+                        for _ in 0..count {
+                            let head = p(len, 4)?;
+                            let next = (u16_at(head, 0) as usize)
+                                .checked_mul(u16_at(head, 2) as usize)
+                                .and_then(|size| size.checked_mul(8))
+                                .and_then(|size| size.checked_add(4))
+                                .and_then(|size| size.checked_add(len));
+                            len = match next {
+                                Some(next) => next,
+                                None => return Some(invalid),
+                            };
+                        }
+                        p(0, len)?;
+                        let data = &rest[3..len];
+                        (DefineNvBitImages { count, data }, len)
+                    }
+                    _ => (Unknown(&rest[..2]), 2),
+                }
+            }
+            0x1d => {
+                let n = |skip| p(skip, 1).map(|n| n[0]);
+                match *rest.get(1)? {
+                    b'!' => (SelectCharSize(n(2)?), 3),
+                    b'$' => (AbsoluteVerticalPosition(u16_at(p(2, 2)?, 0)), 4),
+                    b'(' => {
+                        let head = p(2, 3)?;
+                        let len = u16_at(head, 1) as usize;
+                        let data = p(5, len)?;
+                        let function = head[0];
+                        (Extended { function, data }, 5 + len)
+                    }
+                    b'8' if n(2)? == b'L' => {
+                        let head = p(3, 4)?;
+                        let len = u32::from(u16_at(head, 0)) | u32::from(u16_at(head, 2)) << 16;
+                        let len = match usize::try_from(len).ok().and_then(|len| len.checked_add(7))
+                        {
+                            Some(len) => len,
+                            None => return Some(invalid),
+                        };
+                        let data = p(7, len - 7)?;
+                        (
+                            Extended {
+                                function: b'L',
+                                data,
+                            },
+                            len,
+                        )
+                    }
+                    b'B' => (SelectReversePrinting(n(2)? & 1 == 1), 3),
+                    b'H' => (SelectHriPosition(n(2)?), 3),
+                    b'L' => (SetLeftMargin(u16_at(p(2, 2)?, 0)), 4),
+                    b'V' => match n(2)? {
+                        mode @ (65 | 66 | 97 | 98 | 103 | 104) => {
+                            let feed = Some(n(3)?);
+                            (CutPaper { mode, feed }, 4)
+                        }
+                        mode => (CutPaper { mode, feed: None }, 3),
+                    },
+                    b'W' => (SetPrintAreaWidth(u16_at(p(2, 2)?, 0)), 4),
+                    b'a' => (EnableAsb(n(2)?), 3),
+                    b'f' => (SelectHriFont(n(2)?), 3),
+                    b'h' => (SelectBarCodeHeight(n(2)?), 3),
+                    b'k' => {
+                        let system = n(2)?;
+                        if system <= 6 {
+                            // Function A, data is NUL terminated
+                            let len = rest[3..].iter().position(|&b| b == 0)?;
+                            let data = &rest[3..3 + len];
+                            (PrintBarCode { system, data }, 4 + len)
+                        } else {
+                            let len = n(3)? as usize;
+                            let data = p(4, len)?;
+                            (PrintBarCode { system, data }, 4 + len)
+                        }
+                    }
+                    b'r' => (TransmitStatus(n(2)?), 3),
+                    b'v' if n(2)? == b'0' => {
+                        let head = p(3, 5)?;
+                        let mode = head[0];
+                        let width_bytes = u16_at(head, 1);
+                        let height = u16_at(head, 3);
+                        let len = match (width_bytes as usize)
+                            .checked_mul(height as usize)
+                            .and_then(|len| len.checked_add(8))
+                        {
+                            Some(len) => len,
+                            None => return Some(invalid),
+                        };
+                        let data = p(8, len - 8)?;
+                        let cmd = RasterImage {
+                            mode,
+                            width_bytes,
+                            height,
+                            data,
+                        };
+                        (cmd, len)
+                    }
+                    b'w' => (SelectBarCodeWidth(n(2)?), 3),
+                    _ => (Unknown(&rest[..2]), 2),
+                }
+            }
+            0x00..=0x1f | 0x7f => (Unknown(&rest[..1]), 1),
+            _ => {
+                let len = rest
+                    .iter()
+                    .position(|&b| b < 0x20 || b == 0x7f)
+                    .unwrap_or(rest.len());
+                (Text(&rest[..len]), len)
+            }
+        };
+        Some(cmd)
+    }
+}
+
+impl DecodedCmd<'_> {
+    /// Whether the command only affects the current print job.
+    ///
+    /// False for writes to NV memory, configuration, drawer pulses,
+    /// real-time and status commands, which outlast the job, reach other
+    /// devices or answer to the host. Also false for commands that could
+    /// not be decoded, since their effect is unknown.
+    pub fn is_job_local(&self) -> bool {
+        use DecodedCmd::*;
+        match self {
+            RealTimeStatus(_)
+            | RealTimeRequest(_)
+            | RealTimeCommand { .. }
+            | SelectPaperSensorsOutput(_)
+            | SelectPaperSensorsStop(_)
+            | EnablePanelButtons(_)
+            | GeneratePulse { .. }
+            | DefineNvBitImages { .. }
+            | EnableAsb(_)
+            | TransmitStatus(_)
+            | Unknown(_)
+            | Incomplete(_)
+            | Invalid(_) => false,
+            // 2D symbols
+            Extended { function: b'k', .. } => true,
+            // Printing graphics and download graphics, which are kept in RAM
+            Extended {
+                function: b'L',
+                data,
+            } => matches!(data, [48, 2 | 50 | 69 | 83 | 85 | 112 | 113, ..]),
+            Extended { .. } => false,
+            _ => true,
+        }
+    }
+}
+
+impl<'a> Iterator for Decoder<'a> {
+    type Item = DecodedCmd<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos >= self.bytes.len() {
+            return None;
+        }
+        let (cmd, len) = self.parse().unwrap_or_else(|| {
+            (
+                DecodedCmd::Incomplete(self.remaining()),
+                self.remaining().len(),
+            )
+        });
+        self.pos += len;
+        Some(cmd)
+    }
+}
+
+impl fmt::Display for DecodedCmd<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use DecodedCmd::*;
+        let on_off = |on: &bool| if *on { "on" } else { "off" };
+        match self {
+            Text(text) => write!(f, "Text({:?})", String::from_utf8_lossy(text)),
+            HorizontalTab => write!(f, "HT"),
+            PrintAndLineFeed => write!(f, "LF"),
+            FormFeed => write!(f, "FF"),
+            CarriageReturn => write!(f, "CR"),
+            Cancel => write!(f, "CAN"),
+            RealTimeStatus(n) => write!(f, "DLE EOT {}  real-time status", n),
+            RealTimeRequest(n) => write!(f, "DLE ENQ {}  real-time request", n),
+            RealTimeCommand { function, params } => write!(
+                f,
+                "DLE DC4 {} {} {}  real-time command",
+                function, params[0], params[1]
+            ),
+            RightSideSpacing(n) => write!(f, "ESC SP {}  right-side spacing", n),
+            SelectPrintMode(n) => write!(
+                f,
+                "ESC ! {:#04x}  print mode {:?}",
+                n,
+                PrintMode::from_bits_truncate(*n)
+            ),
+            AbsolutePosition(n) => write!(f, "ESC $ {}  absolute position", n),
+            SelectUserDefinedChars(on) => {
+                write!(f, "ESC %  user-defined characters {}", on_off(on))
+            }
+            DefineUserChars {
+                height,
+                first,
+                last,
+                data,
+            } => write!(
+                f,
+                "ESC & {} {:#04x} {:#04x}  define user characters ({} bytes)",
+                height,
+                first,
+                last,
+                data.len()
+            ),
+            BitImage { mode, width, data } => write!(
+                f,
+                "ESC * {} {}  bit image ({} bytes)",
+                mode,
+                width,
+                data.len()
+            ),
+            SelectUnderlineMode(n) => write!(f, "ESC - {}  underline", n),
+            DefaultLineSpacing => write!(f, "ESC 2  default line spacing"),
+            LineSpacing(n) => write!(f, "ESC 3 {}  line spacing", n),
+            CancelUserChar(n) => write!(f, "ESC ? {:#04x}  cancel user character", n),
+            InitializePrinter => write!(f, "ESC @  initialize printer"),
+            SelectEmphasized(on) => write!(f, "ESC E  emphasized {}", on_off(on)),
+            SelectDoubleStrike(on) => write!(f, "ESC G  double strike {}", on_off(on)),
+            PrintAndFeedDots(n) => write!(f, "ESC J {}  print and feed dots", n),
+            SelectPageMode => write!(f, "ESC L  page mode"),
+            SelectFont(n) => write!(f, "ESC M {}  font", n),
+            SelectInternationalCharSet(n) => write!(f, "ESC R {}  international charset", n),
+            SelectStandardMode => write!(f, "ESC S  standard mode"),
+            SelectPrintDirection(n) => write!(f, "ESC T {}  print direction", n),
+            SelectRotation(n) => write!(f, "ESC V {}  rotation", n),
+            SetPrintArea([x, y, dx, dy]) => {
+                write!(f, "ESC W {} {} {} {}  print area", x, y, dx, dy)
+            }
+            RelativePosition(n) => write!(f, "ESC \\ {}  relative position", n),
+            SelectJustification(n) => write!(f, "ESC a {}  justification", n),
+            SelectPaperSensorsOutput(n) => {
+                write!(f, "ESC c 3 {:#04x}  paper sensors for paper-end signals", n)
+            }
+            SelectPaperSensorsStop(n) => {
+                write!(f, "ESC c 4 {:#04x}  paper sensors to stop printing", n)
+            }
+            EnablePanelButtons(on) => write!(f, "ESC c 5  panel buttons {}", on_off(on)),
+            PrintAndFeedLines(n) => write!(f, "ESC d {}  print and feed lines", n),
+            PrintAndReverseFeedLines(n) => write!(f, "ESC e {}  print and reverse feed", n),
+            GeneratePulse { pin, on, off } => write!(f, "ESC p {} {} {}  pulse", pin, on, off),
+            SelectPrintColor(n) => write!(f, "ESC r {}  print color", n),
+            SelectCharCodeTable(n) => match CharCodeTable::from_code(*n) {
+                Some(table) => write!(f, "ESC t {}  code table {:?}", n, table),
+                None => write!(f, "ESC t {}  code table", n),
+            },
+            SelectUpsideDown(on) => write!(f, "ESC {{  upside down {}", on_off(on)),
+            PrintPage => write!(f, "ESC FF  print page"),
+            PrintNvBitImage { number, mode } => {
+                write!(f, "FS p {} {}  print NV bit image", number, mode)
+            }
+            DefineNvBitImages { count, data } => write!(
+                f,
+                "FS q {}  define NV bit images ({} bytes)",
+                count,
+                data.len()
+            ),
+            SelectCharSize(n) => write!(
+                f,
+                "GS ! {:#04x}  character size {}x{}",
+                n,
+                (n >> 4) + 1,
+                (n & 0x0f) + 1
+            ),
+            AbsoluteVerticalPosition(n) => write!(f, "GS $ {}  absolute vertical position", n),
+            Extended { function, data } => write!(
+                f,
+                "GS ( {}  extended function ({} bytes)",
+                *function as char,
+                data.len()
+            ),
+            SelectReversePrinting(on) => write!(f, "GS B  reverse {}", on_off(on)),
+            SelectHriPosition(n) => write!(f, "GS H {}  HRI position", n),
+            SetLeftMargin(n) => write!(f, "GS L {}  left margin", n),
+            CutPaper { mode, feed } => match feed {
+                Some(feed) => write!(f, "GS V {} {}  cut paper", mode, feed),
+                None => write!(f, "GS V {}  cut paper", mode),
+            },
+            SetPrintAreaWidth(n) => write!(f, "GS W {}  print area width", n),
+            EnableAsb(n) => write!(f, "GS a {:#04x}  automatic status back", n),
+            SelectHriFont(n) => write!(f, "GS f {}  HRI font", n),
+            SelectBarCodeHeight(n) => write!(f, "GS h {}  bar code height", n),
+            PrintBarCode { system, data } => write!(
+                f,
+                "GS k {}  bar code {:?}",
+                system,
+                String::from_utf8_lossy(data)
+            ),
+            TransmitStatus(n) => write!(f, "GS r {}  transmit status", n),
+            RasterImage {
+                mode,
+                width_bytes,
+                height,
+                ..
+            } => write!(
+                f,
+                "GS v 0 {} {} {}  raster image {}x{} dots",
+                mode,
+                width_bytes,
+                height,
+                *width_bytes as usize * 8,
+                height
+            ),
+            SelectBarCodeWidth(n) => write!(f, "GS w {}  bar code width", n),
+            Unknown(bytes) => write!(f, "unknown {}", mnemonic(bytes)),
+            Incomplete(bytes) => write!(f, "incomplete {}", mnemonic(bytes)),
+            Invalid(bytes) => write!(f, "invalid {}", mnemonic(bytes)),
+        }
+    }
+}
+
+/// Name control characters, i.e. `[0x1b, 0x41]` becomes `ESC A`.
+fn mnemonic(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .take(3)
+        .map(|&b| match b as char {
+            chars::ESC => String::from("ESC"),
+            chars::GS => String::from("GS"),
+            chars::DLE => String::from("DLE"),
+            chars::FS => String::from("FS"),
+            c if c.is_ascii_graphic() => c.to_string(),
+            _ => format!("{:#04x}", b),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cmds::Justification, Document, FmtStr, FormattedStr, MemoryTransport, Printer, Table,
+        TextStyle,
+    };
+
+    fn decode_all(bytes: &[u8]) -> Vec<DecodedCmd<'_>> {
+        decode(bytes).collect()
+    }
+
+    #[test]
+    fn nv_bit_images() {
+        // Two images, 1x1 and 2x1 bytes of 8 dots each
+        let mut bytes = vec![0x1c, b'q', 2, 1, 0, 1, 0];
+        bytes.extend_from_slice(&[0xff; 8]);
+        bytes.extend_from_slice(&[2, 0, 1, 0]);
+        bytes.extend_from_slice(&[0xaa; 16]);
+        bytes.extend_from_slice(&[0x1b, b'@']);
+        assert_eq!(
+            decode_all(&bytes),
+            [
+                DecodedCmd::DefineNvBitImages {
+                    count: 2,
+                    data: &bytes[3..35],
+                },
+                DecodedCmd::InitializePrinter,
+            ]
+        );
+    }
+
+    #[test]
+    fn graphics_with_long_length() {
+        let bytes = [
+            0x1d, b'8', b'L', 2, 0, 0, 0, 48, 50, 0x1d, b'(', b'L', 2, 0, 48, 50,
+        ];
+        let graphics = DecodedCmd::Extended {
+            function: b'L',
+            data: &[48, 50],
+        };
+        assert_eq!(decode_all(&bytes), [graphics, graphics]);
+    }
+
+    #[test]
+    fn raster_image() {
+        let bytes = [0x1d, b'v', b'0', 0, 2, 0, 1, 0, 0x80, 0x01, b'A'];
+        assert_eq!(
+            decode_all(&bytes),
+            [
+                DecodedCmd::RasterImage {
+                    mode: 0,
+                    width_bytes: 2,
+                    height: 1,
+                    data: &[0x80, 0x01],
+                },
+                DecodedCmd::Text(b"A"),
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn huge_lengths_are_incomplete() {
+        let streams: [&[u8]; 3] = [
+            &[0x1c, b'q', 255, 0xff, 0xff, 0xff, 0xff, 0],
+            &[0x1d, b'8', b'L', 0xff, 0xff, 0xff, 0xff, 48],
+            &[0x1d, b'v', b'0', 0, 0xff, 0xff, 0xff, 0xff, 0],
+        ];
+        for bytes in streams.iter() {
+            assert_eq!(decode_all(bytes), [DecodedCmd::Incomplete(bytes)]);
+        }
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "32")]
+    fn overflowing_lengths_are_invalid() {
+        let streams: [&[u8]; 2] = [
+            &[0x1c, b'q', 255, 0xff, 0xff, 0xff, 0xff, 0],
+            &[0x1d, b'8', b'L', 0xff, 0xff, 0xff, 0xff, 48],
+        ];
+        for bytes in streams.iter() {
+            assert_eq!(decode_all(bytes), [DecodedCmd::Invalid(bytes)]);
+        }
+    }
+
+    #[test]
+    fn job_local_commands() {
+        let bytes = [
+            0x1b, b'@', b'H', b'i', 0x0a, 0x1d, b'(', b'L', 6, 0, 48, 69, b'L', b'G', 1, 1, 0x1d,
+            b'V', 66, 3, 0x1b, b'p', 0, 25, 250, 0x10, 0x14, 1, 0, 1, 0x1d, b'(', b'L', 4, 0, 48,
+            65, b'C', b'L', 0x1d, b'(', b'E', 3, 0, 1, 73, 78, 0x1b, b'=', 1,
+        ];
+        let local: Vec<_> = decode(&bytes).map(|cmd| cmd.is_job_local()).collect();
+        assert_eq!(
+            local,
+            [true, true, true, true, true, false, false, false, false, false, false]
+        );
+    }
+
+    #[test]
+    fn dump_names_commands() {
+        let bytes = [0x1b, b'@', b'H', b'i', 0x0a, 0x1d, b'V', 66, 3];
+        assert_eq!(
+            dump(&bytes),
+            "000000  1b 40                       ESC @  initialize printer\n\
+             000002  48 69                       \"Hi\"\n\
+             000004  0a                          LF\n\
+             000005  1d 56 42 03                 GS V 66 3  cut paper\n"
+        );
+    }
+
+    /// Compare `bytes` with the fixtures `name.bin` and its listing
+    /// `name.dump`, rewriting both if `ESCPOS_UPDATE_GOLDEN` is set.
+    fn assert_golden(name: &str, bytes: &[u8]) {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+        let bin = dir.join(format!("{}.bin", name));
+        let listing = dir.join(format!("{}.dump", name));
+        if std::env::var_os("ESCPOS_UPDATE_GOLDEN").is_some() {
+            std::fs::write(&bin, bytes).unwrap();
+            std::fs::write(&listing, dump(bytes)).unwrap();
+        }
+        let expected = std::fs::read_to_string(&listing).unwrap();
+        assert_eq!(dump(bytes), expected, "listing of {} changed", name);
+        assert_eq!(bytes, &std::fs::read(&bin).unwrap()[..], "{} changed", name);
+    }
+
+    /// Bytes written after initializing a printer with the default profile.
+    fn printed<F>(print: F) -> Vec<u8>
+    where
+        F: FnOnce(&mut Printer<MemoryTransport>) -> crate::Result<()>,
+    {
+        let mut printer = Printer::new(MemoryTransport::new()).unwrap();
+        printer.transport_mut().take();
+        print(&mut printer).unwrap();
+        printer.into_transport().into_inner()
+    }
+
+    #[test]
+    fn golden_formatted_str() {
+        let bytes = printed(|printer| {
+            printer.write_styled(&FormattedStr::new(("RECEIPT", "\n")).centered().scale(2, 2))?;
+            printer.write_styled(&("Total ", "12,50 €".emph().wider(), "\n"))?;
+            printer.write_styled(
+                &FormattedStr::new(("paid ", "by card".regular().reverse(), " ✓\n")).underline(),
+            )?;
+            printer.write_styled(&FormattedStr::new(("Grüße, Søren", "\n")).small().right())
+        });
+        assert_golden("formatted_str", &bytes);
+    }
+
+    #[test]
+    fn golden_document() {
+        let mut table = Table::new(&[Justification::Left, Justification::Right]);
+        table.header(&["Item", "Price"]).row(&["Käse", "3,20"]);
+        let mut document = Document::with_columns(20, 26);
+        document
+            .justify(Justification::Center)
+            .styled(TextStyle::large(), "Shop")
+            .justify(Justification::Left)
+            .text("A line that needs to be wrapped")
+            .rule_with('=')
+            .row3("Brot", "2x", "4,00")
+            .table(&table)
+            .feed(1)
+            .styled(TextStyle::small(), "Danke schön!");
+        let bytes = printed(|printer| printer.print_document(&document));
+        assert_golden("document", &bytes);
+    }
+}
@@ -0,0 +1,731 @@
+use super::{
+    cmds::{Font, Justification, UnderlineMode},
+    Encode, Encoder, Error, EscPosCmd, PrintState, PrinterProfile, QrCode,
+};
+
+/// Characters per line of font A on 80mm paper.
+pub const FONT_A_COLUMNS: usize = 42;
+/// Characters per line of font B on 80mm paper.
+pub const FONT_B_COLUMNS: usize = 56;
+
+/// Width of a font A character in dots.
+const FONT_A_WIDTH: usize = 12;
+/// Width of a font B character in dots.
+const FONT_B_WIDTH: usize = 9;
+
+/// Appearance of text in a [`Document`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TextStyle {
+    pub font: Font,
+    pub emphasized: bool,
+    pub double_width: bool,
+    pub double_height: bool,
+    pub underline: bool,
+    pub reverse: bool,
+}
+
+/// Layout builder for receipts.
+///
+/// Text is wrapped at word boundaries according to the width of the
+/// characters in their style. The finished document is printed with
+/// [`Printer::print_document`](crate::Printer::print_document).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Document {
+    font_a_columns: usize,
+    font_b_columns: usize,
+    style: TextStyle,
+    justification: Justification,
+    blocks: Vec<Block>,
+}
+
+/// Table with aligned columns, see [`Document::table`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Table {
+    align: Vec<Justification>,
+    widths: Vec<Option<usize>>,
+    header: Option<Vec<String>>,
+    rows: Vec<Vec<String>>,
+}
+
+/// Part of a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Block {
+    /// Single printed line made up of differently styled parts.
+    Line {
+        justification: Justification,
+        spans: Vec<(TextStyle, String)>,
+    },
+    Qr {
+        justification: Justification,
+        code: QrCode,
+    },
+}
+
+impl TextStyle {
+    pub fn emphasized() -> Self {
+        TextStyle {
+            emphasized: true,
+            ..Default::default()
+        }
+    }
+
+    /// Double width and height.
+    pub fn large() -> Self {
+        TextStyle {
+            double_width: true,
+            double_height: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn small() -> Self {
+        TextStyle {
+            font: Font::B,
+            ..Default::default()
+        }
+    }
+
+    /// Width of a character in dots.
+    pub(crate) fn char_width(&self) -> usize {
+        let width = match self.font {
+            Font::A => FONT_A_WIDTH,
+            Font::B | Font::C => FONT_B_WIDTH,
+        };
+        if self.double_width {
+            2 * width
+        } else {
+            width
+        }
+    }
+
+    /// Printer state for text in this style, font C is printed as font B.
+    pub(crate) fn print_state(&self, justification: Justification) -> PrintState {
+        PrintState {
+            font: if self.font == Font::A {
+                Font::A
+            } else {
+                Font::B
+            },
+            emphasized: self.emphasized,
+            underline: if self.underline {
+                UnderlineMode::OneDot
+            } else {
+                UnderlineMode::Off
+            },
+            reverse: self.reverse,
+            width: if self.double_width { 2 } else { 1 },
+            height: if self.double_height { 2 } else { 1 },
+            justification,
+            ..Default::default()
+        }
+    }
+}
+
+impl Document {
+    /// Create a document for 80mm paper.
+    pub fn new() -> Self {
+        Self::with_columns(FONT_A_COLUMNS, FONT_B_COLUMNS)
+    }
+
+    /// Create a document for paper fitting the given number of characters
+    /// per line in font A and B.
+    pub fn with_columns(font_a_columns: usize, font_b_columns: usize) -> Self {
+        Document {
+            font_a_columns,
+            font_b_columns,
+            style: TextStyle::default(),
+            justification: Justification::Left,
+            blocks: Vec::new(),
+        }
+    }
+
+    /// Create a document fitting the paper of `profile`.
+    ///
+    /// Without font B, the font A width is used for small text.
+    pub fn for_profile(profile: &PrinterProfile) -> Self {
+        let font_b_columns = profile.font_b_columns.unwrap_or(profile.font_a_columns);
+        Self::with_columns(profile.font_a_columns, font_b_columns)
+    }
+
+    /// Number of characters fitting on one line using `style`.
+    ///
+    /// Font C is assumed to be as wide as font B.
+    pub fn columns(&self, style: &TextStyle) -> usize {
+        let columns = match style.font {
+            Font::A => self.font_a_columns,
+            Font::B | Font::C => self.font_b_columns,
+        };
+        if style.double_width {
+            columns / 2
+        } else {
+            columns
+        }
+    }
+
+    /// Use `style` for everything added from now on.
+    pub fn style(&mut self, style: TextStyle) -> &mut Self {
+        self.style = style;
+        self
+    }
+
+    /// Justify text added from now on.
+    ///
+    /// Rows, rules and tables always span the full line.
+    pub fn justify(&mut self, justification: Justification) -> &mut Self {
+        self.justification = justification;
+        self
+    }
+
+    /// Add wrapped text, followed by a line break.
+    ///
+    /// Line breaks in `text` are kept.
+    pub fn text(&mut self, text: &str) -> &mut Self {
+        self.rich_text(&[(self.style, text)])
+    }
+
+    /// Add wrapped text made up of differently styled parts, followed
+    /// by a line break.
+    ///
+    /// The current style is ignored, line breaks in the parts are kept.
+    pub fn rich_text<S: AsRef<str>>(&mut self, spans: &[(TextStyle, S)]) -> &mut Self {
+        let width = (self.font_a_columns * FONT_A_WIDTH).max(self.font_b_columns * FONT_B_WIDTH);
+        for spans in wrap_spans(spans, width) {
+            self.blocks.push(Block::Line {
+                justification: self.justification,
+                spans,
+            });
+        }
+        self
+    }
+
+    /// Add `text` using `style` for this paragraph only.
+    pub fn styled(&mut self, style: TextStyle, text: &str) -> &mut Self {
+        let outer = self.style;
+        self.style(style).text(text).style(outer)
+    }
+
+    /// Add `lines` empty lines.
+    pub fn feed(&mut self, lines: usize) -> &mut Self {
+        for _ in 0..lines {
+            self.push(Justification::Left, String::new());
+        }
+        self
+    }
+
+    /// Add a QR code, justified like text.
+    pub fn qr(&mut self, code: QrCode) -> &mut Self {
+        self.blocks.push(Block::Qr {
+            justification: self.justification,
+            code,
+        });
+        self
+    }
+
+    /// Add a line of `-` across the full width.
+    pub fn rule(&mut self) -> &mut Self {
+        self.rule_with('-')
+    }
+
+    /// Add a line of `c` across the full width.
+    pub fn rule_with(&mut self, c: char) -> &mut Self {
+        let width = self.columns(&self.style);
+        self.push(Justification::Left, c.to_string().repeat(width))
+    }
+
+    /// Add a row with `left` aligned left and `right` aligned right,
+    /// i.e. an item and its price.
+    ///
+    /// `left` is wrapped if both do not fit on one line.
+    pub fn row(&mut self, left: &str, right: &str) -> &mut Self {
+        let width = self.columns(&self.style);
+        let right_width = char_len(right).min(width / 2);
+        let cells = [
+            (left, width.saturating_sub(right_width + 1).max(1)),
+            (right, right_width),
+        ];
+        let aligns = [Justification::Left, Justification::Right];
+        self.cells(&cells, &aligns)
+    }
+
+    /// Add a row with three columns, i.e. item, quantity and price.
+    ///
+    /// `middle` is centered and `right` aligned right, both keep their
+    /// natural width while `left` is wrapped if necessary.
+    pub fn row3(&mut self, left: &str, middle: &str, right: &str) -> &mut Self {
+        let width = self.columns(&self.style);
+        let middle_width = char_len(middle).min(width / 4);
+        let right_width = char_len(right).min(width / 4);
+        let cells = [
+            (
+                left,
+                width.saturating_sub(middle_width + right_width + 2).max(1),
+            ),
+            (middle, middle_width),
+            (right, right_width),
+        ];
+        let aligns = [
+            Justification::Left,
+            Justification::Center,
+            Justification::Right,
+        ];
+        self.cells(&cells, &aligns)
+    }
+
+    /// Add `table`, fitted to the line width.
+    ///
+    /// The header is printed emphasized and separated by a rule.
+    pub fn table(&mut self, table: &Table) -> &mut Self {
+        let width = self.columns(&self.style);
+        let widths = table.column_widths(width);
+        if let Some(ref header) = table.header {
+            let outer = self.style;
+            self.style.emphasized = true;
+            self.table_row(header, &widths, &table.align);
+            self.style = outer;
+            self.rule();
+        }
+        for row in &table.rows {
+            self.table_row(row, &widths, &table.align);
+        }
+        self
+    }
+
+    fn table_row(&mut self, row: &[String], widths: &[usize], align: &[Justification]) {
+        let cells: Vec<(&str, usize)> = widths
+            .iter()
+            .enumerate()
+            .map(|(idx, &width)| (row.get(idx).map(String::as_str).unwrap_or(""), width))
+            .collect();
+        self.cells(&cells, align);
+    }
+
+    /// Add cells of the given widths side by side, separated by a space.
+    fn cells(&mut self, cells: &[(&str, usize)], align: &[Justification]) -> &mut Self {
+        let wrapped: Vec<Vec<String>> = cells
+            .iter()
+            .map(|&(text, width)| wrap(text, width))
+            .collect();
+        let height = wrapped.iter().map(Vec::len).max().unwrap_or(0);
+        for idx in 0..height {
+            let line: Vec<String> = wrapped
+                .iter()
+                .zip(cells)
+                .zip(align)
+                .map(|((lines, &(_, width)), &align)| {
+                    let text = lines.get(idx).map(String::as_str).unwrap_or("");
+                    pad(text, width, align)
+                })
+                .collect();
+            self.push(Justification::Left, line.join(" ").trim_end().to_string());
+        }
+        self
+    }
+
+    fn push(&mut self, justification: Justification, text: String) -> &mut Self {
+        self.blocks.push(Block::Line {
+            justification,
+            spans: vec![(self.style, text)],
+        });
+        self
+    }
+
+    /// Encode all text using `encoder`, including the commands for
+    /// styles and QR codes.
+    ///
+    /// QR codes are printed as images if `profile` lacks native support.
+    pub(crate) fn encode(
+        &self,
+        encoder: &mut Encoder,
+        profile: &PrinterProfile,
+    ) -> Result<Vec<u8>, Error> {
+        let mut bytes = Vec::new();
+        let mut state = PrintState::default();
+        // Commands are added as bytes, so their parameters are not encoded
+        let mut switch = |bytes: &mut Vec<u8>, next: PrintState| {
+            state.encode_diff(&next, bytes);
+            state = next;
+        };
+        for block in &self.blocks {
+            match block {
+                Block::Line {
+                    justification,
+                    spans,
+                } => {
+                    for (style, span) in spans {
+                        switch(&mut bytes, style.print_state(*justification));
+                        encoder.encode_into(span, &mut bytes);
+                    }
+                    if spans.is_empty() {
+                        switch(&mut bytes, TextStyle::default().print_state(*justification));
+                    }
+                    EscPosCmd::PrintAndLineFeed.encode_to(&mut bytes);
+                }
+                Block::Qr {
+                    justification,
+                    code,
+                } => {
+                    let next = PrintState {
+                        justification: *justification,
+                        ..Default::default()
+                    };
+                    switch(&mut bytes, next);
+                    bytes.extend_from_slice(&profile.encode_qr(code)?);
+                }
+            }
+        }
+        switch(&mut bytes, PrintState::default());
+        Ok(bytes)
+    }
+}
+
+impl Default for Document {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Table {
+    /// Create a table with one column per entry of `align`.
+    pub fn new(align: &[Justification]) -> Self {
+        Table {
+            align: align.to_vec(),
+            widths: vec![None; align.len()],
+            header: None,
+            rows: Vec::new(),
+        }
+    }
+
+    /// Fix the width of column `column` instead of fitting it to its content.
+    pub fn width(&mut self, column: usize, width: usize) -> &mut Self {
+        if let Some(slot) = self.widths.get_mut(column) {
+            *slot = Some(width);
+        }
+        self
+    }
+
+    pub fn header<S: AsRef<str>>(&mut self, cells: &[S]) -> &mut Self {
+        self.header = Some(cells.iter().map(|cell| cell.as_ref().to_string()).collect());
+        self
+    }
+
+    /// Add a row, missing cells are left empty.
+    pub fn row<S: AsRef<str>>(&mut self, cells: &[S]) -> &mut Self {
+        self.rows
+            .push(cells.iter().map(|cell| cell.as_ref().to_string()).collect());
+        self
+    }
+
+    /// Width of every column for a line of `width` characters.
+    ///
+    /// Columns default to their widest cell, the widest flexible
+    /// columns are narrowed until the table fits.
+    fn column_widths(&self, width: usize) -> Vec<usize> {
+        let mut widths: Vec<usize> = (0..self.align.len())
+            .map(|column| {
+                self.widths[column].unwrap_or_else(|| {
+                    self.header
+                        .iter()
+                        .chain(&self.rows)
+                        .filter_map(|row| row.get(column))
+                        .map(|cell| char_len(cell))
+                        .max()
+                        .unwrap_or(0)
+                        .max(1)
+                })
+            })
+            .collect();
+        let available = width.saturating_sub(widths.len().saturating_sub(1));
+        while widths.iter().sum::<usize>() > available {
+            let widest = (0..widths.len())
+                .filter(|&column| self.widths[column].is_none() && widths[column] > 1)
+                .max_by_key(|&column| widths[column]);
+            match widest {
+                Some(column) => widths[column] -= 1,
+                None => break,
+            }
+        }
+        widths
+    }
+}
+
+/// Break styled text into lines of at most `width` dots.
+///
+/// Lines are broken at spaces and tabs, words longer than a line are split.
+/// Spaces inside of a line are kept as they are, spaces at a line break are
+/// dropped. Non-breaking spaces are kept. Consecutive characters of the same
+/// style are merged again.
+fn wrap_spans<S: AsRef<str>>(
+    spans: &[(TextStyle, S)],
+    width: usize,
+) -> Vec<Vec<(TextStyle, String)>> {
+    let chars: Vec<(TextStyle, char)> = spans
+        .iter()
+        .flat_map(|(style, text)| {
+            text.as_ref()
+                .chars()
+                .map(move |c| (*style, if c == '\t' { ' ' } else { c }))
+        })
+        .collect();
+    let text_width = |chars: &[(TextStyle, char)]| -> usize {
+        chars.iter().map(|(style, _)| style.char_width()).sum()
+    };
+    let mut lines = Vec::new();
+    for paragraph in chars.split(|&(_, c)| c == '\n') {
+        let mut line: Vec<(TextStyle, char)> = Vec::new();
+        let mut used = 0;
+        let mut rest = paragraph;
+        while !rest.is_empty() {
+            let spaces = rest.iter().take_while(|&&(_, c)| c == ' ').count();
+            let (space, after) = rest.split_at(spaces);
+            let word_len = after.iter().take_while(|&&(_, c)| c != ' ').count();
+            let (mut word, after) = after.split_at(word_len);
+            rest = after;
+            let space_width = text_width(space);
+            let word_width = text_width(word);
+            if used + space_width + word_width <= width {
+                line.extend_from_slice(space);
+                line.extend_from_slice(word);
+                used += space_width + word_width;
+                continue;
+            }
+            if word.is_empty() {
+                break;
+            }
+            if !line.is_empty() {
+                lines.push(merge_spans(&std::mem::take(&mut line)));
+            }
+            loop {
+                let mut fitting = 0;
+                let fit = word
+                    .iter()
+                    .take_while(|(style, _)| {
+                        fitting += style.char_width();
+                        fitting <= width
+                    })
+                    .count()
+                    .max(1);
+                if fit >= word.len() {
+                    break;
+                }
+                lines.push(merge_spans(&word[..fit]));
+                word = &word[fit..];
+            }
+            line.extend_from_slice(word);
+            used = text_width(word);
+        }
+        lines.push(merge_spans(&line));
+    }
+    lines
+}
+
+/// Group consecutive characters of the same style.
+fn merge_spans(chars: &[(TextStyle, char)]) -> Vec<(TextStyle, String)> {
+    let mut spans: Vec<(TextStyle, String)> = Vec::new();
+    for &(style, c) in chars {
+        match spans.last_mut() {
+            Some((last, text)) if *last == style => text.push(c),
+            _ => spans.push((style, c.to_string())),
+        }
+    }
+    spans
+}
+
+/// Break `text` into lines of at most `width` characters.
+///
+/// Lines are broken at whitespace, words longer than a line are split.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    for paragraph in text.split('\n') {
+        let mut line = String::new();
+        let mut len = 0;
+        for word in paragraph.split_whitespace() {
+            let mut word: Vec<char> = word.chars().collect();
+            if len > 0 && len + 1 + word.len() <= width {
+                line.push(' ');
+                line.extend(&word);
+                len += 1 + word.len();
+                continue;
+            }
+            if len > 0 {
+                lines.push(std::mem::take(&mut line));
+            }
+            while word.len() > width {
+                lines.push(word.drain(..width).collect());
+            }
+            line = word.iter().collect();
+            len = word.len();
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// Pad `text` with spaces to `width` characters.
+fn pad(text: &str, width: usize, align: Justification) -> String {
+    let free = width.saturating_sub(char_len(text));
+    let left = match align {
+        Justification::Left => 0,
+        Justification::Center => free / 2,
+        Justification::Right => free,
+    };
+    format!("{}{}{}", " ".repeat(left), text, " ".repeat(free - left))
+}
+
+fn char_len(text: &str) -> usize {
+    text.chars().count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MemoryTransport, Printer};
+
+    /// Printed text of every line, without styles.
+    fn lines(document: &Document) -> Vec<String> {
+        document
+            .blocks
+            .iter()
+            .filter_map(|block| match block {
+                Block::Line { spans, .. } => {
+                    Some(spans.iter().map(|(_, text)| text.as_str()).collect())
+                }
+                Block::Qr { .. } => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn text_is_wrapped_at_word_boundaries() {
+        let mut document = Document::with_columns(10, 13);
+        document.text("aaaa bbbb cccc\nabcdefghijkl");
+        assert_eq!(lines(&document), ["aaaa bbbb", "cccc", "abcdefghij", "kl"]);
+    }
+
+    #[test]
+    fn double_width_text_is_wrapped_at_half_the_width() {
+        let mut document = Document::with_columns(10, 13);
+        document.style(TextStyle::large()).text("aaaa bbbb");
+        assert_eq!(lines(&document), ["aaaa", "bbbb"]);
+
+        let mut document = Document::with_columns(10, 13);
+        let spans = [
+            (TextStyle::large(), "aa"),
+            (TextStyle::default(), " bbbb cc"),
+        ];
+        document.rich_text(&spans);
+        assert_eq!(lines(&document), ["aa bbbb", "cc"]);
+    }
+
+    #[test]
+    fn repeated_spaces_are_kept_inside_of_a_line() {
+        let mut document = Document::with_columns(10, 13);
+        document
+            .text(" a  b\tc ")
+            .text("aaaa  bbbbbb")
+            .text("aaaa      ");
+        assert_eq!(
+            lines(&document),
+            [" a  b c ", "aaaa", "bbbbbb", "aaaa      "]
+        );
+    }
+
+    #[test]
+    fn spaces_keep_the_style_of_their_span() {
+        let mut document = Document::with_columns(20, 26);
+        let reverse = TextStyle {
+            reverse: true,
+            ..Default::default()
+        };
+        document.rich_text(&[(reverse, " Name "), (TextStyle::default(), ": hi")]);
+        assert_eq!(
+            document.blocks,
+            [Block::Line {
+                justification: Justification::Left,
+                spans: vec![
+                    (reverse, " Name ".to_string()),
+                    (TextStyle::default(), ": hi".to_string())
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn rows_align_the_right_column() {
+        let mut document = Document::with_columns(20, 26);
+        document
+            .row("Tea", "2.50")
+            .row3("Coffee", "2x", "5.00")
+            .row("A very long product name", "1.00");
+        assert_eq!(
+            lines(&document),
+            [
+                "Tea             2.50",
+                "Coffee       2x 5.00",
+                "A very long     1.00",
+                "product name",
+            ]
+        );
+    }
+
+    #[test]
+    fn table_columns_fit_their_content() {
+        let mut table = Table::new(&[Justification::Left, Justification::Right]);
+        table
+            .header(&["Item", "Price"])
+            .row(&["Apple", "1.00"])
+            .row(&["Banana", "12.50"]);
+        let mut document = Document::with_columns(20, 26);
+        document.table(&table);
+        assert_eq!(
+            lines(&document),
+            [
+                "Item   Price",
+                "--------------------",
+                "Apple   1.00",
+                "Banana 12.50",
+            ]
+        );
+    }
+
+    #[test]
+    fn table_columns_are_narrowed_to_the_line() {
+        let mut table = Table::new(&[
+            Justification::Left,
+            Justification::Center,
+            Justification::Right,
+        ]);
+        table
+            .width(2, 4)
+            .row(&["Long product", "x", "9.99"])
+            .row(&["Tea"]);
+        let mut document = Document::with_columns(12, 16);
+        document.table(&table);
+        assert_eq!(lines(&document), ["Long  x 9.99", "produ", "ct", "Tea"]);
+    }
+
+    #[test]
+    fn failed_documents_keep_the_code_table() {
+        let profile = PrinterProfile {
+            qr: false,
+            paper_width: 16,
+            ..PrinterProfile::generic_80mm()
+        };
+        let mut euro = Document::new();
+        euro.text("5 €");
+        let mut fresh = Printer::with_profile(MemoryTransport::new(), profile.clone()).unwrap();
+        fresh.transport_mut().take();
+        fresh.print_document(&euro).unwrap();
+        let expected = fresh.into_transport().into_inner();
+        assert!(expected.windows(2).any(|bytes| bytes == b"\x1bt"));
+
+        let mut printer = Printer::with_profile(MemoryTransport::new(), profile).unwrap();
+        printer.transport_mut().take();
+        let mut failing = euro.clone();
+        failing.qr(QrCode::new("too wide for the paper").unwrap());
+        assert!(printer.print_document(&failing).is_err());
+        assert!(printer.print_job(&crate::PrintJob::new(failing)).is_err());
+        printer.print_document(&euro).unwrap();
+        assert_eq!(printer.into_transport().into_inner(), expected);
+    }
+}
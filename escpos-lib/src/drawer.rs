@@ -0,0 +1,114 @@
+use std::{error, fmt, time::Duration};
+
+use super::chars;
+
+/// Pin of the drawer kick-out connector.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DrawerPin {
+    /// Connector pin 2, drawer 1 on most setups.
+    #[default]
+    Pin2,
+    /// Connector pin 5, drawer 2 on most setups.
+    Pin5,
+}
+
+/// Pulse sent to the drawer kick-out connector, e.g. to open a
+/// cash drawer or to sound a buzzer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pulse {
+    pub pin: DrawerPin,
+    /// How long the pin is driven.
+    pub on: Duration,
+    /// Pause after the pulse. The printer uses `on` if this is shorter.
+    pub off: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PulseError {
+    /// `ESC p` times must be between 2ms and 510ms.
+    InvalidTime(Duration),
+    /// Real-time pulses must last between 100ms and 800ms.
+    InvalidRealTimeTime(Duration),
+}
+
+/// Unit of the `ESC p` timing parameters.
+const PULSE_STEP: Duration = Duration::from_millis(2);
+/// Unit of the `DLE DC4` timing parameter.
+const REAL_TIME_PULSE_STEP: Duration = Duration::from_millis(100);
+
+impl Pulse {
+    pub fn new(pin: DrawerPin, on: Duration, off: Duration) -> Self {
+        Pulse { pin, on, off }
+    }
+
+    /// Encode the `ESC p m t1 t2` command.
+    ///
+    /// Times are rounded up to the next 2ms step.
+    pub(crate) fn encode(&self) -> Result<[u8; 5], PulseError> {
+        let on = steps(self.on, PULSE_STEP, 255).ok_or(PulseError::InvalidTime(self.on))?;
+        let off = steps(self.off, PULSE_STEP, 255).ok_or(PulseError::InvalidTime(self.off))?;
+        Ok([chars::ESC as u8, b'p', self.pin.code(), on, off])
+    }
+
+    /// Encode the `DLE DC4 1 m t` real-time command.
+    ///
+    /// Only the on time is used, rounded up to the next 100ms step.
+    /// Unlike `ESC p`, this is executed immediately, even while the
+    /// printer is busy.
+    pub(crate) fn encode_real_time(&self) -> Result<[u8; 5], PulseError> {
+        let on = steps(self.on, REAL_TIME_PULSE_STEP, 8)
+            .ok_or(PulseError::InvalidRealTimeTime(self.on))?;
+        Ok([chars::DLE as u8, 0x14, 1, self.pin.code(), on])
+    }
+}
+
+impl Default for Pulse {
+    /// The timing recommended by Epson for cash drawers.
+    fn default() -> Self {
+        Pulse {
+            pin: DrawerPin::Pin2,
+            on: Duration::from_millis(50),
+            off: Duration::from_millis(500),
+        }
+    }
+}
+
+impl DrawerPin {
+    fn code(self) -> u8 {
+        match self {
+            DrawerPin::Pin2 => 0,
+            DrawerPin::Pin5 => 1,
+        }
+    }
+}
+
+/// Number of `step`s needed to cover `time`, between 1 and `max`.
+fn steps(time: Duration, step: Duration, max: u8) -> Option<u8> {
+    let steps = time.as_micros().div_ceil(step.as_micros());
+    if (1..=max as u128).contains(&steps) {
+        Some(steps as u8)
+    } else {
+        None
+    }
+}
+
+impl fmt::Display for PulseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PulseError::InvalidTime(time) => {
+                write!(
+                    f,
+                    "pulse time must be between 2ms and 510ms, got {:?}",
+                    time
+                )
+            }
+            PulseError::InvalidRealTimeTime(time) => write!(
+                f,
+                "real-time pulse must last between 100ms and 800ms, got {:?}",
+                time
+            ),
+        }
+    }
+}
+
+impl error::Error for PulseError {}
@@ -0,0 +1,534 @@
+// Glyphs for emoji missing from every code table, 12x12 dots each.
+//
+// `#` marks black dots. The glyphs are centered in the character cell
+// when downloaded, see `UserChars::emoji`.
+
+pub(crate) const EMOJI: [(char, [&str; 12]); 31] = [
+    (
+        '☔',
+        [
+            ".....#......",
+            "...#####....",
+            "..#######...",
+            ".#########..",
+            "############",
+            "#..#..#..#..",
+            ".....#......",
+            ".....#......",
+            ".....#......",
+            ".....#......",
+            "..#..#......",
+            "...##.......",
+        ],
+    ),
+    (
+        '🍎',
+        [
+            "......#.....",
+            ".....#.##...",
+            "..##.#####..",
+            ".##########.",
+            "##.#########",
+            "#.##########",
+            "############",
+            "############",
+            ".##########.",
+            ".##########.",
+            "..########..",
+            "...##..##...",
+        ],
+    ),
+    (
+        '🥜',
+        [
+            "....####....",
+            "...#....#...",
+            "..#..#...#..",
+            "..#......#..",
+            "...#....#...",
+            "....#..#....",
+            "....#..#....",
+            "...#....#...",
+            "..#...#..#..",
+            "..#......#..",
+            "...#....#...",
+            "....####....",
+        ],
+    ),
+    (
+        '🌱',
+        [
+            "............",
+            ".###....###.",
+            "#####..#####",
+            "######.####.",
+            ".####.####..",
+            ".....##.....",
+            ".....#......",
+            ".....#......",
+            ".....#......",
+            ".....#......",
+            "..########..",
+            ".##########.",
+        ],
+    ),
+    (
+        '🥩',
+        [
+            "............",
+            "..######....",
+            ".########...",
+            "###########.",
+            "####..######",
+            "###.##.#####",
+            "####..######",
+            "############",
+            ".##########.",
+            "..########..",
+            "....#####...",
+            "............",
+        ],
+    ),
+    (
+        '🥨',
+        [
+            "............",
+            "..###..###..",
+            ".#...##...#.",
+            "#....##....#",
+            "#...#..#...#",
+            "#..#....#..#",
+            ".##......##.",
+            "..#......#..",
+            "..##....##..",
+            ".#..####..#.",
+            ".#........#.",
+            "..########..",
+        ],
+    ),
+    (
+        '🧀',
+        [
+            "............",
+            "........###.",
+            ".....###..#.",
+            "..###.....#.",
+            ".#........#.",
+            "############",
+            "#..##.....##",
+            "#.####..#..#",
+            "#..##..###.#",
+            "#.......#..#",
+            "#..#.......#",
+            "############",
+        ],
+    ),
+    (
+        '🍮',
+        [
+            "............",
+            "....####....",
+            "...######...",
+            "...#....#...",
+            "..#......#..",
+            "..#......#..",
+            ".#........#.",
+            ".#........#.",
+            "############",
+            "############",
+            ".##########.",
+            "............",
+        ],
+    ),
+    (
+        '🫖',
+        [
+            ".....##.....",
+            "...######...",
+            "..#......#..",
+            ".##......#.#",
+            "#.#......#.#",
+            "#.#......###",
+            "#.#......##.",
+            ".##......#..",
+            "..#......#..",
+            "..#......#..",
+            "...######...",
+            "............",
+        ],
+    ),
+    (
+        '🥛',
+        [
+            "..########..",
+            "..#......#..",
+            "..#......#..",
+            "..########..",
+            "..########..",
+            "..########..",
+            "..########..",
+            "..########..",
+            "..########..",
+            "..########..",
+            "..########..",
+            "...######...",
+        ],
+    ),
+    (
+        '💡',
+        [
+            "....####....",
+            "..##....##..",
+            ".#........#.",
+            ".#...##...#.",
+            ".#..#..#..#.",
+            ".#...##...#.",
+            "..#..##..#..",
+            "...#.##.#...",
+            "....####....",
+            "....####....",
+            "....####....",
+            ".....##.....",
+        ],
+    ),
+    (
+        '🧁',
+        [
+            ".....##.....",
+            "....####....",
+            "..########..",
+            ".##########.",
+            "############",
+            "############",
+            ".##########.",
+            ".#.#.#.#.##.",
+            ".#.#.#.#.##.",
+            "..#.#.#.#.#.",
+            "..#.#.#.#.#.",
+            "..########..",
+        ],
+    ),
+    (
+        '🧇',
+        [
+            "............",
+            ".##########.",
+            ".#..#..#..#.",
+            ".#..#..#..#.",
+            ".##########.",
+            ".#..#..#..#.",
+            ".#..#..#..#.",
+            ".##########.",
+            ".#..#..#..#.",
+            ".#..#..#..#.",
+            ".##########.",
+            "............",
+        ],
+    ),
+    (
+        '🍿',
+        [
+            "...#.##.#...",
+            "..########..",
+            ".##########.",
+            "..########..",
+            "############",
+            "#..##..##..#",
+            "#..##..##..#",
+            ".#.##..##.#.",
+            ".#.##..##.#.",
+            ".#.##..##.#.",
+            "..###..###..",
+            "..########..",
+        ],
+    ),
+    (
+        '🍬',
+        [
+            "............",
+            "............",
+            "##........##",
+            "###.####.###",
+            "####....####",
+            "###......###",
+            "###......###",
+            "####....####",
+            "###.####.###",
+            "##........##",
+            "............",
+            "............",
+        ],
+    ),
+    (
+        '🥫',
+        [
+            "..########..",
+            ".#........#.",
+            ".##########.",
+            ".#........#.",
+            ".#.######.#.",
+            ".#.#....#.#.",
+            ".#.#....#.#.",
+            ".#.######.#.",
+            ".#........#.",
+            ".#........#.",
+            ".##########.",
+            "............",
+        ],
+    ),
+    (
+        '🥣',
+        [
+            ".........#..",
+            "........#...",
+            ".......#....",
+            "......#.....",
+            "############",
+            "#..........#",
+            "#..........#",
+            ".#........#.",
+            "..#......#..",
+            "...######...",
+            "....####....",
+            "............",
+        ],
+    ),
+    (
+        '🐈',
+        [
+            ".#...#......",
+            ".##.##......",
+            ".#####......",
+            ".#.#.#......",
+            ".#####...#..",
+            "..###.....#.",
+            ".#####....#.",
+            ".######..#..",
+            ".#######.#..",
+            ".########...",
+            ".########...",
+            "............",
+        ],
+    ),
+    (
+        '🧼',
+        [
+            "........##..",
+            ".......#..#.",
+            "..##....##..",
+            ".#..#.......",
+            "..##........",
+            ".##########.",
+            "#..........#",
+            "#.########.#",
+            "#.########.#",
+            "#..........#",
+            ".##########.",
+            "............",
+        ],
+    ),
+    (
+        '💆',
+        [
+            "....####....",
+            "...######...",
+            "..##....##..",
+            "..#.#..#.#..",
+            "..#......#..",
+            "#.#.#..#.#.#",
+            "##.#.##.#.##",
+            "##..#..#..##",
+            ".#...##...#.",
+            ".##......##.",
+            "..##....##..",
+            "............",
+        ],
+    ),
+    (
+        '💄',
+        [
+            ".....##.....",
+            "....###.....",
+            "....####....",
+            "....####....",
+            "....####....",
+            "...######...",
+            "...######...",
+            "..########..",
+            "..#......#..",
+            "..#......#..",
+            "..#......#..",
+            "..########..",
+        ],
+    ),
+    (
+        '🧻',
+        [
+            "............",
+            "...######...",
+            "..#......#..",
+            ".#...##...#.",
+            ".#..#..#..#.",
+            ".#...##...#.",
+            ".#........#.",
+            ".#........#.",
+            ".#........#.",
+            ".#........##",
+            "..########.#",
+            "...........#",
+        ],
+    ),
+    (
+        '🍷',
+        [
+            "..########..",
+            "..#......#..",
+            "..#......#..",
+            "..########..",
+            "..########..",
+            "...######...",
+            "....####....",
+            ".....##.....",
+            ".....##.....",
+            ".....##.....",
+            "....####....",
+            "..########..",
+        ],
+    ),
+    (
+        '🍕',
+        [
+            "############",
+            "#..........#",
+            ".#.##..##.#.",
+            ".#.##..##.#.",
+            "..#......#..",
+            "..#..##..#..",
+            "...#.##.#...",
+            "...#....#...",
+            "....#..#....",
+            "....#..#....",
+            ".....##.....",
+            "............",
+        ],
+    ),
+    (
+        '💲',
+        [
+            ".....##.....",
+            "...######...",
+            "..##.##.##..",
+            "..##.##.....",
+            "..##.##.....",
+            "...######...",
+            ".....##.##..",
+            ".....##.##..",
+            "..##.##.##..",
+            "...######...",
+            ".....##.....",
+            "............",
+        ],
+    ),
+    (
+        '🍞',
+        [
+            "............",
+            "..########..",
+            ".#........#.",
+            "#..........#",
+            "#..........#",
+            ".#........#.",
+            ".#........#.",
+            ".#........#.",
+            ".#........#.",
+            ".#........#.",
+            ".##########.",
+            "............",
+        ],
+    ),
+    (
+        '🛒',
+        [
+            "............",
+            "##..........",
+            ".#..........",
+            ".##########.",
+            ".#.#..#..##.",
+            ".#.#..#..#..",
+            ".########...",
+            ".#..........",
+            ".########...",
+            "..#....#....",
+            ".###..###...",
+            "..#....#....",
+        ],
+    ),
+    (
+        '☕',
+        [
+            "...#..#.....",
+            "..#..#......",
+            "...#..#.....",
+            "............",
+            ".########...",
+            ".#......###.",
+            ".#......#.#.",
+            ".#......#.#.",
+            ".#......###.",
+            "..#....#....",
+            "...####.....",
+            "##########..",
+        ],
+    ),
+    (
+        '🥚',
+        [
+            "....####....",
+            "...#....#...",
+            "..#......#..",
+            "..#......#..",
+            ".#........#.",
+            ".#........#.",
+            ".#........#.",
+            ".#........#.",
+            ".#........#.",
+            "..#......#..",
+            "...#....#...",
+            "....####....",
+        ],
+    ),
+    (
+        '🐟',
+        [
+            "............",
+            "............",
+            "....####....",
+            "..##....##.#",
+            ".#.#......##",
+            "#.........##",
+            ".#........##",
+            "..##....##.#",
+            "....####....",
+            "............",
+            "............",
+            "............",
+        ],
+    ),
+    (
+        '🍺',
+        [
+            "..#.##.#....",
+            ".########...",
+            ".#......###.",
+            ".#.#.#..#.#.",
+            ".#.#.#..#.#.",
+            ".#.#.#..#.#.",
+            ".#.#.#..###.",
+            ".#......#...",
+            ".#......#...",
+            ".########...",
+            "............",
+            "............",
+        ],
+    ),
+];
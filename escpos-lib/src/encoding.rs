@@ -0,0 +1,170 @@
+use super::{
+    chars, cmds::CharCodeTable, code_pages, user_chars::FIRST_USER_CHAR, Error, UserChars,
+};
+
+/// Code tables tried when a character is missing from the active one.
+pub const DEFAULT_CODE_TABLES: [CharCodeTable; 9] = [
+    CharCodeTable::PC437,
+    CharCodeTable::PC850,
+    CharCodeTable::PC858,
+    CharCodeTable::WPC1252,
+    CharCodeTable::PC852,
+    CharCodeTable::PC866,
+    CharCodeTable::PC860,
+    CharCodeTable::PC863,
+    CharCodeTable::PC865,
+];
+
+/// Maps Unicode text to the printer's code tables.
+///
+/// The encoder keeps track of the active code table and switches
+/// to another one if a character is not available. Characters found in
+/// none of the tables are printed with their user-defined glyph, if one
+/// was downloaded, or transliterated to ASCII.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Encoder {
+    current: CharCodeTable,
+    tables: Vec<CharCodeTable>,
+    /// Characters downloaded with `ESC &`, in the order of their codes.
+    user_chars: Vec<char>,
+}
+
+impl Encoder {
+    /// Create an encoder for a freshly initialized printer using all
+    /// [`DEFAULT_CODE_TABLES`].
+    pub fn new() -> Self {
+        Self::with_tables(&DEFAULT_CODE_TABLES)
+    }
+
+    /// Create an encoder that only switches between `tables`.
+    ///
+    /// Tables are tried in the given order.
+    pub fn with_tables(tables: &[CharCodeTable]) -> Self {
+        Encoder {
+            current: CharCodeTable::PC437,
+            tables: tables.to_vec(),
+            user_chars: Vec::new(),
+        }
+    }
+
+    /// The code table the printer is currently using.
+    pub fn current(&self) -> CharCodeTable {
+        self.current
+    }
+
+    /// Inform the encoder that the printer switched to `table`.
+    pub fn set_current(&mut self, table: CharCodeTable) {
+        self.current = table;
+    }
+
+    /// Inform the encoder that `chars` were downloaded to the printer.
+    ///
+    /// Pass an empty set after the definitions were cleared.
+    pub fn set_user_chars(&mut self, chars: &UserChars) {
+        self.user_chars = chars.chars().collect();
+    }
+
+    /// Encode `text`, including any necessary code table switches.
+    pub fn encode(&mut self, text: &str) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(text.len());
+        self.encode_into(text, &mut bytes);
+        bytes
+    }
+
+    /// Like [`Encoder::encode`], but appends to `bytes`.
+    pub fn encode_into(&mut self, text: &str, bytes: &mut Vec<u8>) {
+        for c in text.chars() {
+            if !self.encode_char(c, bytes) {
+                let ascii = any_ascii::any_ascii_char(c);
+                if ascii.is_empty() {
+                    bytes.push(b'?');
+                } else {
+                    bytes.extend(ascii.bytes().filter(|b| !b.is_ascii_control()));
+                }
+            }
+        }
+    }
+
+    /// Like [`Encoder::encode`], but fails with [`Error::Encoding`]
+    /// instead of transliterating a character.
+    ///
+    /// The encoder is left unchanged on failure.
+    pub fn encode_exact(&mut self, text: &str) -> Result<Vec<u8>, Error> {
+        let mut encoder = self.clone();
+        let mut bytes = Vec::with_capacity(text.len());
+        for c in text.chars() {
+            if !encoder.encode_char(c, &mut bytes) {
+                return Err(Error::Encoding(c));
+            }
+        }
+        *self = encoder;
+        Ok(bytes)
+    }
+
+    /// Encode `c` if any code table or user-defined character has it.
+    fn encode_char(&mut self, c: char, bytes: &mut Vec<u8>) -> bool {
+        if c.is_ascii() {
+            // Shared by all tables, this includes embedded commands
+            bytes.push(c as u8);
+        } else if let Some(byte) = lookup(self.current, c) {
+            bytes.push(byte);
+        } else if let Some((table, byte)) = self
+            .tables
+            .iter()
+            .find_map(|&table| lookup(table, c).map(|byte| (table, byte)))
+        {
+            bytes.extend_from_slice(&[chars::ESC as u8, b't', table.code(), byte]);
+            self.current = table;
+        } else if let Some(idx) = self.user_chars.iter().position(|&user| user == c) {
+            let esc = chars::ESC as u8;
+            let code = FIRST_USER_CHAR + idx as u8;
+            bytes.extend_from_slice(&[esc, b'%', 1, code, esc, b'%', 0]);
+        } else {
+            return false;
+        }
+        true
+    }
+}
+
+impl Default for Encoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Interpret `byte` using `table`.
+///
+/// Unknown characters are replaced by `U+FFFD`.
+pub fn decode_byte(table: CharCodeTable, byte: u8) -> char {
+    if byte.is_ascii() {
+        return byte as char;
+    }
+    match upper_half(table).map(|half| half[byte as usize - 0x80]) {
+        Some(c) if c != '\0' => c,
+        _ => char::REPLACEMENT_CHARACTER,
+    }
+}
+
+/// Find `c` in the upper half of `table`.
+fn lookup(table: CharCodeTable, c: char) -> Option<u8> {
+    upper_half(table)?
+        .iter()
+        .position(|&candidate| candidate == c && c != '\0')
+        .map(|idx| 0x80 + idx as u8)
+}
+
+/// Characters `0x80..=0xFF` of `table`, if known.
+pub(crate) fn upper_half(table: CharCodeTable) -> Option<&'static [char; 128]> {
+    match table {
+        CharCodeTable::PC437 => Some(&code_pages::PC437),
+        CharCodeTable::PC850 => Some(&code_pages::PC850),
+        CharCodeTable::PC860 => Some(&code_pages::PC860),
+        CharCodeTable::PC863 => Some(&code_pages::PC863),
+        CharCodeTable::PC865 => Some(&code_pages::PC865),
+        CharCodeTable::WPC1252 => Some(&code_pages::WPC1252),
+        CharCodeTable::PC866 => Some(&code_pages::PC866),
+        CharCodeTable::PC852 => Some(&code_pages::PC852),
+        CharCodeTable::PC858 => Some(&code_pages::PC858),
+        _ => None,
+    }
+}
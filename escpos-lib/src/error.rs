@@ -0,0 +1,129 @@
+use std::{
+    error, fmt,
+    io::{self, ErrorKind},
+};
+
+use super::{BarCodeError, NvError, PrinterStatus, PulseError, QrError};
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Everything that can go wrong while talking to the printer.
+#[derive(Debug)]
+pub enum Error {
+    /// Writing to or reading from the transport failed.
+    Io(io::Error),
+    /// The printer did not answer a request in time.
+    Timeout,
+    /// The command is not available on the printer model.
+    Unsupported {
+        what: String,
+        profile: String,
+    },
+    /// A parameter does not fit the printer, e.g. a margin wider than
+    /// the paper.
+    InvalidInput(String),
+    BarCode(BarCodeError),
+    Qr(QrError),
+    Pulse(PulseError),
+    Nv(NvError),
+    /// The character is in none of the code tables and has no
+    /// user-defined glyph.
+    Encoding(char),
+    /// The printer reported that it cannot print right now.
+    NotReady(PrinterStatus),
+}
+
+impl Error {
+    /// Whether trying again later may succeed, i.e. the printer was busy
+    /// or the connection was interrupted.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Error::Io(_) | Error::Timeout)
+    }
+
+    /// Short explanation for the person trying to print, telling them
+    /// what to do about it.
+    pub fn user_message(&self) -> &'static str {
+        match self.status() {
+            Some(status) if status.paper_out => "The printer is out of paper, please refill it!",
+            Some(status) if status.cover_open => "The printer cover is open, please close it!",
+            Some(_) => "The printer is not ready, try again later!",
+            None if self.is_retryable() => "The printer is not reachable, try again later!",
+            None => "Printing failed!",
+        }
+    }
+
+    /// The reported status, if the printer refused to print.
+    pub fn status(&self) -> Option<&PrinterStatus> {
+        match self {
+            Error::NotReady(status) => Some(status),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(why) => write!(f, "printer connection failed: {}", why),
+            Error::Timeout => write!(f, "printer did not answer in time"),
+            Error::Unsupported { what, profile } => {
+                write!(f, "{} not supported by {}", what, profile)
+            }
+            Error::InvalidInput(why) => write!(f, "{}", why),
+            Error::BarCode(why) => write!(f, "invalid bar code: {}", why),
+            Error::Qr(why) => write!(f, "invalid QR code: {}", why),
+            Error::Pulse(why) => write!(f, "invalid pulse: {}", why),
+            Error::Nv(why) => write!(f, "{}", why),
+            Error::Encoding(c) => write!(f, "cannot print {:?} with this printer", c),
+            Error::NotReady(status) if status.paper_out => write!(f, "printer is out of paper"),
+            Error::NotReady(status) if status.cover_open => write!(f, "printer cover is open"),
+            Error::NotReady(_) => write!(f, "printer is not ready"),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io(why) => Some(why),
+            Error::BarCode(why) => Some(why),
+            Error::Qr(why) => Some(why),
+            Error::Pulse(why) => Some(why),
+            Error::Nv(why) => Some(why),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(why: io::Error) -> Self {
+        match why.kind() {
+            ErrorKind::TimedOut => Error::Timeout,
+            _ => Error::Io(why),
+        }
+    }
+}
+
+impl From<BarCodeError> for Error {
+    fn from(why: BarCodeError) -> Self {
+        Error::BarCode(why)
+    }
+}
+
+impl From<QrError> for Error {
+    fn from(why: QrError) -> Self {
+        Error::Qr(why)
+    }
+}
+
+impl From<PulseError> for Error {
+    fn from(why: PulseError) -> Self {
+        Error::Pulse(why)
+    }
+}
+
+impl From<NvError> for Error {
+    fn from(why: NvError) -> Self {
+        Error::Nv(why)
+    }
+}
@@ -1,18 +1,42 @@
-use std::fmt;
+use bytes::BufMut;
 
-use crate::cmds::UnderlineMode;
+use std::fmt::Write;
 
-use super::{cmds::PrintMode, EscPosCmd};
+use super::{
+    cmds::{Font, Justification, UnderlineMode},
+    PrintState, StyleWriter,
+};
+
+/// Text written together with the commands for its style.
+pub trait Styled {
+    /// Write the text to `w`, switching to its style and back.
+    fn write_styled<B: BufMut>(&self, w: &mut StyleWriter<'_, B>);
+}
 
 /// String with applied formatting.
-/// This can be used to preformat strings before printing.
+/// This can be used to preformat strings before printing, see
+/// [`Printer::write_styled`](crate::Printer::write_styled).
+///
+/// Formatted strings can be nested with [`FormattedStr::new`], e.g.
+/// `FormattedStr::new(("Name: ", "name".reverse())).emph()` prints the
+/// whole line emphasized and the name reversed as well. Styles set on the
+/// inner string override the enclosing ones, the others are inherited.
 #[derive(Debug, Default)]
 pub struct FormattedStr<S> {
-    mode: PrintMode,
-    reverse_color: bool,
     text: S,
-    bold: bool,
-    underline: UnderlineMode,
+    font: Option<Font>,
+    emphasized: Option<bool>,
+    underline: Option<UnderlineMode>,
+    reverse: Option<bool>,
+    /// Width multiplier, 1 to 8.
+    width: Option<u8>,
+    /// Height multiplier, 1 to 8.
+    height: Option<u8>,
+    justification: Option<Justification>,
+    line_spacing: Option<u8>,
+    char_spacing: Option<u8>,
+    upside_down: Option<bool>,
+    rotated: Option<bool>,
 }
 
 pub trait FmtStr<S> {
@@ -22,130 +46,340 @@ pub trait FmtStr<S> {
     fn underline(self) -> FormattedStr<S>;
     fn reverse(self) -> FormattedStr<S>;
     fn small(self) -> FormattedStr<S>;
+    /// Scale characters by 1 to 8 times horizontally and vertically.
+    fn scale(self, width: u8, height: u8) -> FormattedStr<S>;
+    /// Center the line the text starts on.
+    fn centered(self) -> FormattedStr<S>;
+    /// Right align the line the text starts on.
+    fn right(self) -> FormattedStr<S>;
+    /// Use `dots` as line spacing for line feeds within the text.
+    fn line_spacing(self, dots: u8) -> FormattedStr<S>;
+    /// Add `dots` of space to the right of each character.
+    fn char_spacing(self, dots: u8) -> FormattedStr<S>;
+    /// Print lines starting with the text upside-down.
+    fn upside_down(self) -> FormattedStr<S>;
+    /// Rotate characters by 90° clockwise.
+    fn rotated(self) -> FormattedStr<S>;
+    /// Print characters as after initializing the printer, switching off
+    /// the styles of the enclosing text. Styles added afterwards apply.
+    fn regular(self) -> FormattedStr<S>;
+}
+
+impl<S> FormattedStr<S> {
+    /// Wrap `text` without changing its style.
+    ///
+    /// Use this to nest formatted strings, the inner style is applied
+    /// on top of the outer one.
+    pub fn new(text: S) -> Self {
+        FormattedStr {
+            text,
+            font: None,
+            emphasized: None,
+            underline: None,
+            reverse: None,
+            width: None,
+            height: None,
+            justification: None,
+            line_spacing: None,
+            char_spacing: None,
+            upside_down: None,
+            rotated: None,
+        }
+    }
+
+    /// Apply this style on top of `outer`.
+    fn apply(&self, outer: &PrintState) -> PrintState {
+        PrintState {
+            font: self.font.unwrap_or(outer.font),
+            emphasized: self.emphasized.unwrap_or(outer.emphasized),
+            underline: self.underline.unwrap_or(outer.underline),
+            reverse: self.reverse.unwrap_or(outer.reverse),
+            width: self.width.unwrap_or(outer.width),
+            height: self.height.unwrap_or(outer.height),
+            justification: self.justification.unwrap_or(outer.justification),
+            line_spacing: self.line_spacing.or(outer.line_spacing),
+            char_spacing: self.char_spacing.unwrap_or(outer.char_spacing),
+            upside_down: self.upside_down.unwrap_or(outer.upside_down),
+            rotated: self.rotated.unwrap_or(outer.rotated),
+        }
+    }
 }
 
-impl<S: fmt::Display> fmt::Display for FormattedStr<S> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        use EscPosCmd::*;
-        macro_rules! maybe_print {
-            ($q:expr, $val:expr) => {
-                if $q {
-                    $val
-                } else {
-                    EscPosCmd::Text("")
-                }
-            };
-        }
-        write!(
-            f,
-            "{}{}{}{}{}{}{}",
-            SelectPrintMode(self.mode),
-            maybe_print!(self.reverse_color, SelectReversePrinting(true)),
-            maybe_print!(
-                self.underline != UnderlineMode::Off,
-                SelectUnderlineMode(self.underline)
-            ),
-            self.text,
-            maybe_print!(
-                self.underline != UnderlineMode::Off,
-                SelectUnderlineMode(UnderlineMode::Off)
-            ),
-            maybe_print!(self.reverse_color, SelectReversePrinting(false)),
-            SelectPrintMode(PrintMode::empty()),
-        )
+/// Only the settings that differ from the enclosing style are changed and
+/// restored afterwards.
+impl<S: Styled> Styled for FormattedStr<S> {
+    fn write_styled<B: BufMut>(&self, w: &mut StyleWriter<'_, B>) {
+        let state = self.apply(w.current());
+        w.push(state);
+        self.text.write_styled(w);
+        w.pop();
     }
 }
 
+impl Styled for str {
+    fn write_styled<B: BufMut>(&self, w: &mut StyleWriter<'_, B>) {
+        let _ = w.write_str(self);
+    }
+}
+
+impl Styled for String {
+    fn write_styled<B: BufMut>(&self, w: &mut StyleWriter<'_, B>) {
+        self.as_str().write_styled(w)
+    }
+}
+
+impl<T: Styled + ?Sized> Styled for &T {
+    fn write_styled<B: BufMut>(&self, w: &mut StyleWriter<'_, B>) {
+        (**self).write_styled(w)
+    }
+}
+
+/// Tuples write their parts one after another, so parts of a
+/// [`FormattedStr`] can be styled differently.
+macro_rules! impl_styled_for_tuple {
+    ($($part:ident),+) => {
+        impl<$($part: Styled),+> Styled for ($($part,)+) {
+            #[allow(non_snake_case)]
+            fn write_styled<B: BufMut>(&self, w: &mut StyleWriter<'_, B>) {
+                let ($($part,)+) = self;
+                $($part.write_styled(w);)+
+            }
+        }
+    };
+}
+
+impl_styled_for_tuple!(T1, T2);
+impl_styled_for_tuple!(T1, T2, T3);
+impl_styled_for_tuple!(T1, T2, T3, T4);
+impl_styled_for_tuple!(T1, T2, T3, T4, T5);
+
 impl<'s> FmtStr<&'s str> for &'s str {
     fn emph(self) -> FormattedStr<&'s str> {
-        FormattedStr {
-            mode: PrintMode::EMPHASIZED,
-            text: self,
-            ..Default::default()
-        }
+        FormattedStr::from(self).emph()
     }
 
     fn higher(self) -> FormattedStr<&'s str> {
-        FormattedStr {
-            mode: PrintMode::DOUBLE_HEIGHT,
-            text: self,
-            ..Default::default()
-        }
+        FormattedStr::from(self).higher()
     }
 
     fn wider(self) -> FormattedStr<&'s str> {
-        FormattedStr {
-            mode: PrintMode::DOUBLE_WIDTH,
-            text: self,
-            ..Default::default()
-        }
+        FormattedStr::from(self).wider()
     }
 
     fn underline(self) -> FormattedStr<&'s str> {
-        FormattedStr {
-            underline: UnderlineMode::OneDot,
-            text: self,
-            ..Default::default()
-        }
+        FormattedStr::from(self).underline()
     }
 
     fn reverse(self) -> FormattedStr<&'s str> {
-        FormattedStr {
-            reverse_color: true,
-            text: self,
-            ..Default::default()
-        }
+        FormattedStr::from(self).reverse()
     }
 
     fn small(self) -> FormattedStr<&'s str> {
-        FormattedStr {
-            mode: PrintMode::FONT_B,
-            text: self,
-            ..Default::default()
-        }
+        FormattedStr::from(self).small()
+    }
+
+    fn scale(self, width: u8, height: u8) -> FormattedStr<&'s str> {
+        FormattedStr::from(self).scale(width, height)
+    }
+
+    fn centered(self) -> FormattedStr<&'s str> {
+        FormattedStr::from(self).centered()
+    }
+
+    fn right(self) -> FormattedStr<&'s str> {
+        FormattedStr::from(self).right()
+    }
+
+    fn line_spacing(self, dots: u8) -> FormattedStr<&'s str> {
+        FormattedStr::from(self).line_spacing(dots)
+    }
+
+    fn char_spacing(self, dots: u8) -> FormattedStr<&'s str> {
+        FormattedStr::from(self).char_spacing(dots)
+    }
+
+    fn upside_down(self) -> FormattedStr<&'s str> {
+        FormattedStr::from(self).upside_down()
+    }
+
+    fn rotated(self) -> FormattedStr<&'s str> {
+        FormattedStr::from(self).rotated()
+    }
+
+    fn regular(self) -> FormattedStr<&'s str> {
+        FormattedStr::from(self).regular()
+    }
+}
+
+impl<'s> From<&'s str> for FormattedStr<&'s str> {
+    fn from(text: &'s str) -> Self {
+        FormattedStr::new(text)
     }
 }
 
 impl<S> FmtStr<S> for FormattedStr<S> {
     fn emph(self) -> FormattedStr<S> {
         FormattedStr {
-            mode: self.mode | PrintMode::EMPHASIZED,
+            emphasized: Some(true),
             ..self
         }
     }
 
     fn higher(self) -> FormattedStr<S> {
         FormattedStr {
-            mode: self.mode | PrintMode::DOUBLE_HEIGHT,
+            height: Some(2),
             ..self
         }
     }
 
     fn wider(self) -> FormattedStr<S> {
         FormattedStr {
-            mode: self.mode | PrintMode::DOUBLE_WIDTH,
+            width: Some(2),
             ..self
         }
     }
 
     fn underline(self) -> FormattedStr<S> {
         FormattedStr {
-            underline: UnderlineMode::OneDot,
+            underline: Some(UnderlineMode::OneDot),
             ..self
         }
     }
 
     fn reverse(self) -> FormattedStr<S> {
         FormattedStr {
-            reverse_color: true,
+            reverse: Some(true),
             ..self
         }
     }
 
     fn small(self) -> FormattedStr<S> {
         FormattedStr {
-            mode: self.mode | PrintMode::FONT_B,
+            font: Some(Font::B),
+            ..self
+        }
+    }
+
+    fn scale(self, width: u8, height: u8) -> FormattedStr<S> {
+        FormattedStr {
+            width: Some(width.clamp(1, 8)),
+            height: Some(height.clamp(1, 8)),
+            ..self
+        }
+    }
+
+    fn centered(self) -> FormattedStr<S> {
+        FormattedStr {
+            justification: Some(Justification::Center),
             ..self
         }
     }
+
+    fn right(self) -> FormattedStr<S> {
+        FormattedStr {
+            justification: Some(Justification::Right),
+            ..self
+        }
+    }
+
+    fn line_spacing(self, dots: u8) -> FormattedStr<S> {
+        FormattedStr {
+            line_spacing: Some(dots),
+            ..self
+        }
+    }
+
+    fn char_spacing(self, dots: u8) -> FormattedStr<S> {
+        FormattedStr {
+            char_spacing: Some(dots),
+            ..self
+        }
+    }
+
+    fn upside_down(self) -> FormattedStr<S> {
+        FormattedStr {
+            upside_down: Some(true),
+            ..self
+        }
+    }
+
+    fn rotated(self) -> FormattedStr<S> {
+        FormattedStr {
+            rotated: Some(true),
+            ..self
+        }
+    }
+
+    fn regular(self) -> FormattedStr<S> {
+        FormattedStr {
+            font: Some(Font::A),
+            emphasized: Some(false),
+            underline: Some(UnderlineMode::Off),
+            reverse: Some(false),
+            width: Some(1),
+            height: Some(1),
+            char_spacing: Some(0),
+            upside_down: Some(false),
+            rotated: Some(false),
+            ..self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Encode, Encoder, EscPosCmd, MemoryTransport, Printer};
+
+    /// Bytes written by printing `text` after initializing the printer.
+    fn styled<S: Styled>(text: S) -> Vec<u8> {
+        let mut printer = Printer::new(MemoryTransport::new()).unwrap();
+        printer.transport_mut().take();
+        printer.write_styled(&text).unwrap();
+        printer.into_transport().into_inner()
+    }
+
+    #[test]
+    fn nested_styles_restore_the_outer_one() {
+        let text = FormattedStr::new(("a", "b".underline(), "c")).emph();
+        assert_eq!(styled(text), b"\x1bE\x01a\x1b-\x01b\x1b-\x00c\x1bE\x00");
+    }
+
+    #[test]
+    fn inner_styles_are_only_changed_where_they_differ() {
+        let inner = FormattedStr::new(("b", "c".emph().wider())).underline();
+        let text = FormattedStr::new(("a", inner, "d")).emph();
+        assert_eq!(
+            styled(text),
+            b"\x1bE\x01a\x1b-\x01b\x1d!\x10c\x1d!\x00\x1b-\x00d\x1bE\x00"
+        );
+    }
+
+    #[test]
+    fn inner_styles_override_outer_ones() {
+        let text = FormattedStr::new(("a", "b".regular().underline(), "c"))
+            .emph()
+            .reverse();
+        assert_eq!(
+            styled(text),
+            b"\x1bE\x01\x1dB\x01a\x1bE\x00\x1b-\x01\x1dB\x00b\
+              \x1bE\x01\x1b-\x00\x1dB\x01c\x1bE\x00\x1dB\x00"
+        );
+    }
+
+    #[test]
+    fn spacing_above_0x7f_is_sent_as_is() {
+        let mut encoder = Encoder::new();
+        let mut bytes = Vec::new();
+        let mut w = StyleWriter::new(&mut bytes, &mut encoder);
+        w.write_styled(&"a".line_spacing(200).char_spacing(255));
+        w.finish();
+        let contains = |cmd: EscPosCmd<'_>| {
+            let cmd = cmd.encode();
+            bytes.windows(cmd.len()).any(|window| window == &cmd[..])
+        };
+        assert!(contains(EscPosCmd::SetLineSpacing(200)));
+        assert!(contains(EscPosCmd::SetRightSideSpacing(255)));
+        assert!(contains(EscPosCmd::SelectDefaultLineSpacing));
+    }
 }
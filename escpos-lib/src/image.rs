@@ -0,0 +1,511 @@
+use std::{error, fmt};
+
+use super::chars;
+
+/// Printable width of 80mm paper on the TM-T88III in dots.
+pub const PAPER_WIDTH_DOTS: usize = 512;
+
+/// Number of rows of the `ESC *` 24-dot bit image mode.
+pub(crate) const BIT_IMAGE_BAND: usize = 24;
+
+/// 4x4 Bayer threshold matrix used for ordered dithering.
+const BAYER_4X4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// Layout of the pixel buffer given to [`Image::new`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelFormat {
+    /// One byte per pixel, `0` is black.
+    Gray8,
+    /// Four bytes per pixel, transparent pixels are treated as white paper.
+    Rgba8,
+}
+
+/// Algorithm used to reduce an image to black and white dots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dithering {
+    /// Pixels darker than the given level are printed.
+    Threshold(u8),
+    FloydSteinberg,
+    Atkinson,
+    /// Ordered dithering with a 4x4 Bayer matrix.
+    Bayer,
+}
+
+/// Command used to transmit the image to the printer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RasterMode {
+    /// `GS v 0` raster bit image.
+    Raster,
+    /// `ESC *` 24-dot double density bit image, for printers without raster support.
+    BitImage,
+}
+
+/// How to fit an image onto the paper.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scaling {
+    /// Keep the original size, cropping the right side if necessary.
+    None,
+    /// Only scale down images wider than the paper.
+    ShrinkToFit,
+    /// Always scale to the full paper width.
+    FitWidth,
+}
+
+/// Settings for [`Printer::print_image`](crate::Printer::print_image).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageOptions {
+    /// Printable width in dots.
+    pub paper_width: usize,
+    pub scaling: Scaling,
+    pub dithering: Dithering,
+    pub mode: RasterMode,
+    /// Maximum number of rows sent in one raster command, so the
+    /// printer's receive buffer does not overflow.
+    pub band_height: usize,
+}
+
+/// Grayscale image, `0` is black, `255` is white.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    luma: Vec<u8>,
+}
+
+/// Black and white image, one bit per dot, rows are packed MSB first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bitmap {
+    width: usize,
+    height: usize,
+    data: Vec<u8>,
+}
+
+/// Reasons an image cannot be created.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImageError {
+    /// Width or height is zero.
+    Empty,
+    /// The buffer size does not match the dimensions and pixel format.
+    InvalidBufferSize { expected: usize, found: usize },
+}
+
+impl Image {
+    /// Create an image from a pixel buffer.
+    pub fn new(
+        width: usize,
+        height: usize,
+        format: PixelFormat,
+        pixels: &[u8],
+    ) -> Result<Self, ImageError> {
+        if width == 0 || height == 0 {
+            return Err(ImageError::Empty);
+        }
+        let bytes_per_pixel = match format {
+            PixelFormat::Gray8 => 1,
+            PixelFormat::Rgba8 => 4,
+        };
+        let expected = width * height * bytes_per_pixel;
+        if pixels.len() != expected {
+            return Err(ImageError::InvalidBufferSize {
+                expected,
+                found: pixels.len(),
+            });
+        }
+        let luma = match format {
+            PixelFormat::Gray8 => pixels.to_vec(),
+            PixelFormat::Rgba8 => pixels
+                .chunks_exact(4)
+                .map(|px| {
+                    // ITU-R BT.601 luma, blended onto white paper
+                    let luma =
+                        (299 * px[0] as u32 + 587 * px[1] as u32 + 114 * px[2] as u32) / 1000;
+                    let alpha = px[3] as u32;
+                    ((luma * alpha + 255 * (255 - alpha)) / 255) as u8
+                })
+                .collect(),
+        };
+        Ok(Image {
+            width,
+            height,
+            luma,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Scale the image to `width`, keeping the aspect ratio.
+    ///
+    /// Each target pixel averages the source area it covers.
+    pub fn scale_to_width(&self, width: usize) -> Image {
+        let width = width.max(1);
+        if width == self.width {
+            return self.clone();
+        }
+        let height = ((self.height * width + self.width / 2) / self.width).max(1);
+        let mut luma = Vec::with_capacity(width * height);
+        for y in 0..height {
+            let y0 = y * self.height / height;
+            let y1 = ((y + 1) * self.height / height).max(y0 + 1);
+            for x in 0..width {
+                let x0 = x * self.width / width;
+                let x1 = ((x + 1) * self.width / width).max(x0 + 1);
+                let mut sum = 0_u32;
+                for sy in y0..y1 {
+                    let row = &self.luma[sy * self.width..];
+                    sum += row[x0..x1].iter().map(|&l| l as u32).sum::<u32>();
+                }
+                luma.push((sum / ((y1 - y0) * (x1 - x0)) as u32) as u8);
+            }
+        }
+        Image {
+            width,
+            height,
+            luma,
+        }
+    }
+
+    /// Reduce the image to black and white using `dithering`.
+    pub fn dither(&self, dithering: Dithering) -> Bitmap {
+        let mut bitmap = Bitmap::new(self.width, self.height);
+        match dithering {
+            Dithering::Threshold(level) => {
+                for y in 0..self.height {
+                    for x in 0..self.width {
+                        bitmap.set(x, y, self.luma[y * self.width + x] < level);
+                    }
+                }
+            }
+            Dithering::Bayer => {
+                for y in 0..self.height {
+                    for x in 0..self.width {
+                        let level = BAYER_4X4[y % 4][x % 4] as u32 * 16 + 8;
+                        bitmap.set(x, y, (self.luma[y * self.width + x] as u32) < level);
+                    }
+                }
+            }
+            Dithering::FloydSteinberg => {
+                self.diffuse(
+                    &mut bitmap,
+                    16,
+                    &[(1, 0, 7), (-1, 1, 3), (0, 1, 5), (1, 1, 1)],
+                );
+            }
+            Dithering::Atkinson => {
+                self.diffuse(
+                    &mut bitmap,
+                    8,
+                    &[
+                        (1, 0, 1),
+                        (2, 0, 1),
+                        (-1, 1, 1),
+                        (0, 1, 1),
+                        (1, 1, 1),
+                        (0, 2, 1),
+                    ],
+                );
+            }
+        }
+        bitmap
+    }
+
+    /// Error diffusion dithering.
+    ///
+    /// The quantization error of every pixel is spread to its neighbours
+    /// at `(dx, dy)` with `weight / divisor`.
+    fn diffuse(&self, bitmap: &mut Bitmap, divisor: i32, neighbours: &[(isize, usize, i32)]) {
+        let mut values: Vec<i32> = self.luma.iter().map(|&l| l as i32).collect();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let old = values[y * self.width + x];
+                let black = old < 128;
+                let error = if black { old } else { old - 255 };
+                bitmap.set(x, y, black);
+                for &(dx, dy, weight) in neighbours {
+                    let nx = x as isize + dx;
+                    let ny = y + dy;
+                    if nx >= 0 && (nx as usize) < self.width && ny < self.height {
+                        values[ny * self.width + nx as usize] += error * weight / divisor;
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Bitmap {
+    /// Create a white bitmap.
+    pub fn new(width: usize, height: usize) -> Self {
+        Bitmap {
+            width,
+            height,
+            data: vec![0; width.div_ceil(8) * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn bytes_per_row(&self) -> usize {
+        self.width.div_ceil(8)
+    }
+
+    /// Packed dots of row `y`, MSB first.
+    pub fn row(&self, y: usize) -> &[u8] {
+        let len = self.bytes_per_row();
+        &self.data[y * len..(y + 1) * len]
+    }
+
+    /// Whether the dot at `(x, y)` is black.
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        self.data[y * self.bytes_per_row() + x / 8] & (0x80 >> (x % 8)) != 0
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, black: bool) {
+        let idx = y * self.bytes_per_row() + x / 8;
+        if black {
+            self.data[idx] |= 0x80 >> (x % 8);
+        } else {
+            self.data[idx] &= !(0x80 >> (x % 8));
+        }
+    }
+
+    /// Cut the bitmap down to at most `width` dots.
+    pub fn crop_width(&self, width: usize) -> Bitmap {
+        if width >= self.width {
+            return self.clone();
+        }
+        let mut cropped = Bitmap::new(width, self.height);
+        for y in 0..self.height {
+            for x in 0..width {
+                cropped.set(x, y, self.get(x, y));
+            }
+        }
+        cropped
+    }
+
+    /// Scale down to `width` by `height` dots.
+    ///
+    /// A dot is black if any of the dots it covers is, so thin lines
+    /// do not disappear.
+    pub(crate) fn shrink(&self, width: usize, height: usize) -> Bitmap {
+        let span = |i: usize, from: usize, to: usize| {
+            let start = i * from / to;
+            start..((i + 1) * from / to).max(start + 1)
+        };
+        let mut shrunk = Bitmap::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let black = span(y, self.height, height)
+                    .any(|sy| span(x, self.width, width).any(|sx| self.get(sx, sy)));
+                shrunk.set(x, y, black);
+            }
+        }
+        shrunk
+    }
+
+    /// Encode as `GS v 0` raster commands of at most `band_height` rows each.
+    pub(crate) fn encode_raster(&self, band_height: usize) -> Vec<u8> {
+        let bytes_per_row = self.bytes_per_row();
+        let band_height = band_height.max(1);
+        let mut bytes = Vec::with_capacity(self.data.len() + 8 * (self.height / band_height + 1));
+        for y0 in (0..self.height).step_by(band_height) {
+            let rows = band_height.min(self.height - y0);
+            bytes.extend_from_slice(&[
+                chars::GS as u8,
+                b'v',
+                b'0',
+                0,
+                bytes_per_row as u8,
+                (bytes_per_row >> 8) as u8,
+                rows as u8,
+                (rows >> 8) as u8,
+            ]);
+            bytes.extend_from_slice(&self.data[y0 * bytes_per_row..(y0 + rows) * bytes_per_row]);
+        }
+        bytes
+    }
+
+    /// Encode as `ESC *` 24-dot double density bit image, one band per line.
+    pub(crate) fn encode_bit_image(&self) -> Vec<u8> {
+        let esc = chars::ESC as u8;
+        // Line spacing must match the band height to avoid gaps
+        let mut bytes = vec![esc, b'3', BIT_IMAGE_BAND as u8];
+        for y0 in (0..self.height).step_by(BIT_IMAGE_BAND) {
+            bytes.extend_from_slice(&self.encode_bit_image_band(y0));
+            bytes.push(chars::LF as u8);
+        }
+        bytes.extend_from_slice(&[esc, b'2']);
+        bytes
+    }
+
+    /// Encode the `ESC *` command for the 24 rows starting at `y0`.
+    pub(crate) fn encode_bit_image_band(&self, y0: usize) -> Vec<u8> {
+        let mut bytes = vec![
+            chars::ESC as u8,
+            b'*',
+            33,
+            self.width as u8,
+            (self.width >> 8) as u8,
+        ];
+        for x in 0..self.width {
+            for slice in 0..BIT_IMAGE_BAND / 8 {
+                let mut column = 0_u8;
+                for bit in 0..8 {
+                    let y = y0 + slice * 8 + bit;
+                    if y < self.height && self.get(x, y) {
+                        column |= 0x80 >> bit;
+                    }
+                }
+                bytes.push(column);
+            }
+        }
+        bytes
+    }
+}
+
+#[cfg(feature = "preview")]
+impl Bitmap {
+    /// Enlarge every dot to `sx` by `sy` dots.
+    pub(crate) fn scale(&self, sx: usize, sy: usize) -> Bitmap {
+        let mut scaled = Bitmap::new(self.width * sx, self.height * sy);
+        for y in 0..scaled.height {
+            for x in 0..scaled.width {
+                scaled.set(x, y, self.get(x / sx, y / sy));
+            }
+        }
+        scaled
+    }
+
+    /// Add white rows at the bottom until the bitmap is `height` dots high.
+    pub(crate) fn extend_height(&mut self, height: usize) {
+        if height > self.height {
+            self.height = height;
+            self.data.resize(self.bytes_per_row() * height, 0);
+        }
+    }
+
+    /// Draw the black dots of `src` with its top left corner at `(x, y)`.
+    ///
+    /// Dots right of the bitmap are dropped, the height grows as needed.
+    pub(crate) fn draw(&mut self, src: &Bitmap, x: usize, y: usize) {
+        self.extend_height(y + src.height);
+        for sy in 0..src.height {
+            for sx in 0..src.width.min(self.width.saturating_sub(x)) {
+                if src.get(sx, sy) {
+                    self.set(x + sx, y + sy, true);
+                }
+            }
+        }
+    }
+
+    /// Drop rows below `height`.
+    pub(crate) fn crop_height(&mut self, height: usize) {
+        if height < self.height {
+            self.height = height;
+            self.data.truncate(self.bytes_per_row() * height);
+        }
+    }
+
+    /// Rotate by 90° clockwise.
+    pub(crate) fn rotate_cw(&self) -> Bitmap {
+        let mut rotated = Bitmap::new(self.height, self.width);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                rotated.set(self.height - 1 - y, x, self.get(x, y));
+            }
+        }
+        rotated
+    }
+
+    /// Rotate by 180° in place.
+    pub(crate) fn rotate_half(&mut self) {
+        let mut rotated = Bitmap::new(self.width, self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                rotated.set(self.width - 1 - x, self.height - 1 - y, self.get(x, y));
+            }
+        }
+        *self = rotated;
+    }
+
+    /// Swap black and white dots.
+    pub(crate) fn invert(&mut self) {
+        for byte in &mut self.data {
+            *byte = !*byte;
+        }
+        // Keep the padding bits of every row white
+        let rest = self.width % 8;
+        if rest > 0 {
+            let mask = 0xff_u8 << (8 - rest);
+            let len = self.bytes_per_row();
+            for row in self.data.chunks_mut(len) {
+                row[len - 1] &= mask;
+            }
+        }
+    }
+}
+
+impl ImageOptions {
+    /// Scale and dither `image` and encode it using these options.
+    pub(crate) fn encode(&self, image: &Image) -> Vec<u8> {
+        let scaled;
+        let image = match self.scaling {
+            Scaling::FitWidth => {
+                scaled = image.scale_to_width(self.paper_width);
+                &scaled
+            }
+            Scaling::ShrinkToFit if image.width() > self.paper_width => {
+                scaled = image.scale_to_width(self.paper_width);
+                &scaled
+            }
+            _ => image,
+        };
+        let bitmap = image.dither(self.dithering).crop_width(self.paper_width);
+        self.encode_bitmap(&bitmap)
+    }
+
+    pub(crate) fn encode_bitmap(&self, bitmap: &Bitmap) -> Vec<u8> {
+        match self.mode {
+            RasterMode::Raster => bitmap.encode_raster(self.band_height),
+            RasterMode::BitImage => bitmap.encode_bit_image(),
+        }
+    }
+}
+
+impl Default for ImageOptions {
+    fn default() -> Self {
+        ImageOptions {
+            paper_width: PAPER_WIDTH_DOTS,
+            scaling: Scaling::ShrinkToFit,
+            dithering: Dithering::FloydSteinberg,
+            mode: RasterMode::Raster,
+            band_height: 48,
+        }
+    }
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImageError::Empty => write!(f, "image has no pixels"),
+            ImageError::InvalidBufferSize { expected, found } => write!(
+                f,
+                "expected {} bytes of pixel data, found {}",
+                expected, found
+            ),
+        }
+    }
+}
+
+impl error::Error for ImageError {}
@@ -0,0 +1,111 @@
+use super::{cmds::CutMode, Document, Encode, Encoder, Error, EscPosCmd, PrinterProfile};
+
+/// How the paper is cut after every copy of a [`PrintJob`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum CutPolicy {
+    /// The cut preferred by the printer, see [`PrinterProfile::cut_mode`].
+    /// Printers without a cutter only feed the paper.
+    #[default]
+    Preferred,
+    /// Always use this cut, fail if the printer lacks it.
+    Cut(CutMode),
+    /// Feed the paper past the cutter, e.g. to tear it off by hand.
+    FeedOnly,
+}
+
+/// A complete receipt, printed with
+/// [`Printer::print_job`](crate::Printer::print_job).
+///
+/// All copies are encoded into a single buffer and written at once, so
+/// the output of different jobs never interleaves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrintJob {
+    header: Option<Document>,
+    body: Document,
+    footer: Option<Document>,
+    copies: usize,
+    cut: CutPolicy,
+    feed: u8,
+}
+
+impl PrintJob {
+    /// Create a job printing `body` once, cut as the printer prefers.
+    pub fn new(body: Document) -> Self {
+        PrintJob {
+            header: None,
+            body,
+            footer: None,
+            copies: 1,
+            cut: CutPolicy::default(),
+            feed: 0,
+        }
+    }
+
+    /// Print `header` above the body of every copy.
+    pub fn header(&mut self, header: Document) -> &mut Self {
+        self.header = Some(header);
+        self
+    }
+
+    /// Print `footer` below the body of every copy.
+    pub fn footer(&mut self, footer: Document) -> &mut Self {
+        self.footer = Some(footer);
+        self
+    }
+
+    /// Print the receipt `copies` times, each one cut separately.
+    /// At least one copy is printed.
+    pub fn copies(&mut self, copies: usize) -> &mut Self {
+        self.copies = copies.max(1);
+        self
+    }
+
+    /// Cut every copy according to `cut`, [`CutPolicy::Preferred`] by
+    /// default.
+    pub fn cut(&mut self, cut: CutPolicy) -> &mut Self {
+        self.cut = cut;
+        self
+    }
+
+    /// Feed the paper `dots` beyond the cutting position before cutting,
+    /// 0 by default.
+    pub fn feed(&mut self, dots: u8) -> &mut Self {
+        self.feed = dots;
+        self
+    }
+
+    /// Encode all copies, including the cuts.
+    pub(crate) fn encode(
+        &self,
+        encoder: &mut Encoder,
+        profile: &PrinterProfile,
+    ) -> Result<Vec<u8>, Error> {
+        let cut = match self.cut {
+            CutPolicy::Preferred => profile.cut_mode(),
+            CutPolicy::Cut(mode) => {
+                profile.check_cut(mode)?;
+                Some(mode)
+            }
+            CutPolicy::FeedOnly => None,
+        };
+        let mut bytes = Vec::new();
+        for _ in 0..self.copies {
+            let documents = self
+                .header
+                .iter()
+                .chain(Some(&self.body))
+                .chain(&self.footer);
+            for document in documents {
+                bytes.extend_from_slice(&document.encode(encoder, profile)?);
+            }
+            match cut {
+                // The printer feeds to the cutting position by itself
+                Some(mode) => EscPosCmd::FeedAndCutPaper(mode, self.feed).encode_to(&mut bytes),
+                None => profile.encode_tear_off(self.feed, &mut bytes),
+            }
+        }
+        Ok(bytes)
+    }
+}
@@ -1,40 +1,329 @@
-use serialport::SerialPort;
-
-use std::io::Result as IoResult;
+use std::{io::ErrorKind, time::Duration};
 
+#[cfg(feature = "async")]
+mod async_printer;
+mod barcode;
 mod cmds;
+mod code_pages;
+mod config;
+mod decode;
+mod document;
+mod drawer;
+mod emoji;
+mod encoding;
+mod error;
 mod format;
+mod image;
+mod job;
+mod markdown;
+mod nv;
+mod page;
+#[cfg(feature = "preview")]
+mod preview;
+mod profile;
+mod qr;
+mod sanitize;
+mod status;
+mod style;
+mod transport;
+mod user_chars;
 
-use cmds::{CutMode, EscPosCmd};
-pub use format::{FmtStr, FormattedStr};
+#[cfg(feature = "async")]
+pub use async_printer::{AsyncPrinter, Drained, DEFAULT_DRAIN_TIMEOUT};
+pub use barcode::{BarCode, BarCodeError, BarCodeStyle, BarCodeSystem, HriFont, HriPosition};
+pub use cmds::{
+    CharCodeTable, CutMode, Encode, EscPosCmd, Font, Justification, PaperSensors, PrintMode,
+    UnderlineMode,
+};
+pub use config::{FlowControl, PrinterConfig};
+pub use decode::{decode, dump, DecodedCmd, Decoder};
+pub use document::{Document, Table, TextStyle, FONT_A_COLUMNS, FONT_B_COLUMNS};
+pub use drawer::{DrawerPin, Pulse, PulseError};
+pub use encoding::{decode_byte, Encoder, DEFAULT_CODE_TABLES};
+pub use error::{Error, Result};
+pub use format::{FmtStr, FormattedStr, Styled};
+pub use image::{
+    Bitmap, Dithering, Image, ImageError, ImageOptions, PixelFormat, RasterMode, Scaling,
+    PAPER_WIDTH_DOTS,
+};
+pub use job::{CutPolicy, PrintJob};
+pub use nv::{NvError, NvKey, NV_WRITES_PER_DAY};
+pub use page::{Page, PageDirection};
+#[cfg(feature = "preview")]
+pub use preview::{Emulator, PreviewTransport};
+pub use profile::{PrinterProfile, ProfileError, BUILTIN_PROFILES};
+pub use qr::{QrCode, QrError, QrErrorCorrection, QrModel, WifiAuth, QR_MAX_DATA_LEN};
+pub use sanitize::SanitizePolicy;
+pub use status::{AsbFlags, PrinterStatus, StatusKind, TransmitStatusKind, DEFAULT_STATUS_TIMEOUT};
+pub use style::{PrintState, StyleWriter};
+pub use transport::{
+    BufferedTransport, FileTransport, MemoryTransport, SerialTransport, TcpTransport, Transport,
+    RAW_TCP_PORT,
+};
+pub use user_chars::{emoji_glyph, UserCharError, UserChars};
 
 /// Special characters
 mod chars {
+    pub const EOT: char = '\x04';
     pub const ESC: char = '\x1b';
+    pub const FF: char = '\x0c';
     pub const LF: char = '\x0a';
+    pub const DLE: char = '\x10';
+    pub const FS: char = '\x1c';
     pub const GS: char = '\x1d';
     pub const INITIALIZE_PRINTER: char = '@';
 }
 
-pub struct Printer<P>
+pub struct Printer<T>
 where
-    P: SerialPort,
+    T: Transport,
 {
-    port: P,
+    port: T,
+    status_timeout: Duration,
+    encoder: Encoder,
+    profile: PrinterProfile,
+    sanitize: SanitizePolicy,
 }
 
-impl<P> Printer<P>
+impl<T> Printer<T>
 where
-    P: SerialPort,
+    T: Transport,
 {
-    pub fn new(port: P) -> IoResult<Self> {
-        let mut printer = Printer { port };
+    /// Initialize a TM-T88III.
+    pub fn new(port: T) -> Result<Self> {
+        Self::with_profile(port, PrinterProfile::tm_t88iii())
+    }
+
+    /// Initialize a printer described by `profile`.
+    ///
+    /// Text is only encoded for the code tables of the profile.
+    pub fn with_profile(port: T, profile: PrinterProfile) -> Result<Self> {
+        let mut printer = Printer {
+            port,
+            status_timeout: DEFAULT_STATUS_TIMEOUT,
+            encoder: Encoder::with_tables(&profile.code_tables),
+            profile,
+            sanitize: SanitizePolicy::default(),
+        };
         printer.exec(EscPosCmd::InitializePrinter)?;
         Ok(printer)
     }
 
-    pub fn print_test_page(&mut self) -> IoResult<()> {
-        let header = format!("{}\nDies ist ein Test\n", " TEST ".reverse());
+    /// Capabilities of the printer.
+    pub fn profile(&self) -> &PrinterProfile {
+        &self.profile
+    }
+
+    /// Create an empty document fitting the paper.
+    pub fn document(&self) -> Document {
+        Document::for_profile(&self.profile)
+    }
+
+    /// Access the underlying transport.
+    pub fn transport(&self) -> &T {
+        &self.port
+    }
+
+    /// Access the underlying transport mutably.
+    pub fn transport_mut(&mut self) -> &mut T {
+        &mut self.port
+    }
+
+    pub fn into_transport(self) -> T {
+        self.port
+    }
+
+    /// Switch the printer to another code table.
+    ///
+    /// Text is still encoded automatically, this only changes the
+    /// table tried first.
+    pub fn set_code_table(&mut self, table: CharCodeTable) -> Result<()> {
+        self.exec(EscPosCmd::SelectCharCodeTable(table))
+    }
+
+    /// Restrict automatic code table switching to `tables`, i.e. to
+    /// the ones installed on the printer.
+    pub fn set_code_tables(&mut self, tables: &[CharCodeTable]) {
+        let current = self.encoder.current();
+        self.encoder = Encoder::with_tables(tables);
+        self.encoder.set_current(current);
+    }
+
+    /// Download glyphs for characters missing from the code tables.
+    ///
+    /// Replaces the previous definitions. Text containing the characters
+    /// is printed with these glyphs afterwards, in font A and B.
+    /// Font A is selected when done.
+    pub fn define_user_chars(&mut self, chars: &UserChars) -> Result<()> {
+        if !self.profile.user_chars {
+            return Err(self.profile.unsupported("user-defined characters"));
+        }
+        for font in [Font::B, Font::A].iter() {
+            if self.profile.supports_font(*font) {
+                self.exec(EscPosCmd::SelectFont(*font))?;
+                self.port.write_all(&chars.encode(*font))?;
+            }
+        }
+        self.encoder.set_user_chars(chars);
+        Ok(())
+    }
+
+    /// Set how long to wait for the printer to answer status requests.
+    pub fn set_status_timeout(&mut self, timeout: Duration) {
+        self.status_timeout = timeout;
+    }
+
+    /// Query the complete real-time status of the printer.
+    ///
+    /// Real-time requests are answered immediately, even while the
+    /// printer is busy or offline.
+    pub fn status(&mut self) -> Result<PrinterStatus> {
+        Ok(PrinterStatus::query(&mut self.port, self.status_timeout)?)
+    }
+
+    /// Fail with [`Error::NotReady`] if the printer reports that it
+    /// cannot print, e.g. because it is out of paper.
+    ///
+    /// Succeeds without knowing if the transport cannot read responses
+    /// or the printer does not answer, e.g. because it only has a receive
+    /// line connected. Printing is attempted anyway in these cases.
+    pub fn check_ready(&mut self) -> Result<()> {
+        match self.status() {
+            Ok(status) if !status.is_ready() => Err(Error::NotReady(status)),
+            Err(Error::Io(why)) if why.kind() == ErrorKind::Unsupported => Ok(()),
+            Err(Error::Timeout) => Ok(()),
+            res => res.map(|_| ()),
+        }
+    }
+
+    /// Send a single `DLE EOT` request and return the raw response.
+    pub fn real_time_status(&mut self, kind: StatusKind) -> Result<u8> {
+        Ok(status::request(&mut self.port, kind, self.status_timeout)?)
+    }
+
+    /// Send a `GS r` request and return the raw response.
+    ///
+    /// The printer answers after processing everything sent before,
+    /// so this can be used to wait for previous data to be printed.
+    pub fn transmitted_status(&mut self, kind: TransmitStatusKind) -> Result<u8> {
+        Ok(status::request_transmitted(
+            &mut self.port,
+            kind,
+            self.status_timeout,
+        )?)
+    }
+
+    /// Flush everything written so far and wait up to `timeout` for the
+    /// printer to process it.
+    ///
+    /// Transports that cannot read responses and printers that never
+    /// answer, e.g. because only their receive line is wired, are only
+    /// flushed.
+    pub fn drain(&mut self, timeout: Duration) -> Result<()> {
+        match status::request_transmitted(&mut self.port, TransmitStatusKind::PaperSensor, timeout)
+        {
+            Err(why) if matches!(why.kind(), ErrorKind::Unsupported | ErrorKind::TimedOut) => {
+                Ok(self.port.flush()?)
+            }
+            res => Ok(res.map(|_| ())?),
+        }
+    }
+
+    /// Enable automatic status back for the given events.
+    ///
+    /// Use [`AsbFlags::empty`] to disable it again.
+    pub fn enable_asb(&mut self, flags: AsbFlags) -> Result<()> {
+        Ok(self.port.write_all(&flags.encode())?)
+    }
+
+    /// Wait for the next automatic status back message.
+    pub fn read_asb(&mut self) -> Result<PrinterStatus> {
+        let bytes = status::read_asb(&mut self.port, self.status_timeout)?;
+        Ok(PrinterStatus::from_asb(bytes))
+    }
+
+    /// Set the line spacing in dots, `None` selects the default spacing.
+    pub fn set_line_spacing(&mut self, dots: Option<u8>) -> Result<()> {
+        match dots {
+            Some(dots) => self.exec(EscPosCmd::SetLineSpacing(dots)),
+            None => self.exec(EscPosCmd::SelectDefaultLineSpacing),
+        }
+    }
+
+    /// Add `dots` of space to the right of every character.
+    pub fn set_char_spacing(&mut self, dots: u8) -> Result<()> {
+        self.exec(EscPosCmd::SetRightSideSpacing(dots))
+    }
+
+    /// Set the left margin in dots.
+    ///
+    /// Takes effect at the beginning of the next line.
+    pub fn set_left_margin(&mut self, dots: u16) -> Result<()> {
+        if usize::from(dots) >= self.profile.paper_width {
+            let why = format!("left margin of {} dots exceeds the paper", dots);
+            return Err(Error::InvalidInput(why));
+        }
+        self.exec(EscPosCmd::SetLeftMargin(dots))
+    }
+
+    /// Set the width of the printable area, starting at the left margin.
+    ///
+    /// Takes effect at the beginning of the next line.
+    pub fn set_print_area_width(&mut self, dots: u16) -> Result<()> {
+        if dots == 0 || usize::from(dots) > self.profile.paper_width {
+            let why = format!("print area of {} dots does not fit the paper", dots);
+            return Err(Error::InvalidInput(why));
+        }
+        self.exec(EscPosCmd::SetPrintAreaWidth(dots))
+    }
+
+    /// Select the sensors whose paper-end signals are output on the
+    /// parallel interface.
+    pub fn set_paper_end_signals(&mut self, sensors: PaperSensors) -> Result<()> {
+        self.exec(EscPosCmd::SelectPaperSensorsOutput(sensors))
+    }
+
+    /// Select the sensors that stop printing when they detect the paper end.
+    ///
+    /// Printing always stops at the paper end, so only
+    /// [`PaperSensors::NEAR_END`] has an effect.
+    pub fn set_paper_stop_sensors(&mut self, sensors: PaperSensors) -> Result<()> {
+        self.exec(EscPosCmd::SelectPaperSensorsStop(sensors))
+    }
+
+    /// Enable or disable the buttons on the printer's panel, e.g. to
+    /// prevent wasting paper with the feed button.
+    pub fn enable_panel_buttons(&mut self, enable: bool) -> Result<()> {
+        self.exec(EscPosCmd::EnablePanelButtons(enable))
+    }
+
+    /// Send a pulse to the drawer kick-out connector, after everything
+    /// sent before has been printed.
+    pub fn pulse(&mut self, pulse: &Pulse) -> Result<()> {
+        let bytes = pulse.encode()?;
+        Ok(self.port.write_all(&bytes)?)
+    }
+
+    /// Send a pulse to the drawer kick-out connector immediately,
+    /// even while the printer is busy.
+    ///
+    /// Only the pin and on time of `pulse` are used.
+    pub fn real_time_pulse(&mut self, pulse: &Pulse) -> Result<()> {
+        let bytes = pulse.encode_real_time()?;
+        Ok(self.port.write_all(&bytes)?)
+    }
+
+    /// Query whether pin 3 of the drawer kick-out connector is high.
+    ///
+    /// Whether this means the drawer is open or closed depends on the drawer.
+    pub fn drawer_kick(&mut self) -> Result<bool> {
+        let mut status = PrinterStatus::default();
+        let byte = self.real_time_status(StatusKind::Printer)?;
+        status.update(StatusKind::Printer, byte);
+        Ok(status.drawer_kick)
+    }
+
+    pub fn print_test_page(&mut self) -> Result<()> {
         let format_strings = vec![
             "Emphasized".emph(),
             "Higher".higher(),
@@ -58,47 +347,315 @@ where
             "Underlined Small".underline().small(),
             "Reversed Small".reverse().small(),
         ];
-        self.write(header)?;
-        for string in format_strings {
-            self.write(&format!(" - {}\n", string))?
+        let mut bytes = Vec::new();
+        let mut w = StyleWriter::new(&mut bytes, &mut self.encoder);
+        w.write_styled(&" TEST ".reverse())
+            .write_styled("\nDies ist ein Test\n");
+        for string in &format_strings {
+            w.write_styled(" - ")
+                .write_styled(string)
+                .write_styled("\n");
         }
-        self.write(&format!("\n{}\n", "CHARS".wider()))?;
+        w.write_styled("\n")
+            .write_styled(&"CHARS".wider())
+            .write_styled("\n");
         let numbers = "0123456789";
         let chars = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
         let german = "äöüß";
-        for c in numbers.chars() {
-            self.write(&format!(" {}", c))?;
+        for line in &[numbers, chars, german] {
+            for c in line.chars() {
+                w.write_styled(&format!(" {}", c));
+            }
+            w.write_styled("\n");
         }
-        self.write("\n")?;
-        for c in chars.chars() {
-            self.write(&format!(" {}", c))?;
+        w.write_styled("\n")
+            .write_styled(&"BARCODES".wider())
+            .write_styled("\n");
+        w.finish();
+        self.port.write_all(&bytes)?;
+        let style = BarCodeStyle {
+            height: 60,
+            hri_position: HriPosition::Below,
+            ..Default::default()
+        };
+        let barcodes = [
+            BarCode::new(BarCodeSystem::Ean13, "4006381333931"),
+            BarCode::new(BarCodeSystem::Code39, "*TEST*"),
+            BarCode::code128("Test 128"),
+        ];
+        for barcode in barcodes.iter().flatten() {
+            if !self.profile.supports_barcode(barcode.system()) {
+                continue;
+            }
+            self.print_barcode(barcode, &style)?;
+            self.write("\n")?;
         }
-        self.write("\n")?;
-        for c in german.chars() {
-            self.write(&format!(" {}", c))?;
+        self.cut()
+    }
+
+    /// Write text, encoded for the printer's code tables.
+    pub fn write<S: Into<String>>(&mut self, text: S) -> Result<()> {
+        let mut encoder = self.encoder.clone();
+        let bytes = encoder.encode(&text.into());
+        self.write_encoded(&bytes, encoder)
+    }
+
+    /// Write text together with the commands for its style, e.g. a
+    /// [`FormattedStr`].
+    pub fn write_styled<S: Styled + ?Sized>(&mut self, text: &S) -> Result<()> {
+        let mut encoder = self.encoder.clone();
+        let mut bytes = Vec::new();
+        let mut w = StyleWriter::new(&mut bytes, &mut encoder);
+        w.write_styled(text);
+        w.finish();
+        self.write_encoded(&bytes, encoder)
+    }
+
+    /// Print `text` like [`Printer::write`], but fail with
+    /// [`Error::Encoding`] instead of transliterating characters the
+    /// printer lacks.
+    pub fn write_exact(&mut self, text: &str) -> Result<()> {
+        let mut encoder = self.encoder.clone();
+        let bytes = encoder.encode_exact(text)?;
+        self.write_encoded(&bytes, encoder)
+    }
+
+    /// Set how [`Printer::write_untrusted`] handles control characters.
+    pub fn set_sanitize_policy(&mut self, policy: SanitizePolicy) {
+        self.sanitize = policy;
+    }
+
+    /// Print text from an untrusted source like [`Printer::write`].
+    ///
+    /// Control characters are handled according to the sanitize policy,
+    /// so the text cannot contain commands, e.g. to cut the paper, pulse
+    /// the drawer or write the NV memory.
+    pub fn write_untrusted(&mut self, text: &str) -> Result<()> {
+        let mut encoder = self.encoder.clone();
+        let bytes = encoder.encode(&self.sanitize.sanitize(text));
+        self.write_encoded(&bytes, encoder)
+    }
+
+    /// Print a laid out document.
+    pub fn print_document(&mut self, document: &Document) -> Result<()> {
+        let mut encoder = self.encoder.clone();
+        let bytes = document.encode(&mut encoder, &self.profile)?;
+        self.write_encoded(&bytes, encoder)
+    }
+
+    /// Print all copies of `job` with a single write.
+    pub fn print_job(&mut self, job: &PrintJob) -> Result<()> {
+        let mut encoder = self.encoder.clone();
+        let bytes = job.encode(&mut encoder, &self.profile)?;
+        self.write_encoded(&bytes, encoder)
+    }
+
+    /// Print a page mode canvas and return to standard mode.
+    pub fn print_page(&mut self, page: &Page) -> Result<()> {
+        if !self.profile.page_mode {
+            return Err(self.profile.unsupported("page mode"));
         }
-        self.write("\n")?;
-        self.exec(EscPosCmd::PrintAndFeedLines(4))?;
-        self.exec(EscPosCmd::CutPaper(CutMode::Full))?;
-        Ok(())
+        if page.right() > self.profile.paper_width {
+            let why = "page print area exceeds the paper";
+            return Err(Error::InvalidInput(why.into()));
+        }
+        let mut encoder = self.encoder.clone();
+        let bytes = page.encode(&mut encoder);
+        self.write_encoded(&bytes, encoder)
     }
 
-    pub fn write<S: Into<String>>(&mut self, text: S) -> IoResult<()> {
-        write!(self.port, "{}", EscPosCmd::Text(&text.into()))
+    /// Write `bytes` encoded with a copy of the encoder and keep the
+    /// code table switches of that copy only once they were sent.
+    ///
+    /// A job failing to encode or write thus never leaves the encoder
+    /// assuming a table the printer did not receive.
+    fn write_encoded(&mut self, bytes: &[u8], encoder: Encoder) -> Result<()> {
+        self.port.write_all(bytes)?;
+        self.encoder = encoder;
+        Ok(())
     }
 
-    pub fn write_and_cut<S: Into<String>>(&mut self, text: S) -> IoResult<()> {
+    pub fn write_and_cut<S: Into<String>>(&mut self, text: S) -> Result<()> {
         self.write(text)?;
-        self.exec(EscPosCmd::PrintAndFeedLines(4))?;
-        self.exec(EscPosCmd::CutPaper(CutMode::Full))
+        self.cut()
+    }
+
+    /// Feed the paper past the cutter and cut it.
+    ///
+    /// Printers without a cutter only feed the paper.
+    pub fn cut(&mut self) -> Result<()> {
+        self.exec(EscPosCmd::PrintAndFeedLines(self.profile.cut_feed_lines))?;
+        match self.profile.cut_mode() {
+            Some(mode) => self.exec(EscPosCmd::CutPaper(mode)),
+            None => Ok(()),
+        }
+    }
+
+    /// Store `bitmaps` as NV bit images with `FS q`, numbered from 1.
+    ///
+    /// This replaces all previously stored bit images. NV memory only
+    /// survives a limited number of writes, see [`NV_WRITES_PER_DAY`],
+    /// so store images once instead of before every print.
+    pub fn define_nv_bit_images(&mut self, bitmaps: &[Bitmap]) -> Result<()> {
+        if !self.profile.nv_bit_image {
+            return Err(self.profile.unsupported("NV bit images"));
+        }
+        self.check_nv_width(bitmaps)?;
+        let bytes = nv::encode_bit_images(bitmaps)?;
+        Ok(self.port.write_all(&bytes)?)
+    }
+
+    /// Store `bitmap` as NV graphic with `GS ( L`, replacing any graphic
+    /// with the same key.
+    ///
+    /// NV memory only survives a limited number of writes, see
+    /// [`NV_WRITES_PER_DAY`]. Check [`Printer::nv_graphic_keys`] before
+    /// storing a graphic again.
+    pub fn define_nv_graphic(&mut self, key: NvKey, bitmap: &Bitmap) -> Result<()> {
+        if !self.profile.nv_graphics {
+            return Err(self.profile.unsupported("NV graphics"));
+        }
+        self.check_nv_width(std::slice::from_ref(bitmap))?;
+        let bytes = key.encode_define(bitmap)?;
+        Ok(self.port.write_all(&bytes)?)
     }
 
-    pub fn exec(&mut self, cmd: EscPosCmd) -> IoResult<()> {
-        write!(self.port, "{}", cmd)
+    /// Query the keys of all stored NV graphics.
+    pub fn nv_graphic_keys(&mut self) -> Result<Vec<NvKey>> {
+        if !self.profile.nv_graphics {
+            return Err(self.profile.unsupported("NV graphics"));
+        }
+        Ok(nv::request_graphic_keys(
+            &mut self.port,
+            self.status_timeout,
+        )?)
+    }
+
+    /// Delete a stored NV graphic.
+    pub fn delete_nv_graphic(&mut self, key: NvKey) -> Result<()> {
+        if !self.profile.nv_graphics {
+            return Err(self.profile.unsupported("NV graphics"));
+        }
+        let bytes = key.encode_delete()?;
+        Ok(self.port.write_all(&bytes)?)
+    }
+
+    /// Print an image stored in NV memory.
+    pub fn print_nv(&mut self, key: NvKey) -> Result<()> {
+        let supported = match key {
+            NvKey::BitImage(_) => self.profile.nv_bit_image,
+            NvKey::Graphic(_) => self.profile.nv_graphics,
+        };
+        if !supported {
+            return Err(self.profile.unsupported("printing NV images"));
+        }
+        Ok(self.port.write_all(&key.encode_print())?)
+    }
+
+    /// Refuse NV images wider than the paper, as they are not cropped.
+    fn check_nv_width(&self, bitmaps: &[Bitmap]) -> Result<()> {
+        if bitmaps
+            .iter()
+            .any(|bitmap| bitmap.width() > self.profile.paper_width)
+        {
+            let why = "NV image is wider than the paper";
+            return Err(Error::InvalidInput(why.into()));
+        }
+        Ok(())
+    }
+
+    /// Print a bar code.
+    ///
+    /// The style is validated before anything is sent to the printer.
+    pub fn print_barcode(&mut self, barcode: &BarCode, style: &BarCodeStyle) -> Result<()> {
+        if !self.profile.supports_barcode(barcode.system()) {
+            let what = format!("bar code system {:?}", barcode.system());
+            return Err(self.profile.unsupported(&what));
+        }
+        let bytes = barcode.encode(style)?;
+        Ok(self.port.write_all(&bytes)?)
+    }
+
+    /// Print a QR code using the printer's native 2D symbol commands.
+    ///
+    /// Falls back to a raster image if the profile lacks QR support.
+    pub fn print_qr(&mut self, qr: &QrCode) -> Result<()> {
+        let bytes = self.profile.encode_qr(qr)?;
+        Ok(self.port.write_all(&bytes)?)
+    }
+
+    /// Print a QR code as a raster image.
+    ///
+    /// Use this for printers like the TM-T88III whose firmware lacks
+    /// native QR code support. The module size is reduced if the code
+    /// would be wider than the paper.
+    pub fn print_qr_raster(&mut self, qr: &QrCode) -> Result<()> {
+        let bytes = self.profile.encode_qr_raster(qr)?;
+        Ok(self.port.write_all(&bytes)?)
+    }
+
+    /// Print an image, scaled and dithered according to `options`.
+    ///
+    /// The options are limited to what the profile supports, e.g.
+    /// bit images are used if raster images are not available.
+    pub fn print_image(&mut self, image: &Image, options: &ImageOptions) -> Result<()> {
+        let options = self.profile.image_options(options)?;
+        Ok(self.port.write_all(&options.encode(image))?)
+    }
+
+    /// Print a black and white bitmap as is, cropped to the paper width.
+    ///
+    /// Only the mode and band height of `options` are used.
+    pub fn print_bitmap(&mut self, bitmap: &Bitmap, options: &ImageOptions) -> Result<()> {
+        let bytes = self.profile.encode_bitmap(bitmap, options)?;
+        Ok(self.port.write_all(&bytes)?)
+    }
+
+    /// Send a single command.
+    ///
+    /// Fails with [`Error::Unsupported`] for fonts, code tables and cuts
+    /// the printer lacks. Cuts on printers without a cutter only feed the
+    /// paper, so it can be torn off.
+    pub fn exec(&mut self, cmd: EscPosCmd) -> Result<()> {
+        match cmd {
+            EscPosCmd::InitializePrinter => {
+                // Also clears the user-defined characters
+                self.encoder.set_current(CharCodeTable::PC437);
+                self.encoder.set_user_chars(&UserChars::new());
+            }
+            EscPosCmd::SelectFont(font) if !self.profile.supports_font(font) => {
+                return Err(self.profile.unsupported(&format!("font {:?}", font)));
+            }
+            EscPosCmd::SelectCharCodeTable(table) => {
+                if !self.profile.supports_code_table(table) {
+                    return Err(self.profile.unsupported(&format!("code table {:?}", table)));
+                }
+                self.encoder.set_current(table)
+            }
+            EscPosCmd::CutPaper(_) | EscPosCmd::FeedAndCutPaper(..)
+                if self.profile.cut_mode().is_none() =>
+            {
+                let dots = match cmd {
+                    EscPosCmd::FeedAndCutPaper(_, dots) => dots,
+                    _ => 0,
+                };
+                let mut bytes = Vec::new();
+                self.profile.encode_tear_off(dots, &mut bytes);
+                return Ok(self.port.write_all(&bytes)?);
+            }
+            EscPosCmd::CutPaper(mode) | EscPosCmd::FeedAndCutPaper(mode, _) => {
+                self.profile.check_cut(mode)?
+            }
+            _ => {}
+        }
+        Ok(self.port.write_all(&cmd.encode())?)
     }
 }
 
 /// Escape a string to print safely
+///
+/// Replaces all control characters except LF with `?`, see
+/// [`SanitizePolicy`] for other options.
 pub fn escape(raw: &str) -> String {
-    raw.replace(chars::ESC, "?").replace(chars::GS, "?")
+    SanitizePolicy::default().sanitize(raw)
 }
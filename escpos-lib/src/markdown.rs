@@ -0,0 +1,241 @@
+use super::{Document, Font, Justification, QrCode, TextStyle};
+
+/// Module size of the QR codes printed for links.
+const LINK_QR_MODULE_SIZE: u8 = 4;
+
+impl Document {
+    /// Add text formatted with a subset of Markdown.
+    ///
+    /// Supported are:
+    /// - `**bold**` and `__bold__`, printed emphasized
+    /// - `*italic*` and `_italic_`, printed underlined
+    /// - `` `code` `` and fenced code blocks, printed in font B
+    /// - `#` headings, the first two levels in double size
+    /// - lists starting with `-`, `*`, `+` or a number
+    /// - horizontal rules like `---`
+    /// - `[links](url)`, numbered and listed with a QR code at the end
+    ///
+    /// Everything else is printed as is. The justification is left
+    /// afterwards.
+    pub fn markdown(&mut self, text: &str) -> &mut Self {
+        let mut links = Vec::new();
+        let mut paragraph: Vec<&str> = Vec::new();
+        let mut code_block: Option<Vec<&str>> = None;
+        // Blank lines between blocks are kept, but not at the start
+        let mut gap = false;
+        let mut empty = true;
+        for line in text.lines() {
+            let trimmed = line.trim();
+            if let Some(ref mut code) = code_block {
+                if trimmed.starts_with("```") {
+                    self.code_block(code);
+                    code_block = None;
+                } else {
+                    code.push(line);
+                }
+                continue;
+            }
+            if trimmed.is_empty() {
+                self.paragraph(&mut paragraph, &mut links);
+                gap = !empty;
+                continue;
+            }
+            if gap && paragraph.is_empty() {
+                self.feed(1);
+                gap = false;
+            }
+            empty = false;
+            if trimmed.starts_with("```") {
+                self.paragraph(&mut paragraph, &mut links);
+                code_block = Some(Vec::new());
+            } else if is_rule(trimmed) {
+                self.paragraph(&mut paragraph, &mut links);
+                self.rule();
+            } else if let Some((level, title)) = heading(trimmed) {
+                self.paragraph(&mut paragraph, &mut links);
+                let style = TextStyle {
+                    emphasized: true,
+                    double_width: level == 1,
+                    double_height: level <= 2,
+                    ..Default::default()
+                };
+                self.rich_text(&inline(title, style, &mut links));
+            } else if let Some((marker, item)) = list_item(trimmed) {
+                self.paragraph(&mut paragraph, &mut links);
+                let indent = (line.len() - line.trim_start().len()) / 2 * 2;
+                let prefix = format!("{}{} ", "\u{a0}".repeat(indent), marker);
+                let mut spans = vec![(TextStyle::default(), prefix)];
+                spans.extend(inline(item, TextStyle::default(), &mut links));
+                self.rich_text(&spans);
+            } else {
+                paragraph.push(trimmed);
+            }
+        }
+        self.paragraph(&mut paragraph, &mut links);
+        if let Some(code) = code_block {
+            self.code_block(&code);
+        }
+        if !links.is_empty() {
+            self.rule();
+        }
+        for (idx, url) in links.iter().enumerate() {
+            self.styled(TextStyle::small(), &format!("[{}] {}", idx + 1, url));
+            let code = QrCode::new(url).and_then(|code| code.module_size(LINK_QR_MODULE_SIZE));
+            if let Ok(code) = code {
+                self.justify(Justification::Center)
+                    .qr(code)
+                    .justify(Justification::Left);
+            }
+        }
+        self.justify(Justification::Left)
+    }
+
+    /// Add the collected lines of a paragraph and clear them.
+    fn paragraph(&mut self, lines: &mut Vec<&str>, links: &mut Vec<String>) {
+        if !lines.is_empty() {
+            let text = lines.join(" ");
+            self.rich_text(&inline(&text, TextStyle::default(), links));
+            lines.clear();
+        }
+    }
+
+    /// Add a code block in font B, keeping the indentation.
+    fn code_block(&mut self, lines: &[&str]) {
+        for line in lines {
+            let line = line.trim_end().replace(' ', "\u{a0}");
+            self.rich_text(&[(TextStyle::small(), line)]);
+        }
+    }
+}
+
+/// Parse inline formatting of `text` into styled parts.
+///
+/// Links are replaced by their label and footnote number, the URL
+/// is appended to `links`.
+fn inline(text: &str, base: TextStyle, links: &mut Vec<String>) -> Vec<(TextStyle, String)> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut spans = Vec::new();
+    let mut current = String::new();
+    let (mut bold, mut italic, mut code) = (false, false, false);
+    let style_of = |bold: bool, italic: bool, code: bool| TextStyle {
+        emphasized: base.emphasized || bold,
+        underline: base.underline || italic,
+        font: if code { Font::B } else { base.font },
+        ..base
+    };
+    let mut idx = 0;
+    while idx < chars.len() {
+        let style = style_of(bold, italic, code);
+        let c = chars[idx];
+        let prev = if idx > 0 { chars.get(idx - 1) } else { None };
+        let next = chars.get(idx + 1);
+        let rest = &chars[idx + 1..];
+        match c {
+            '`' if code || contains(rest, &['`']) => {
+                push_span(&mut spans, &mut current, style);
+                code = !code;
+            }
+            _ if code => current.push(c),
+            '\\' if next.is_some_and(|n| n.is_ascii_punctuation()) => {
+                current.push(chars[idx + 1]);
+                idx += 1;
+            }
+            '*' | '_' if next == Some(&c) => {
+                if bold || contains(&rest[1..], &[c, c]) {
+                    push_span(&mut spans, &mut current, style);
+                    bold = !bold;
+                } else {
+                    current.push(c);
+                    current.push(c);
+                }
+                idx += 1;
+            }
+            // Underscores inside of words are no markup, i.e. snake_case
+            '_' if prev.is_some_and(|p| p.is_alphanumeric())
+                && next.is_some_and(|n| n.is_alphanumeric()) =>
+            {
+                current.push(c)
+            }
+            '*' | '_' if italic || contains(rest, &[c]) => {
+                push_span(&mut spans, &mut current, style);
+                italic = !italic;
+            }
+            '[' => match link(rest) {
+                Some((label, url, len)) => {
+                    links.push(url);
+                    current.push_str(&format!("{}[{}]", label, links.len()));
+                    idx += len;
+                }
+                None => current.push(c),
+            },
+            _ => current.push(c),
+        }
+        idx += 1;
+    }
+    push_span(&mut spans, &mut current, style_of(bold, italic, code));
+    spans
+}
+
+/// Move `current` into a new span using `style`, if it is not empty.
+fn push_span(spans: &mut Vec<(TextStyle, String)>, current: &mut String, style: TextStyle) {
+    if !current.is_empty() {
+        spans.push((style, std::mem::take(current)));
+    }
+}
+
+/// Whether `pattern` occurs in `chars`.
+fn contains(chars: &[char], pattern: &[char]) -> bool {
+    chars.windows(pattern.len()).any(|window| window == pattern)
+}
+
+/// Parse `label](url)` following an opening bracket.
+///
+/// Returns label, URL and the number of characters consumed.
+fn link(chars: &[char]) -> Option<(String, String, usize)> {
+    let close = chars.iter().position(|&c| c == ']')?;
+    if chars.get(close + 1) != Some(&'(') {
+        return None;
+    }
+    let end = close + 2 + chars[close + 2..].iter().position(|&c| c == ')')?;
+    let label = chars[..close].iter().collect();
+    let url = chars[close + 2..end].iter().collect();
+    Some((label, url, end + 1))
+}
+
+/// Whether `line` consists of at least three `-`, `*` or `_`.
+fn is_rule(line: &str) -> bool {
+    let mut chars = line.chars().filter(|c| !c.is_whitespace());
+    match chars.next() {
+        Some(first @ ('-' | '*' | '_')) => {
+            line.chars().filter(|&c| c == first).count() >= 3 && chars.all(|c| c == first)
+        }
+        _ => false,
+    }
+}
+
+/// Parse `# Title` into level and title.
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    let title = line[level..].strip_prefix(' ')?;
+    if (1..=6).contains(&level) {
+        Some((level, title.trim()))
+    } else {
+        None
+    }
+}
+
+/// Parse `- item` or `1. item` into marker and item.
+fn list_item(line: &str) -> Option<(&str, &str)> {
+    for bullet in &["- ", "* ", "+ "] {
+        if let Some(item) = line.strip_prefix(bullet) {
+            return Some(("-", item));
+        }
+    }
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    let rest = &line[digits..];
+    if digits > 0 && (rest.starts_with(". ") || rest.starts_with(") ")) {
+        Some((&line[..digits + 1], &rest[2..]))
+    } else {
+        None
+    }
+}
@@ -0,0 +1,240 @@
+use std::{
+    convert::TryFrom,
+    error, fmt,
+    io::{self, Result as IoResult},
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use super::{chars, status, Bitmap, Transport};
+
+/// Number of NV memory writes per day Epson recommends not to exceed.
+///
+/// Flash memory only survives a limited number of write cycles, so
+/// images should be stored once and not on every start.
+pub const NV_WRITES_PER_DAY: usize = 10;
+
+/// Maximum size of an `FS q` image in dots.
+const MAX_BIT_IMAGE_SIZE: (usize, usize) = (1023 * 8, 288 * 8);
+/// Maximum size of a `GS ( L` graphic in dots.
+const MAX_GRAPHIC_SIZE: (usize, usize) = (8192, 2304);
+/// `m` parameter of the `GS ( L` graphics functions.
+const GRAPHICS: u8 = 48;
+
+/// Image stored in the printer's non-volatile memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NvKey {
+    /// Image defined with `FS q`, numbered from 1 in the order of
+    /// definition.
+    BitImage(u8),
+    /// Graphic defined with `GS ( L`, identified by two printable ASCII
+    /// characters.
+    Graphic([u8; 2]),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NvError {
+    /// Keys are numbers from 1 to 255 or two printable ASCII characters.
+    InvalidKey(String),
+    /// The image is empty or larger than the command allows.
+    InvalidSize { width: usize, height: usize },
+    /// `FS q` defines 1 to 255 images.
+    InvalidCount(usize),
+    /// `FS q` images can only be defined all at once.
+    NotAGraphic(NvKey),
+}
+
+impl NvKey {
+    /// Key of a `GS ( L` graphic, e.g. `"LG"`.
+    pub fn graphic(code: &str) -> Result<Self, NvError> {
+        match code.as_bytes() {
+            &[c1, c2] if is_key_char(c1) && is_key_char(c2) => Ok(NvKey::Graphic([c1, c2])),
+            _ => Err(NvError::InvalidKey(code.to_string())),
+        }
+    }
+
+    /// Encode the command printing the image in its original size.
+    pub(crate) fn encode_print(self) -> Vec<u8> {
+        match self {
+            NvKey::BitImage(n) => vec![chars::FS as u8, b'p', n, 0],
+            NvKey::Graphic([c1, c2]) => graphics_function(69, &[c1, c2, 1, 1]),
+        }
+    }
+
+    /// Encode the `GS ( L` command deleting the graphic.
+    pub(crate) fn encode_delete(self) -> Result<Vec<u8>, NvError> {
+        match self {
+            NvKey::Graphic([c1, c2]) => Ok(graphics_function(66, &[c1, c2])),
+            key => Err(NvError::NotAGraphic(key)),
+        }
+    }
+
+    /// Encode the `GS ( L` command storing `bitmap` under this key.
+    pub(crate) fn encode_define(self, bitmap: &Bitmap) -> Result<Vec<u8>, NvError> {
+        let [c1, c2] = match self {
+            NvKey::Graphic(code) => code,
+            key => return Err(NvError::NotAGraphic(key)),
+        };
+        check_size(bitmap, MAX_GRAPHIC_SIZE)?;
+        let [xl, xh] = (bitmap.width() as u16).to_le_bytes();
+        let [yl, yh] = (bitmap.height() as u16).to_le_bytes();
+        // Raster format, one color
+        let mut params = vec![48, c1, c2, 1, xl, xh, yl, yh, 49];
+        for y in 0..bitmap.height() {
+            params.extend_from_slice(bitmap.row(y));
+        }
+        Ok(graphics_function(67, &params))
+    }
+}
+
+/// Encode the `FS q` command replacing all NV bit images with `bitmaps`.
+///
+/// Images are padded to multiples of 8 dots.
+pub(crate) fn encode_bit_images(bitmaps: &[Bitmap]) -> Result<Vec<u8>, NvError> {
+    if bitmaps.is_empty() || bitmaps.len() > 255 {
+        return Err(NvError::InvalidCount(bitmaps.len()));
+    }
+    let mut bytes = vec![chars::FS as u8, b'q', bitmaps.len() as u8];
+    for bitmap in bitmaps {
+        check_size(bitmap, MAX_BIT_IMAGE_SIZE)?;
+        let (width, height) = (bitmap.width().div_ceil(8), bitmap.height().div_ceil(8));
+        bytes.extend_from_slice(&(width as u16).to_le_bytes());
+        bytes.extend_from_slice(&(height as u16).to_le_bytes());
+        // Columns of vertical bytes, top to bottom
+        for x in 0..width * 8 {
+            for y0 in (0..height * 8).step_by(8) {
+                let mut byte = 0;
+                for y in y0..y0 + 8 {
+                    if x < bitmap.width() && y < bitmap.height() && bitmap.get(x, y) {
+                        byte |= 0x80 >> (y - y0);
+                    }
+                }
+                bytes.push(byte);
+            }
+        }
+    }
+    Ok(bytes)
+}
+
+/// Request the key codes of all NV graphics and wait for the response.
+///
+/// The printer sends the list in blocks, every further block is
+/// requested with `ACK`.
+pub(crate) fn request_graphic_keys<T: Transport>(
+    port: &mut T,
+    timeout: Duration,
+) -> IoResult<Vec<NvKey>> {
+    port.write_all(&graphics_function(64, b"KC"))?;
+    port.flush()?;
+    let deadline = Instant::now() + timeout;
+    let mut keys = Vec::new();
+    loop {
+        let remaining = || deadline.saturating_duration_since(Instant::now());
+        // Header and identifier of the response
+        status::read_matching(port, remaining(), |byte| byte == 0x37)?;
+        let identifier = status::read_matching(port, remaining(), |_| true)?;
+        let status = status::read_matching(port, remaining(), |_| true)?;
+        if identifier != 0x72 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "unexpected response to key code request",
+            ));
+        }
+        loop {
+            let c1 = status::read_matching(port, remaining(), |_| true)?;
+            if c1 == 0 {
+                break;
+            }
+            let c2 = status::read_matching(port, remaining(), |_| true)?;
+            keys.push(NvKey::Graphic([c1, c2]));
+        }
+        match status {
+            // More key codes follow
+            0x40 => {
+                port.write_all(&[0x06])?;
+                port.flush()?;
+            }
+            _ => return Ok(keys),
+        }
+    }
+}
+
+/// Encode the `GS ( L` graphics function `fn` with `params`.
+///
+/// Uses `GS 8 L` if the parameters exceed 64 KiB.
+fn graphics_function(function: u8, params: &[u8]) -> Vec<u8> {
+    let len = params.len() + 2;
+    let mut bytes = Vec::with_capacity(len + 7);
+    match u16::try_from(len) {
+        Ok(len) => {
+            bytes.extend_from_slice(&[chars::GS as u8, b'(', b'L']);
+            bytes.extend_from_slice(&len.to_le_bytes());
+        }
+        Err(_) => {
+            bytes.extend_from_slice(&[chars::GS as u8, b'8', b'L']);
+            bytes.extend_from_slice(&(len as u32).to_le_bytes());
+        }
+    }
+    bytes.extend_from_slice(&[GRAPHICS, function]);
+    bytes.extend_from_slice(params);
+    bytes
+}
+
+fn is_key_char(c: u8) -> bool {
+    (0x20..=0x7e).contains(&c)
+}
+
+fn check_size(bitmap: &Bitmap, (max_width, max_height): (usize, usize)) -> Result<(), NvError> {
+    let (width, height) = (bitmap.width(), bitmap.height());
+    if width == 0 || height == 0 || width > max_width || height > max_height {
+        return Err(NvError::InvalidSize { width, height });
+    }
+    Ok(())
+}
+
+impl FromStr for NvKey {
+    type Err = NvError;
+
+    /// Parse a bit image number like `"1"` or a graphic key like `"LG"`.
+    fn from_str(key: &str) -> Result<Self, Self::Err> {
+        match key.parse::<u8>() {
+            Ok(0) => Err(NvError::InvalidKey(key.to_string())),
+            Ok(n) => Ok(NvKey::BitImage(n)),
+            Err(_) => NvKey::graphic(key),
+        }
+    }
+}
+
+impl fmt::Display for NvKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NvKey::BitImage(n) => write!(f, "{}", n),
+            NvKey::Graphic([c1, c2]) => write!(f, "{}{}", *c1 as char, *c2 as char),
+        }
+    }
+}
+
+impl fmt::Display for NvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NvError::InvalidKey(key) => write!(
+                f,
+                "invalid NV key {:?}, expected a number from 1 to 255 or two ASCII characters",
+                key
+            ),
+            NvError::InvalidSize { width, height } => {
+                write!(f, "invalid NV image size {}x{} dots", width, height)
+            }
+            NvError::InvalidCount(count) => {
+                write!(
+                    f,
+                    "cannot define {} NV bit images, 1 to 255 are allowed",
+                    count
+                )
+            }
+            NvError::NotAGraphic(key) => write!(f, "NV bit image {} is not a graphic", key),
+        }
+    }
+}
+
+impl error::Error for NvError {}
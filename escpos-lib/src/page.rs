@@ -0,0 +1,207 @@
+use super::{
+    chars, cmds::Justification, image::BIT_IMAGE_BAND, Bitmap, Encoder, PrintState, TextStyle,
+};
+
+/// Direction of text in page mode, named after the starting corner.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PageDirection {
+    /// Left to right, starting at the upper left.
+    #[default]
+    UpperLeft,
+    /// Bottom to top, starting at the lower left.
+    LowerLeft,
+    /// Right to left, starting at the lower right.
+    LowerRight,
+    /// Top to bottom, starting at the upper right.
+    UpperRight,
+}
+
+/// Canvas for page mode.
+///
+/// Text and images are placed at absolute positions inside the print
+/// area and printed all at once with
+/// [`Printer::print_page`](crate::Printer::print_page). Positions are
+/// given in dots relative to the starting corner of the
+/// [`PageDirection`], `y` is the baseline of text and the top of images.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Page {
+    x: u16,
+    y: u16,
+    width: u16,
+    height: u16,
+    direction: PageDirection,
+    style: TextStyle,
+    items: Vec<PageItem>,
+}
+
+/// Part of a page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PageItem {
+    /// `ESC $` and `GS $`
+    MoveTo {
+        x: u16,
+        y: u16,
+    },
+    /// `ESC \`
+    Skip(i16),
+    Text {
+        style: TextStyle,
+        text: String,
+    },
+    Bitmap {
+        x: u16,
+        y: u16,
+        bitmap: Bitmap,
+    },
+    /// `ESC FF`
+    Print,
+}
+
+impl Page {
+    /// Create a page with a print area of `width` by `height` dots.
+    pub fn new(width: u16, height: u16) -> Self {
+        Page {
+            x: 0,
+            y: 0,
+            width,
+            height,
+            direction: PageDirection::default(),
+            style: TextStyle::default(),
+            items: Vec::new(),
+        }
+    }
+
+    /// Move the print area, its size is not changed.
+    pub fn origin(&mut self, x: u16, y: u16) -> &mut Self {
+        self.x = x;
+        self.y = y;
+        self
+    }
+
+    pub fn direction(&mut self, direction: PageDirection) -> &mut Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Use `style` for text added from now on.
+    pub fn style(&mut self, style: TextStyle) -> &mut Self {
+        self.style = style;
+        self
+    }
+
+    /// Add text starting at `(x, y)`.
+    pub fn text_at(&mut self, x: u16, y: u16, text: &str) -> &mut Self {
+        self.items.push(PageItem::MoveTo { x, y });
+        self.text(text)
+    }
+
+    /// Add text ending at `(x, y)`.
+    pub fn text_right_at(&mut self, x: u16, y: u16, text: &str) -> &mut Self {
+        let width = text.chars().count() * self.style.char_width();
+        let x = (x as usize).saturating_sub(width) as u16;
+        self.text_at(x, y, text)
+    }
+
+    /// Add text after the previous one.
+    pub fn text(&mut self, text: &str) -> &mut Self {
+        self.items.push(PageItem::Text {
+            style: self.style,
+            text: text.to_string(),
+        });
+        self
+    }
+
+    /// Move the print position by `dots`, to the left if negative.
+    pub fn skip(&mut self, dots: i16) -> &mut Self {
+        self.items.push(PageItem::Skip(dots));
+        self
+    }
+
+    /// Add a bitmap with its top left corner at `(x, y)`.
+    pub fn bitmap_at(&mut self, x: u16, y: u16, bitmap: &Bitmap) -> &mut Self {
+        self.items.push(PageItem::Bitmap {
+            x,
+            y,
+            bitmap: bitmap.clone(),
+        });
+        self
+    }
+
+    /// Print everything added so far and stay in page mode, e.g. to
+    /// print multiple copies.
+    pub fn print_copy(&mut self) -> &mut Self {
+        self.items.push(PageItem::Print);
+        self
+    }
+
+    /// Width of the print area in the direction of the text.
+    pub fn width(&self) -> u16 {
+        match self.direction {
+            PageDirection::UpperLeft | PageDirection::LowerRight => self.width,
+            PageDirection::LowerLeft | PageDirection::UpperRight => self.height,
+        }
+    }
+
+    /// Height of the print area in the direction of the text.
+    pub fn height(&self) -> u16 {
+        match self.direction {
+            PageDirection::UpperLeft | PageDirection::LowerRight => self.height,
+            PageDirection::LowerLeft | PageDirection::UpperRight => self.width,
+        }
+    }
+
+    /// Right edge of the print area on the paper.
+    pub(crate) fn right(&self) -> usize {
+        self.x as usize + self.width as usize
+    }
+
+    /// Encode the page, from entering page mode to printing it with `FF`.
+    pub(crate) fn encode(&self, encoder: &mut Encoder) -> Vec<u8> {
+        let esc = chars::ESC as u8;
+        let gs = chars::GS as u8;
+        let direction = match self.direction {
+            PageDirection::UpperLeft => 0,
+            PageDirection::LowerLeft => 1,
+            PageDirection::LowerRight => 2,
+            PageDirection::UpperRight => 3,
+        };
+        let mut bytes = vec![esc, b'L', esc, b'T', direction, esc, b'W'];
+        for param in &[self.x, self.y, self.width, self.height] {
+            bytes.extend_from_slice(&param.to_le_bytes());
+        }
+        let move_to = |bytes: &mut Vec<u8>, x: u16, y: u16| {
+            bytes.extend_from_slice(&[esc, b'$']);
+            bytes.extend_from_slice(&x.to_le_bytes());
+            bytes.extend_from_slice(&[gs, b'$']);
+            bytes.extend_from_slice(&y.to_le_bytes());
+        };
+        let mut state = PrintState::default();
+        for item in &self.items {
+            match item {
+                PageItem::MoveTo { x, y } => move_to(&mut bytes, *x, *y),
+                PageItem::Skip(dots) => {
+                    bytes.extend_from_slice(&[esc, b'\\']);
+                    bytes.extend_from_slice(&dots.to_le_bytes());
+                }
+                PageItem::Text { style, text } => {
+                    let next = style.print_state(Justification::Left);
+                    state.encode_diff(&next, &mut bytes);
+                    encoder.encode_into(text, &mut bytes);
+                    state = next;
+                }
+                PageItem::Bitmap { x, y, bitmap } => {
+                    // Bit images are printed above the vertical position
+                    for y0 in (0..bitmap.height()).step_by(BIT_IMAGE_BAND) {
+                        let baseline = *y as usize + y0 + BIT_IMAGE_BAND;
+                        move_to(&mut bytes, *x, baseline as u16);
+                        bytes.extend_from_slice(&bitmap.encode_bit_image_band(y0));
+                    }
+                }
+                PageItem::Print => bytes.extend_from_slice(&[esc, chars::FF as u8]),
+            }
+        }
+        state.encode_diff(&PrintState::default(), &mut bytes);
+        bytes.push(chars::FF as u8);
+        bytes
+    }
+}
@@ -0,0 +1,1116 @@
+use embedded_graphics::{
+    mono_font::{
+        iso_8859_1::{FONT_10X20, FONT_7X14},
+        MonoFont, MonoTextStyle,
+    },
+    pixelcolor::BinaryColor,
+    prelude::*,
+    text::{Baseline, Text},
+};
+
+use std::{
+    collections::{HashMap, VecDeque},
+    convert::Infallible,
+    fs::{self, File},
+    io::{self, BufWriter, ErrorKind, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use super::{
+    barcode::{upc_check_digit, BarCodeSystem},
+    decode, encoding, Bitmap, CharCodeTable, DecodedCmd, QrCode, QrErrorCorrection, Transport,
+    PAPER_WIDTH_DOTS,
+};
+
+/// Default line spacing of 1/6 inch in dots.
+const DEFAULT_LINE_SPACING: usize = 30;
+/// Distance between the default tab stops in characters.
+const TAB_WIDTH: usize = 8;
+/// Height of the default page mode print area in dots.
+const PAGE_HEIGHT: u16 = 1662;
+/// Response to every `DLE EOT n`: online, no errors, paper present.
+const STATUS_OK: u8 = 0b0001_0010;
+
+/// Character cell of a printer font.
+struct Font {
+    width: usize,
+    height: usize,
+    /// Font drawn into the cell and its position inside it.
+    glyphs: &'static MonoFont<'static>,
+    offset: Point,
+}
+
+/// Font A, 12x24 dots.
+const FONT_A: Font = Font {
+    width: 12,
+    height: 24,
+    glyphs: &FONT_10X20,
+    offset: Point::new(1, 2),
+};
+
+/// Font B, 9x17 dots.
+const FONT_B: Font = Font {
+    width: 9,
+    height: 17,
+    glyphs: &FONT_7X14,
+    offset: Point::new(1, 2),
+};
+
+/// Interprets ESC/POS command streams and renders the receipts they
+/// would produce.
+///
+/// Supported are print modes, fonts, character sizes, underline, reverse
+/// printing, rotated and upside-down text, user-defined characters,
+/// justification, margins, feeds, cuts, page mode, bit and raster images,
+/// NV images, bar codes and QR codes. Other commands are ignored. Only
+/// EAN/UPC and ITF bar codes are drawn faithfully, other symbologies get a
+/// placeholder of similar size. NV images are kept in memory only.
+#[derive(Debug, Clone)]
+pub struct Emulator {
+    paper_width: usize,
+    /// The receipt currently being printed.
+    receipt: Bitmap,
+    /// Receipts that were cut off but not yet taken.
+    finished: Vec<Bitmap>,
+    /// Top of the next printed line.
+    y: usize,
+    /// Characters and bit images of the line being assembled.
+    line: Vec<(usize, Bitmap)>,
+    x: usize,
+    style: Style,
+    table: CharCodeTable,
+    /// Glyphs defined with `ESC &`, by font B flag and character code.
+    user_glyphs: HashMap<(bool, u8), Bitmap>,
+    /// Whether user-defined characters are selected with `ESC %`.
+    user_chars: bool,
+    line_spacing: usize,
+    justification: u8,
+    upside_down: bool,
+    left_margin: usize,
+    area_width: usize,
+    /// Justification and upside-down mode at the start of the current line.
+    line_mode: (u8, bool),
+    /// Page mode print area set with `ESC W`.
+    page_area: [u16; 4],
+    page_direction: u8,
+    /// Canvas while in page mode.
+    page: Option<Page>,
+    /// Images defined with `FS q`.
+    nv_bit_images: Vec<Bitmap>,
+    /// Graphics defined with `GS ( L`, by key code.
+    nv_graphics: HashMap<[u8; 2], Bitmap>,
+    barcode: BarCodeSettings,
+    qr: QrSettings,
+    /// Start of a command that was split across calls to [`Emulator::feed`].
+    pending: Vec<u8>,
+}
+
+/// Transport rendering everything to PNG files instead of printing.
+///
+/// Every receipt is saved as `receipt-0001.png`, `receipt-0002.png`, ...
+/// in the given directory once it is cut. Flushing saves the receipt in
+/// progress, which is later overwritten when it is finished.
+/// Status requests are answered as if the printer was ready.
+pub struct PreviewTransport {
+    emulator: Emulator,
+    dir: PathBuf,
+    /// Number of the last saved receipt.
+    count: usize,
+    responses: VecDeque<u8>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Style {
+    font_b: bool,
+    emphasized: bool,
+    double_strike: bool,
+    /// Underline thickness in dots.
+    underline: usize,
+    reverse: bool,
+    width: usize,
+    height: usize,
+    right_spacing: usize,
+    /// Rotated by 90° clockwise.
+    rotated: bool,
+}
+
+/// Page mode canvas, in the coordinates of the print direction.
+#[derive(Debug, Clone)]
+struct Page {
+    canvas: Bitmap,
+    x: usize,
+    /// Baseline of the current line.
+    y: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct BarCodeSettings {
+    height: usize,
+    module_width: usize,
+    hri_above: bool,
+    hri_below: bool,
+    hri_font_b: bool,
+}
+
+#[derive(Debug, Clone)]
+struct QrSettings {
+    module_size: u8,
+    error_correction: QrErrorCorrection,
+    data: Vec<u8>,
+}
+
+/// Adapter to draw into a [`Bitmap`] with embedded-graphics.
+struct Canvas<'a>(&'a mut Bitmap);
+
+impl Emulator {
+    /// Create an emulator for [`PAPER_WIDTH_DOTS`] wide paper.
+    pub fn new() -> Self {
+        Self::with_paper_width(PAPER_WIDTH_DOTS)
+    }
+
+    /// Create an emulator for a printable width of `paper_width` dots.
+    pub fn with_paper_width(paper_width: usize) -> Self {
+        Emulator {
+            paper_width,
+            receipt: Bitmap::new(paper_width, 0),
+            finished: Vec::new(),
+            y: 0,
+            line: Vec::new(),
+            x: 0,
+            style: Style::default(),
+            table: CharCodeTable::PC437,
+            user_glyphs: HashMap::new(),
+            user_chars: false,
+            line_spacing: DEFAULT_LINE_SPACING,
+            justification: 0,
+            upside_down: false,
+            left_margin: 0,
+            area_width: paper_width,
+            line_mode: (0, false),
+            page_area: [0, 0, paper_width as u16, PAGE_HEIGHT],
+            page_direction: 0,
+            page: None,
+            nv_bit_images: Vec::new(),
+            nv_graphics: HashMap::new(),
+            barcode: BarCodeSettings::default(),
+            qr: QrSettings::default(),
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn paper_width(&self) -> usize {
+        self.paper_width
+    }
+
+    /// Interpret `bytes` and return what the printer would send back.
+    ///
+    /// Commands may be split across multiple calls.
+    pub fn feed(&mut self, bytes: &[u8]) -> Vec<u8> {
+        let mut stream = std::mem::take(&mut self.pending);
+        stream.extend_from_slice(bytes);
+        let mut responses = Vec::new();
+        for cmd in decode(&stream) {
+            match cmd {
+                DecodedCmd::Incomplete(rest) => self.pending = rest.to_vec(),
+                cmd => self.exec(cmd, &mut responses),
+            }
+        }
+        responses
+    }
+
+    /// Take all receipts that were cut off since the last call.
+    pub fn take_receipts(&mut self) -> Vec<Bitmap> {
+        std::mem::take(&mut self.finished)
+    }
+
+    /// Render the receipt in progress, including the line being assembled.
+    pub fn current(&self) -> Bitmap {
+        let mut emulator = self.clone();
+        emulator.print_line(0);
+        emulator.receipt.extend_height(emulator.y);
+        emulator.receipt
+    }
+
+    /// Whether nothing was printed since the last cut.
+    pub fn is_blank(&self) -> bool {
+        self.line.is_empty() && self.y == 0 && self.receipt.height() == 0
+    }
+
+    fn exec(&mut self, cmd: DecodedCmd<'_>, responses: &mut Vec<u8>) {
+        use DecodedCmd::*;
+        if self.page.is_some() && self.exec_page(cmd) {
+            return;
+        }
+        match cmd {
+            Text(text) => {
+                for &byte in text {
+                    let glyph = self.render(byte);
+                    self.put(glyph);
+                }
+            }
+            HorizontalTab => {
+                let font = self.style.font();
+                let tab = TAB_WIDTH * (font.width + self.style.right_spacing) * self.style.width;
+                self.x = (self.x / tab + 1) * tab;
+                if self.x >= self.printable_width() {
+                    self.print_line(self.line_spacing);
+                }
+            }
+            PrintAndLineFeed => self.print_line(self.line_spacing),
+            RealTimeStatus(_) => responses.push(STATUS_OK),
+            TransmitStatus(1 | 2 | 49 | 50) => responses.push(0),
+            RightSideSpacing(n) => self.style.right_spacing = n as usize,
+            SelectPrintMode(n) => {
+                self.style.font_b = n & 0x01 != 0;
+                self.style.emphasized = n & 0x08 != 0;
+                self.style.height = if n & 0x10 != 0 { 2 } else { 1 };
+                self.style.width = if n & 0x20 != 0 { 2 } else { 1 };
+                self.style.underline = if n & 0x80 != 0 { 1 } else { 0 };
+            }
+            BitImage { mode, width, data } => {
+                if let Some(image) = bit_image(mode, width as usize, data) {
+                    self.put(image);
+                }
+            }
+            SelectUnderlineMode(n) => self.style.underline = (n as usize % 48).min(2),
+            DefaultLineSpacing => self.line_spacing = DEFAULT_LINE_SPACING,
+            LineSpacing(n) => self.line_spacing = n as usize,
+            InitializePrinter => {
+                let paper_width = self.paper_width;
+                let receipt = std::mem::replace(&mut self.receipt, Bitmap::new(paper_width, 0));
+                let finished = std::mem::take(&mut self.finished);
+                let nv_bit_images = std::mem::take(&mut self.nv_bit_images);
+                let nv_graphics = std::mem::take(&mut self.nv_graphics);
+                let y = self.y;
+                *self = Emulator {
+                    receipt,
+                    finished,
+                    y,
+                    nv_bit_images,
+                    nv_graphics,
+                    ..Emulator::with_paper_width(paper_width)
+                };
+            }
+            SelectEmphasized(on) => self.style.emphasized = on,
+            SelectDoubleStrike(on) => self.style.double_strike = on,
+            PrintAndFeedDots(n) => self.print_line(n as usize),
+            SelectFont(n) => self.style.font_b = n % 48 == 1,
+            SelectJustification(n) => self.justification = n % 48,
+            SelectRotation(n) => self.style.rotated = n % 48 == 1,
+            SelectUpsideDown(on) => self.upside_down = on,
+            SetLeftMargin(dots) => self.left_margin = dots as usize,
+            SetPrintAreaWidth(dots) => self.area_width = dots as usize,
+            SelectUserDefinedChars(on) => self.user_chars = on,
+            DefineUserChars {
+                height,
+                first,
+                last,
+                data,
+            } => self.define_user_chars(height as usize, first, last, data),
+            CancelUserChar(code) => {
+                self.user_glyphs.remove(&(self.style.font_b, code));
+            }
+            SelectPageMode => {
+                if !self.line.is_empty() {
+                    self.print_line(self.line_spacing);
+                }
+                self.page = Some(self.new_page());
+            }
+            SetPrintArea(area) => self.page_area = area,
+            SelectPrintDirection(n) => self.page_direction = n % 48 % 4,
+            PrintAndFeedLines(n) => self.print_line(n as usize * self.line_spacing),
+            PrintAndReverseFeedLines(n) => {
+                self.print_line(0);
+                self.y = self.y.saturating_sub(n as usize * self.line_spacing);
+            }
+            SelectCharCodeTable(code) => {
+                self.table = CharCodeTable::from_code(code).unwrap_or(self.table)
+            }
+            SelectCharSize(n) => {
+                self.style.width = (n >> 4) as usize + 1;
+                self.style.height = (n & 0x0f) as usize + 1;
+            }
+            Extended {
+                function: b'k',
+                data,
+            } => self.qr_function(data),
+            Extended {
+                function: b'L',
+                data,
+            } => self.graphics_function(data, responses),
+            PrintNvBitImage { number, mode } => {
+                let image = (number as usize)
+                    .checked_sub(1)
+                    .and_then(|idx| self.nv_bit_images.get(idx));
+                if let Some(image) = image {
+                    let mode = mode as usize % 48;
+                    let image = image.scale(1 + (mode & 1), 1 + ((mode >> 1) & 1));
+                    self.print_block(&image);
+                }
+            }
+            DefineNvBitImages { count, data } => self.define_nv_bit_images(count, data),
+            SelectReversePrinting(on) => self.style.reverse = on,
+            SelectHriPosition(n) => {
+                self.barcode.hri_above = (n % 48) & 1 != 0;
+                self.barcode.hri_below = (n % 48) & 2 != 0;
+            }
+            CutPaper { feed, .. } => {
+                self.print_line(feed.unwrap_or(0) as usize);
+                self.cut();
+            }
+            SelectHriFont(n) => self.barcode.hri_font_b = n % 48 == 1,
+            SelectBarCodeHeight(n) => self.barcode.height = n as usize,
+            PrintBarCode { system, data } => self.print_barcode(system, data),
+            RasterImage {
+                mode,
+                width_bytes,
+                height,
+                data,
+            } => {
+                let mut image = Bitmap::new(width_bytes as usize * 8, height as usize);
+                for y in 0..image.height() {
+                    for x in 0..image.width() {
+                        let byte = data[y * width_bytes as usize + x / 8];
+                        image.set(x, y, byte & (0x80 >> (x % 8)) != 0);
+                    }
+                }
+                let image = image.scale(1 + (mode as usize & 1), 1 + ((mode as usize >> 1) & 1));
+                self.print_block(&image);
+            }
+            SelectBarCodeWidth(n) => self.barcode.module_width = n as usize,
+            _ => {}
+        }
+    }
+
+    /// Render the character `byte` in the current style.
+    fn render(&self, byte: u8) -> Bitmap {
+        let user_glyph = self
+            .user_glyphs
+            .get(&(self.style.font_b, byte))
+            .filter(|_| self.user_chars);
+        match user_glyph {
+            Some(glyph) => self.style.render_user(glyph),
+            None => self.style.render(encoding::decode_byte(self.table, byte)),
+        }
+    }
+
+    /// Store the glyphs of an `ESC &` command for the current font.
+    ///
+    /// Every glyph consists of its width followed by `height` bytes
+    /// per column.
+    fn define_user_chars(&mut self, height: usize, first: u8, last: u8, mut data: &[u8]) {
+        let font_b = self.style.font_b;
+        let rows = self.style.font().height;
+        for code in first..=last {
+            let width = data[0] as usize;
+            let mut glyph = Bitmap::new(width, rows);
+            for x in 0..width {
+                for y in 0..rows.min(height * 8) {
+                    let byte = data[1 + x * height + y / 8];
+                    glyph.set(x, y, byte & (0x80 >> (y % 8)) != 0);
+                }
+            }
+            self.user_glyphs.insert((font_b, code), glyph);
+            data = &data[1 + width * height..];
+        }
+    }
+
+    /// Handle commands that behave differently in page mode.
+    ///
+    /// Returns `false` for commands that work the same in both modes.
+    fn exec_page(&mut self, cmd: DecodedCmd<'_>) -> bool {
+        use DecodedCmd::*;
+        match cmd {
+            Text(text) => {
+                for &byte in text {
+                    let glyph = self.render(byte);
+                    self.put_page(&glyph);
+                }
+            }
+            BitImage { mode, width, data } => {
+                if let Some(image) = bit_image(mode, width as usize, data) {
+                    self.put_page(&image);
+                }
+            }
+            PrintAndLineFeed => self.page_line_feed(self.line_spacing),
+            PrintAndFeedDots(n) => self.page_line_feed(n as usize),
+            PrintAndFeedLines(n) => self.page_line_feed(n as usize * self.line_spacing),
+            AbsolutePosition(x) => self.page_mut().x = x as usize,
+            RelativePosition(dx) => {
+                let page = self.page_mut();
+                page.x = (page.x as isize + dx as isize).max(0) as usize;
+            }
+            AbsoluteVerticalPosition(y) => self.page_mut().y = y as usize,
+            SetPrintArea(area) => {
+                self.page_area = area;
+                self.page = Some(self.new_page());
+            }
+            SelectPrintDirection(n) => {
+                self.page_direction = n % 48 % 4;
+                self.page = Some(self.new_page());
+            }
+            PrintPage => self.print_page(),
+            FormFeed => {
+                self.print_page();
+                self.page = None;
+            }
+            SelectStandardMode => self.page = None,
+            _ => return false,
+        }
+        true
+    }
+
+    /// Empty canvas for the current print area and direction.
+    fn new_page(&self) -> Page {
+        let [_, _, width, height] = self.page_area;
+        let (width, height) = match self.page_direction {
+            1 | 3 => (height, width),
+            _ => (width, height),
+        };
+        Page {
+            canvas: Bitmap::new(width as usize, height as usize),
+            x: 0,
+            y: 0,
+        }
+    }
+
+    fn page_mut(&mut self) -> &mut Page {
+        self.page.as_mut().expect("not in page mode")
+    }
+
+    /// Draw `item` with its bottom on the baseline, wrapping if necessary.
+    fn put_page(&mut self, item: &Bitmap) {
+        let line_spacing = self.line_spacing;
+        let page = self.page_mut();
+        if page.x + item.width() > page.canvas.width() && page.x > 0 {
+            page.x = 0;
+            page.y += line_spacing;
+        }
+        // The first line starts below the top of the print area
+        page.y = page.y.max(item.height());
+        let height = page.canvas.height();
+        page.canvas.draw(item, page.x, page.y - item.height());
+        page.canvas.crop_height(height);
+        page.x += item.width();
+    }
+
+    fn page_line_feed(&mut self, feed: usize) {
+        let page = self.page_mut();
+        page.x = 0;
+        page.y += feed;
+    }
+
+    /// Print the canvas, rotated according to the print direction.
+    fn print_page(&mut self) {
+        let page = self.page.as_ref().expect("not in page mode");
+        let canvas = match self.page_direction {
+            1 => {
+                let mut canvas = page.canvas.rotate_cw();
+                canvas.rotate_half();
+                canvas
+            }
+            2 => {
+                let mut canvas = page.canvas.clone();
+                canvas.rotate_half();
+                canvas
+            }
+            3 => page.canvas.rotate_cw(),
+            _ => page.canvas.clone(),
+        };
+        let [x, y, _, _] = self.page_area;
+        self.receipt.draw(&canvas, x as usize, self.y + y as usize);
+        self.y += y as usize + canvas.height();
+    }
+
+    /// Append `item` to the current line, wrapping if it does not fit.
+    fn put(&mut self, item: Bitmap) {
+        if self.x + item.width() > self.printable_width() && !self.line.is_empty() {
+            self.print_line(self.line_spacing);
+        }
+        if self.line.is_empty() {
+            self.line_mode = (self.justification, self.upside_down);
+        }
+        let width = item.width();
+        self.line.push((self.x, item));
+        self.x += width;
+    }
+
+    /// Print the current line and advance the paper by at least `feed` dots.
+    fn print_line(&mut self, feed: usize) {
+        let height = self.line.iter().map(|(_, item)| item.height()).max();
+        let (justification, upside_down) = self.line_mode;
+        let offset = self.justify(justification, self.x);
+        let mut line = Bitmap::new(self.paper_width, height.unwrap_or(0));
+        for (x, item) in std::mem::take(&mut self.line) {
+            // Items of a line share their bottom edge
+            let top = line.height() - item.height();
+            line.draw(&item, offset + x, top);
+        }
+        if upside_down {
+            line.rotate_half();
+        }
+        self.receipt.draw(&line, 0, self.y);
+        self.y += feed.max(line.height());
+        self.x = 0;
+    }
+
+    /// Print `block` on its own, i.e. raster images and bar codes.
+    fn print_block(&mut self, block: &Bitmap) {
+        if !self.line.is_empty() {
+            self.print_line(self.line_spacing);
+        }
+        let offset = self.justify(self.justification, block.width());
+        self.receipt.draw(block, offset, self.y);
+        self.y += block.height();
+    }
+
+    /// Horizontal offset of content `width` dots wide.
+    fn justify(&self, justification: u8, width: usize) -> usize {
+        let free = self.printable_width().saturating_sub(width);
+        let offset = match justification {
+            1 => free / 2,
+            2 => free,
+            _ => 0,
+        };
+        self.left_margin.min(self.paper_width) + offset
+    }
+
+    /// Width of the print area right of the left margin.
+    fn printable_width(&self) -> usize {
+        let width = self
+            .area_width
+            .min(self.paper_width.saturating_sub(self.left_margin));
+        // The printer prints at least one character per line
+        width.max(self.style.font().width)
+    }
+
+    fn cut(&mut self) {
+        self.receipt.extend_height(self.y);
+        let receipt = std::mem::replace(&mut self.receipt, Bitmap::new(self.paper_width, 0));
+        self.finished.push(receipt);
+        self.y = 0;
+    }
+
+    fn print_barcode(&mut self, system: u8, data: &[u8]) {
+        let system = match BarCodeSystem::from_code(system) {
+            Some(system) => system,
+            None => return,
+        };
+        let settings = self.barcode;
+        let modules = bar_modules(system, data);
+        let mut bars = Bitmap::new(modules.len() * settings.module_width, settings.height);
+        for (idx, _) in modules.iter().enumerate().filter(|(_, &black)| black) {
+            for x in idx * settings.module_width..(idx + 1) * settings.module_width {
+                for y in 0..settings.height {
+                    bars.set(x, y, true);
+                }
+            }
+        }
+        let hri_style = Style {
+            font_b: settings.hri_font_b,
+            ..Style::default()
+        };
+        let hri: Vec<Bitmap> = hri_text(system, data)
+            .chars()
+            .map(|c| hri_style.render(c))
+            .collect();
+        let hri_width: usize = hri.iter().map(Bitmap::width).sum();
+        let hri_height = hri_style.font().height;
+        let mut block = Bitmap::new(bars.width().max(hri_width), 0);
+        let mut y = 0;
+        let draw_hri = |block: &mut Bitmap, y: usize| {
+            let mut x = (block.width() - hri_width) / 2;
+            for glyph in &hri {
+                block.draw(glyph, x, y);
+                x += glyph.width();
+            }
+        };
+        if settings.hri_above {
+            draw_hri(&mut block, y);
+            y += hri_height;
+        }
+        block.draw(&bars, (block.width() - bars.width()) / 2, y);
+        y += bars.height();
+        if settings.hri_below {
+            draw_hri(&mut block, y);
+            y += hri_height;
+        }
+        block.extend_height(y);
+        self.print_block(&block);
+    }
+
+    /// Handle the NV graphics functions of `GS ( L`.
+    fn graphics_function(&mut self, data: &[u8], responses: &mut Vec<u8>) {
+        match data {
+            [48, 64, b'K', b'C', ..] => {
+                responses.extend_from_slice(&[0x37, 0x72, 0x41]);
+                let mut keys: Vec<_> = self.nv_graphics.keys().collect();
+                keys.sort();
+                for key in keys {
+                    responses.extend_from_slice(key);
+                }
+                responses.push(0);
+            }
+            [48, 65, b'C', b'L', b'R', ..] => self.nv_graphics.clear(),
+            [48, 66, c1, c2, ..] => {
+                self.nv_graphics.remove(&[*c1, *c2]);
+            }
+            [48, 67, 48, c1, c2, 1, xl, xh, yl, yh, 49, pixels @ ..] => {
+                let width = u16::from_le_bytes([*xl, *xh]) as usize;
+                let height = u16::from_le_bytes([*yl, *yh]) as usize;
+                let mut image = Bitmap::new(width, height);
+                if pixels.len() < image.bytes_per_row() * height {
+                    return;
+                }
+                for y in 0..height {
+                    for x in 0..width {
+                        let byte = pixels[y * image.bytes_per_row() + x / 8];
+                        image.set(x, y, byte & (0x80 >> (x % 8)) != 0);
+                    }
+                }
+                self.nv_graphics.insert([*c1, *c2], image);
+            }
+            // The printer ignores the command for other scales
+            [48, 69, c1, c2, sx @ 1..=2, sy @ 1..=2, ..] => {
+                if let Some(image) = self.nv_graphics.get(&[*c1, *c2]) {
+                    let image = image.scale(*sx as usize, *sy as usize);
+                    self.print_block(&image);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Replace the NV bit images with the ones of an `FS q` command.
+    fn define_nv_bit_images(&mut self, count: u8, mut data: &[u8]) {
+        self.nv_bit_images.clear();
+        for _ in 0..count {
+            let width = u16::from_le_bytes([data[0], data[1]]) as usize * 8;
+            let height = u16::from_le_bytes([data[2], data[3]]) as usize * 8;
+            let mut image = Bitmap::new(width, height);
+            for x in 0..width {
+                for y in 0..height {
+                    let byte = data[4 + x * height / 8 + y / 8];
+                    image.set(x, y, byte & (0x80 >> (y % 8)) != 0);
+                }
+            }
+            self.nv_bit_images.push(image);
+            data = &data[4 + width * height / 8..];
+        }
+    }
+
+    /// Handle the QR code functions of `GS ( k`.
+    fn qr_function(&mut self, data: &[u8]) {
+        match data {
+            [49, 67, size, ..] => self.qr.module_size = *size,
+            [49, 69, level, ..] => {
+                self.qr.error_correction = match level {
+                    48 => QrErrorCorrection::L,
+                    50 => QrErrorCorrection::Q,
+                    51 => QrErrorCorrection::H,
+                    _ => QrErrorCorrection::M,
+                }
+            }
+            [49, 80, 48, symbol @ ..] => self.qr.data = symbol.to_vec(),
+            [49, 81, ..] => {
+                let bitmap = QrCode::new(&self.qr.data)
+                    .and_then(|code| code.module_size(self.qr.module_size))
+                    .map(|code| code.error_correction(self.qr.error_correction))
+                    .and_then(|code| code.to_bitmap());
+                // The printer silently ignores invalid codes as well
+                if let Ok(bitmap) = bitmap {
+                    self.print_block(&bitmap);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Default for Emulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PreviewTransport {
+    /// Save receipts to `dir`, creating it if necessary.
+    ///
+    /// Numbering continues after receipts already in the directory.
+    pub fn new<P: AsRef<Path>>(dir: P) -> io::Result<Self> {
+        Self::with_emulator(dir, Emulator::new())
+    }
+
+    /// Like [`PreviewTransport::new`], but with a custom emulator.
+    pub fn with_emulator<P: AsRef<Path>>(dir: P, emulator: Emulator) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        let mut count = 0;
+        for entry in fs::read_dir(&dir)? {
+            let name = entry?.file_name();
+            let number = name
+                .to_str()
+                .and_then(|name| name.strip_prefix("receipt-"))
+                .and_then(|name| name.strip_suffix(".png"))
+                .and_then(|number| number.parse().ok());
+            count = count.max(number.unwrap_or(0));
+        }
+        Ok(PreviewTransport {
+            emulator,
+            dir,
+            count,
+            responses: VecDeque::new(),
+        })
+    }
+
+    pub fn emulator(&self) -> &Emulator {
+        &self.emulator
+    }
+
+    /// Path of the receipt with the given number.
+    pub fn receipt_path(&self, number: usize) -> PathBuf {
+        self.dir.join(format!("receipt-{:04}.png", number))
+    }
+
+    /// Number of the last finished receipt, `0` if there is none.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+impl Write for PreviewTransport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let responses = self.emulator.feed(buf);
+        self.responses.extend(responses);
+        for receipt in self.emulator.take_receipts() {
+            self.count += 1;
+            receipt.save_png(self.receipt_path(self.count))?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.emulator.is_blank() {
+            return Ok(());
+        }
+        self.emulator
+            .current()
+            .save_png(self.receipt_path(self.count + 1))
+    }
+}
+
+impl Transport for PreviewTransport {
+    fn read_timeout(&mut self, buf: &mut [u8], _timeout: Duration) -> io::Result<usize> {
+        if self.responses.is_empty() && !buf.is_empty() {
+            return Err(io::Error::new(ErrorKind::TimedOut, "no response pending"));
+        }
+        let len = buf.len().min(self.responses.len());
+        for (dst, src) in buf.iter_mut().zip(self.responses.drain(..len)) {
+            *dst = src;
+        }
+        Ok(len)
+    }
+}
+
+impl Drop for PreviewTransport {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+impl Bitmap {
+    /// Write the bitmap as grayscale PNG.
+    pub fn write_png<W: Write>(&self, writer: W) -> io::Result<()> {
+        let mut encoder = png::Encoder::new(writer, self.width() as u32, self.height() as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::One);
+        let mut writer = encoder.write_header().map_err(io::Error::other)?;
+        // PNG uses 1 for white
+        let data: Vec<u8> = (0..self.height())
+            .flat_map(|y| self.row(y).iter().map(|byte| !byte))
+            .collect();
+        writer.write_image_data(&data).map_err(io::Error::other)
+    }
+
+    /// Save the bitmap as PNG file at `path`.
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let file = File::create(path)?;
+        self.write_png(BufWriter::new(file))
+    }
+}
+
+impl Style {
+    fn font(&self) -> &'static Font {
+        if self.font_b {
+            &FONT_B
+        } else {
+            &FONT_A
+        }
+    }
+
+    /// Render `c` into a character cell using this style.
+    fn render(&self, c: char) -> Bitmap {
+        let font = self.font();
+        let mut cell = Bitmap::new(font.width + self.right_spacing, font.height);
+        let mut buf = [0; 4];
+        let text_style = MonoTextStyle::new(font.glyphs, BinaryColor::On);
+        let text = Text::with_baseline(
+            c.encode_utf8(&mut buf),
+            font.offset,
+            text_style,
+            Baseline::Top,
+        );
+        let _ = text.draw(&mut Canvas(&mut cell));
+        self.apply(cell)
+    }
+
+    /// Render a user-defined glyph using this style.
+    fn render_user(&self, glyph: &Bitmap) -> Bitmap {
+        let mut cell = Bitmap::new(glyph.width() + self.right_spacing, glyph.height());
+        cell.draw(glyph, 0, 0);
+        self.apply(cell)
+    }
+
+    /// Apply emphasis, underline, rotation, size and reverse printing
+    /// to a character cell.
+    fn apply(&self, mut cell: Bitmap) -> Bitmap {
+        if self.emphasized || self.double_strike {
+            // Thicken every stroke by one dot to the right
+            for y in 0..cell.height() {
+                for x in (1..cell.width()).rev() {
+                    if cell.get(x - 1, y) {
+                        cell.set(x, y, true);
+                    }
+                }
+            }
+        }
+        for y in cell.height() - self.underline..cell.height() {
+            for x in 0..cell.width() {
+                cell.set(x, y, true);
+            }
+        }
+        if self.rotated {
+            cell = cell.rotate_cw();
+        }
+        let mut glyph = cell.scale(self.width, self.height);
+        if self.reverse {
+            glyph.invert();
+        }
+        glyph
+    }
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style {
+            font_b: false,
+            emphasized: false,
+            double_strike: false,
+            underline: 0,
+            reverse: false,
+            width: 1,
+            height: 1,
+            right_spacing: 0,
+            rotated: false,
+        }
+    }
+}
+
+impl Default for BarCodeSettings {
+    fn default() -> Self {
+        BarCodeSettings {
+            height: 162,
+            module_width: 3,
+            hri_above: false,
+            hri_below: false,
+            hri_font_b: false,
+        }
+    }
+}
+
+impl Default for QrSettings {
+    fn default() -> Self {
+        QrSettings {
+            module_size: 3,
+            error_correction: QrErrorCorrection::M,
+            data: Vec::new(),
+        }
+    }
+}
+
+impl OriginDimensions for Canvas<'_> {
+    fn size(&self) -> Size {
+        Size::new(self.0.width() as u32, self.0.height() as u32)
+    }
+}
+
+impl DrawTarget for Canvas<'_> {
+    type Color = BinaryColor;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            let (x, y) = (point.x as usize, point.y as usize);
+            if point.x >= 0 && point.y >= 0 && x < self.0.width() && y < self.0.height() {
+                self.0.set(x, y, color.is_on());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Decode the column data of `ESC *` into a bitmap at 180 dpi.
+fn bit_image(mode: u8, width: usize, data: &[u8]) -> Option<Bitmap> {
+    // Bytes per column and the enlargement to 180 dpi
+    let (rows, sx, sy) = match mode {
+        0 => (1, 2, 3),
+        1 => (1, 1, 3),
+        32 => (3, 2, 1),
+        33 => (3, 1, 1),
+        _ => return None,
+    };
+    let mut image = Bitmap::new(width, rows * 8);
+    for (x, column) in data.chunks_exact(rows).enumerate() {
+        for (idx, byte) in column.iter().enumerate() {
+            for bit in 0..8 {
+                image.set(x, idx * 8 + bit, byte & (0x80 >> bit) != 0);
+            }
+        }
+    }
+    Some(image.scale(sx, sy))
+}
+
+/// Modules of a bar code, `true` is a bar.
+///
+/// EAN/UPC and ITF are encoded properly, everything else is
+/// approximated with a pattern derived from the data.
+fn bar_modules(system: BarCodeSystem, data: &[u8]) -> Vec<bool> {
+    let digits: Vec<u8> = data.iter().map(|b| b.wrapping_sub(b'0')).collect();
+    let all_digits = digits.iter().all(|&d| d < 10);
+    match system {
+        BarCodeSystem::UpcA | BarCodeSystem::Ean13 | BarCodeSystem::Ean8 if all_digits => {
+            let mut digits = digits;
+            if system == BarCodeSystem::UpcA {
+                digits.insert(0, 0);
+            }
+            let full_len = if system == BarCodeSystem::Ean8 { 8 } else { 13 };
+            if digits.len() == full_len - 1 {
+                digits.push(upc_check_digit(&digits));
+            }
+            if digits.len() == full_len {
+                return ean_modules(&digits);
+            }
+        }
+        BarCodeSystem::Itf if all_digits => {
+            let pairs = digits.chunks_exact(2);
+            if pairs.remainder().is_empty() {
+                let mut widths = vec![1, 1, 1, 1];
+                for pair in pairs {
+                    let bars = ITF_WIDTHS[pair[0] as usize];
+                    let spaces = ITF_WIDTHS[pair[1] as usize];
+                    for (&bar, &space) in bars.iter().zip(&spaces) {
+                        widths.push(bar);
+                        widths.push(space);
+                    }
+                }
+                widths.extend_from_slice(&[3, 1, 1]);
+                return widths
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(idx, &width)| (0..width).map(move |_| idx % 2 == 0))
+                    .collect();
+            }
+        }
+        _ => {}
+    }
+    let mut modules = vec![true, false, true, false];
+    for byte in data {
+        modules.push(true);
+        modules.extend((0..8).map(|bit| byte & (0x80 >> bit) != 0));
+        modules.push(false);
+    }
+    modules.extend_from_slice(&[true, true, false, true]);
+    modules
+}
+
+/// Encode EAN-13 or EAN-8 including the check digit.
+fn ean_modules(digits: &[u8]) -> Vec<bool> {
+    let mut modules = Vec::with_capacity(95);
+    let mut push = |pattern: u8, bits: usize| {
+        modules.extend((0..bits).rev().map(|bit| pattern & (1 << bit) != 0));
+    };
+    // EAN-13 encodes the first digit in the parity of the left half
+    let (parity, left, right) = if digits.len() == 13 {
+        (EAN_PARITY[digits[0] as usize], &digits[1..7], &digits[7..])
+    } else {
+        (0, &digits[..4], &digits[4..])
+    };
+    push(0b101, 3);
+    for (idx, &d) in left.iter().enumerate() {
+        let even = parity & (0b10_0000 >> idx) != 0;
+        let code = if even {
+            // G codes are the mirrored R codes
+            (!EAN_L[d as usize] & 0x7f).reverse_bits() >> 1
+        } else {
+            EAN_L[d as usize]
+        };
+        push(code, 7);
+    }
+    push(0b01010, 5);
+    for &d in right {
+        push(!EAN_L[d as usize] & 0x7f, 7);
+    }
+    push(0b101, 3);
+    modules
+}
+
+/// Text printed as HRI characters below or above a bar code.
+fn hri_text(system: BarCodeSystem, data: &[u8]) -> String {
+    if system != BarCodeSystem::Code128 {
+        return data.iter().map(|&b| b as char).collect();
+    }
+    // Drop code set selections and unescape braces
+    let mut text = String::new();
+    let mut bytes = data.iter();
+    while let Some(&b) = bytes.next() {
+        match b {
+            b'{' => {
+                if bytes.next() == Some(&b'{') {
+                    text.push('{');
+                }
+            }
+            b => text.push(b as char),
+        }
+    }
+    text
+}
+
+/// Left-hand odd parity (L) codes of EAN/UPC, 7 modules each.
+const EAN_L: [u8; 10] = [
+    0b0001101, 0b0011001, 0b0010011, 0b0111101, 0b0100011, 0b0110001, 0b0101111, 0b0111011,
+    0b0110111, 0b0001011,
+];
+
+/// Digits of the left half using even parity (G codes) for the first EAN-13 digit.
+const EAN_PARITY: [u8; 10] = [
+    0b000000, 0b001011, 0b001101, 0b001110, 0b010011, 0b011001, 0b011100, 0b010101, 0b010110,
+    0b011010,
+];
+
+/// Narrow (1) and wide (3) elements of the ITF digits.
+const ITF_WIDTHS: [[usize; 5]; 10] = [
+    [1, 1, 3, 3, 1],
+    [3, 1, 1, 1, 3],
+    [1, 3, 1, 1, 3],
+    [3, 3, 1, 1, 1],
+    [1, 1, 3, 1, 3],
+    [3, 1, 3, 1, 1],
+    [1, 3, 3, 1, 1],
+    [1, 1, 1, 3, 3],
+    [3, 1, 1, 3, 1],
+    [1, 3, 1, 3, 1],
+];
@@ -0,0 +1,385 @@
+use bytes::BufMut;
+
+use std::{error, fmt, io::Error as IoError};
+
+use super::{
+    cmds::{CharCodeTable, CutMode, Font},
+    BarCodeSystem, Bitmap, Encode, Error, EscPosCmd, ImageOptions, QrCode, RasterMode,
+};
+
+/// Names accepted by [`PrinterProfile::builtin`].
+pub const BUILTIN_PROFILES: [&str; 6] = [
+    "tm-t88iii",
+    "tm-t88v",
+    "tm-t20",
+    "star-tsp100",
+    "generic-58mm",
+    "generic-80mm",
+];
+
+/// Capabilities of a printer model.
+///
+/// The [`Printer`](crate::Printer) refuses commands the profile does not
+/// support or emulates them where possible, e.g. QR codes are printed
+/// as images on printers without native support.
+///
+/// With the `toml-profiles` feature, profiles can be loaded from TOML.
+/// Missing keys are taken from [`PrinterProfile::generic_80mm`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct PrinterProfile {
+    pub name: String,
+    /// Printable width in dots.
+    pub paper_width: usize,
+    /// Characters per line in font A.
+    pub font_a_columns: usize,
+    /// Characters per line in font B, `None` if the font is missing.
+    pub font_b_columns: Option<usize>,
+    /// Characters per line in font C, `None` if the font is missing.
+    pub font_c_columns: Option<usize>,
+    /// Installed code tables, in the order they are tried when encoding.
+    pub code_tables: Vec<CharCodeTable>,
+    /// Supported cuts, preferred first. Empty if there is no cutter.
+    pub cut_modes: Vec<CutMode>,
+    /// Lines fed before cutting, so the last line clears the cutter.
+    pub cut_feed_lines: u8,
+    /// `GS v 0` raster images.
+    pub raster: bool,
+    /// `ESC *` bit images.
+    pub bit_image: bool,
+    /// Native `GS ( k` QR codes.
+    pub qr: bool,
+    /// `ESC L` page mode.
+    pub page_mode: bool,
+    /// `ESC &` user-defined characters.
+    pub user_chars: bool,
+    /// `FS q` and `FS p` NV bit images.
+    pub nv_bit_image: bool,
+    /// `GS ( L` NV graphics.
+    pub nv_graphics: bool,
+    pub barcodes: Vec<BarCodeSystem>,
+    /// Size of the receive buffer in bytes.
+    pub buffer_size: usize,
+}
+
+/// Error loading a profile from a file.
+#[derive(Debug)]
+pub enum ProfileError {
+    Io(IoError),
+    #[cfg(feature = "toml-profiles")]
+    Parse(toml::de::Error),
+}
+
+/// Bar code systems of the `GS k m` function B.
+const ALL_BARCODES: [BarCodeSystem; 9] = [
+    BarCodeSystem::UpcA,
+    BarCodeSystem::UpcE,
+    BarCodeSystem::Ean13,
+    BarCodeSystem::Ean8,
+    BarCodeSystem::Code39,
+    BarCodeSystem::Itf,
+    BarCodeSystem::Codabar,
+    BarCodeSystem::Code93,
+    BarCodeSystem::Code128,
+];
+
+impl PrinterProfile {
+    /// Look up a built-in profile by one of the [`BUILTIN_PROFILES`] names.
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "tm-t88iii" => Some(Self::tm_t88iii()),
+            "tm-t88v" => Some(Self::tm_t88v()),
+            "tm-t20" => Some(Self::tm_t20()),
+            "star-tsp100" => Some(Self::star_tsp100()),
+            "generic-58mm" => Some(Self::generic_58mm()),
+            "generic-80mm" => Some(Self::generic_80mm()),
+            _ => None,
+        }
+    }
+
+    /// Epson TM-T88III, 80mm paper without native QR codes.
+    pub fn tm_t88iii() -> Self {
+        use CharCodeTable::*;
+        PrinterProfile {
+            name: "tm-t88iii".into(),
+            paper_width: 512,
+            font_a_columns: 42,
+            font_b_columns: Some(56),
+            font_c_columns: None,
+            code_tables: vec![PC437, PC850, PC860, PC863, PC865, Katakana],
+            cut_modes: vec![CutMode::Full, CutMode::Partial],
+            cut_feed_lines: 4,
+            raster: true,
+            bit_image: true,
+            qr: false,
+            page_mode: true,
+            user_chars: true,
+            nv_bit_image: true,
+            nv_graphics: false,
+            barcodes: ALL_BARCODES.to_vec(),
+            buffer_size: 4096,
+        }
+    }
+
+    /// Epson TM-T88V, 80mm paper.
+    pub fn tm_t88v() -> Self {
+        use CharCodeTable::*;
+        PrinterProfile {
+            name: "tm-t88v".into(),
+            paper_width: 512,
+            font_a_columns: 42,
+            font_b_columns: Some(56),
+            font_c_columns: None,
+            code_tables: vec![
+                PC437, PC850, PC858, WPC1252, PC852, PC866, PC860, PC863, PC865, Katakana,
+            ],
+            cut_modes: vec![CutMode::Partial],
+            cut_feed_lines: 3,
+            raster: true,
+            bit_image: true,
+            qr: true,
+            page_mode: true,
+            user_chars: true,
+            nv_bit_image: true,
+            nv_graphics: true,
+            barcodes: ALL_BARCODES.to_vec(),
+            buffer_size: 4096,
+        }
+    }
+
+    /// Epson TM-T20, 80mm paper with 576 dots per line.
+    pub fn tm_t20() -> Self {
+        PrinterProfile {
+            name: "tm-t20".into(),
+            paper_width: 576,
+            font_a_columns: 48,
+            font_b_columns: Some(64),
+            ..Self::tm_t88v()
+        }
+    }
+
+    /// Star TSP100 series in ESC/POS emulation mode.
+    pub fn star_tsp100() -> Self {
+        use CharCodeTable::*;
+        PrinterProfile {
+            name: "star-tsp100".into(),
+            paper_width: 576,
+            font_a_columns: 48,
+            font_b_columns: Some(64),
+            font_c_columns: None,
+            code_tables: vec![
+                PC437, PC850, PC858, WPC1252, PC852, PC866, PC860, PC863, PC865,
+            ],
+            cut_modes: vec![CutMode::Partial, CutMode::Full],
+            cut_feed_lines: 3,
+            raster: true,
+            bit_image: true,
+            qr: true,
+            page_mode: true,
+            user_chars: true,
+            nv_bit_image: true,
+            nv_graphics: false,
+            barcodes: ALL_BARCODES.to_vec(),
+            buffer_size: 2048,
+        }
+    }
+
+    /// Cheap 58mm printer without cutter.
+    pub fn generic_58mm() -> Self {
+        use BarCodeSystem::*;
+        use CharCodeTable::*;
+        PrinterProfile {
+            name: "generic-58mm".into(),
+            paper_width: 384,
+            font_a_columns: 32,
+            font_b_columns: Some(42),
+            font_c_columns: None,
+            code_tables: vec![PC437, PC850, PC858, WPC1252, PC866],
+            cut_modes: Vec::new(),
+            cut_feed_lines: 3,
+            raster: true,
+            bit_image: true,
+            qr: false,
+            page_mode: false,
+            user_chars: false,
+            nv_bit_image: true,
+            nv_graphics: false,
+            barcodes: vec![UpcA, UpcE, Ean13, Ean8, Code39, Itf, Codabar],
+            buffer_size: 1024,
+        }
+    }
+
+    /// Typical 80mm printer with cutter and native QR codes.
+    pub fn generic_80mm() -> Self {
+        use CharCodeTable::*;
+        PrinterProfile {
+            name: "generic-80mm".into(),
+            paper_width: 576,
+            font_a_columns: 48,
+            font_b_columns: Some(64),
+            font_c_columns: None,
+            code_tables: vec![PC437, PC850, PC858, WPC1252, PC852, PC866],
+            cut_modes: vec![CutMode::Full, CutMode::Partial],
+            cut_feed_lines: 4,
+            raster: true,
+            bit_image: true,
+            qr: true,
+            page_mode: true,
+            user_chars: true,
+            nv_bit_image: true,
+            nv_graphics: true,
+            barcodes: ALL_BARCODES.to_vec(),
+            buffer_size: 4096,
+        }
+    }
+
+    /// Parse a profile from TOML.
+    #[cfg(feature = "toml-profiles")]
+    pub fn from_toml(text: &str) -> Result<Self, ProfileError> {
+        toml::from_str(text).map_err(ProfileError::Parse)
+    }
+
+    /// Load a profile from a TOML file.
+    #[cfg(feature = "toml-profiles")]
+    pub fn load<P: AsRef<std::path::Path>>(path: P) -> Result<Self, ProfileError> {
+        let text = std::fs::read_to_string(path).map_err(ProfileError::Io)?;
+        Self::from_toml(&text)
+    }
+
+    /// Use the built-in profile called `name` or load it from a TOML file
+    /// at the path `name`.
+    #[cfg(feature = "toml-profiles")]
+    pub fn find(name: &str) -> Result<Self, ProfileError> {
+        match Self::builtin(name) {
+            Some(profile) => Ok(profile),
+            None => Self::load(name),
+        }
+    }
+
+    /// Characters per line in `font`, `None` if the font is missing.
+    pub fn columns(&self, font: Font) -> Option<usize> {
+        match font {
+            Font::A => Some(self.font_a_columns),
+            Font::B => self.font_b_columns,
+            Font::C => self.font_c_columns,
+        }
+    }
+
+    pub fn supports_font(&self, font: Font) -> bool {
+        self.columns(font).is_some()
+    }
+
+    pub fn supports_code_table(&self, table: CharCodeTable) -> bool {
+        self.code_tables.contains(&table)
+    }
+
+    pub fn supports_barcode(&self, system: BarCodeSystem) -> bool {
+        self.barcodes.contains(&system)
+    }
+
+    /// The preferred cut, `None` if there is no cutter.
+    pub fn cut_mode(&self) -> Option<CutMode> {
+        self.cut_modes.first().copied()
+    }
+
+    /// Fail if the printer cannot cut with `mode`.
+    pub(crate) fn check_cut(&self, mode: CutMode) -> Result<(), Error> {
+        match mode {
+            _ if self.cut_modes.contains(&mode) => Ok(()),
+            CutMode::Full => Err(self.unsupported("full cut")),
+            CutMode::Partial => Err(self.unsupported("partial cut")),
+        }
+    }
+
+    /// Feed the paper past the cutting position plus `dots`, so it can
+    /// be torn off by hand.
+    pub(crate) fn encode_tear_off<B: BufMut>(&self, dots: u8, buf: &mut B) {
+        EscPosCmd::PrintAndFeedLines(self.cut_feed_lines).encode_to(buf);
+        if dots > 0 {
+            EscPosCmd::PrintAndFeedDots(dots).encode_to(buf);
+        }
+    }
+
+    /// Error for commands this printer does not support.
+    pub(crate) fn unsupported(&self, what: &str) -> Error {
+        Error::Unsupported {
+            what: what.to_string(),
+            profile: self.name.clone(),
+        }
+    }
+
+    /// Adapt image options to the printer.
+    ///
+    /// The width is limited to the paper, bands are kept small enough
+    /// for the receive buffer and raster images fall back to bit images.
+    pub(crate) fn image_options(&self, options: &ImageOptions) -> Result<ImageOptions, Error> {
+        let mode = match options.mode {
+            RasterMode::Raster if self.raster => RasterMode::Raster,
+            RasterMode::BitImage if self.bit_image => RasterMode::BitImage,
+            _ if self.raster => RasterMode::Raster,
+            _ if self.bit_image => RasterMode::BitImage,
+            _ => return Err(self.unsupported("printing images")),
+        };
+        let paper_width = options.paper_width.min(self.paper_width);
+        // Leave some room for the command header
+        let max_rows = self.buffer_size.saturating_sub(16) / paper_width.div_ceil(8).max(1);
+        Ok(ImageOptions {
+            paper_width,
+            mode,
+            band_height: options.band_height.min(max_rows).max(1),
+            ..*options
+        })
+    }
+
+    /// Encode a bitmap, cropped to the paper width.
+    pub(crate) fn encode_bitmap(
+        &self,
+        bitmap: &Bitmap,
+        options: &ImageOptions,
+    ) -> Result<Vec<u8>, Error> {
+        let options = self.image_options(options)?;
+        if bitmap.width() > options.paper_width {
+            Ok(options.encode_bitmap(&bitmap.crop_width(options.paper_width)))
+        } else {
+            Ok(options.encode_bitmap(bitmap))
+        }
+    }
+
+    /// Encode a QR code, as an image if there is no native support.
+    pub(crate) fn encode_qr(&self, qr: &QrCode) -> Result<Vec<u8>, Error> {
+        if self.qr {
+            return Ok(qr.encode());
+        }
+        self.encode_qr_raster(qr)
+    }
+
+    /// Encode a QR code as an image, with smaller modules if it would be
+    /// wider than the paper.
+    pub(crate) fn encode_qr_raster(&self, qr: &QrCode) -> Result<Vec<u8>, Error> {
+        let options = self.image_options(&ImageOptions::default())?;
+        let bitmap = qr.to_bitmap_within(options.paper_width)?;
+        Ok(options.encode_bitmap(&bitmap))
+    }
+}
+
+impl Default for PrinterProfile {
+    fn default() -> Self {
+        Self::generic_80mm()
+    }
+}
+
+impl fmt::Display for ProfileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProfileError::Io(why) => write!(f, "failed to read profile: {}", why),
+            #[cfg(feature = "toml-profiles")]
+            ProfileError::Parse(why) => write!(f, "invalid profile: {}", why),
+        }
+    }
+}
+
+impl error::Error for ProfileError {}
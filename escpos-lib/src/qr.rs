@@ -0,0 +1,231 @@
+use std::{error, fmt};
+
+use super::{chars, Bitmap};
+
+/// Maximum number of data bytes the printer accepts for a QR code.
+pub const QR_MAX_DATA_LEN: usize = 7089;
+
+/// Function type `cn` of the QR code variant of `GS ( k`.
+const QR_CN: u8 = 49;
+/// Number of quiet modules around a rasterized QR code.
+const QUIET_ZONE: usize = 4;
+
+/// QR code model, see the ESC/POS documentation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QrModel {
+    Model1,
+    Model2,
+    Micro,
+}
+
+/// Error correction level, roughly the share of the code that may be damaged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QrErrorCorrection {
+    /// ~7%
+    L,
+    /// ~15%
+    M,
+    /// ~25%
+    Q,
+    /// ~30%
+    H,
+}
+
+/// Authentication used by a Wi-Fi network, see [`QrCode::wifi`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WifiAuth {
+    Open,
+    Wep,
+    Wpa,
+}
+
+/// QR code with validated settings, ready to print.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QrCode {
+    data: Vec<u8>,
+    model: QrModel,
+    module_size: u8,
+    error_correction: QrErrorCorrection,
+}
+
+/// Reasons a QR code cannot be printed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QrError {
+    /// The data is empty or too long.
+    InvalidLength(usize),
+    /// The module size is not within `1..=16`.
+    InvalidModuleSize(u8),
+    /// The data does not fit into a QR code when rasterizing.
+    Encoding(String),
+    /// The code is `width` dots wide even with the smallest modules,
+    /// wider than the `max_width` available.
+    TooWide { width: usize, max_width: usize },
+}
+
+impl QrCode {
+    /// Create a model 2 QR code with module size 3 and error correction level M.
+    pub fn new<D: AsRef<[u8]>>(data: D) -> Result<Self, QrError> {
+        let data = data.as_ref();
+        if data.is_empty() || data.len() > QR_MAX_DATA_LEN {
+            return Err(QrError::InvalidLength(data.len()));
+        }
+        Ok(QrCode {
+            data: data.to_vec(),
+            model: QrModel::Model2,
+            module_size: 3,
+            error_correction: QrErrorCorrection::M,
+        })
+    }
+
+    /// Create a QR code that lets phones join a Wi-Fi network.
+    pub fn wifi(ssid: &str, password: &str, auth: WifiAuth) -> Result<Self, QrError> {
+        let escape = |raw: &str| {
+            raw.chars().fold(String::new(), |mut s, c| {
+                if "\\;,:\"".contains(c) {
+                    s.push('\\');
+                }
+                s.push(c);
+                s
+            })
+        };
+        let data = match auth {
+            WifiAuth::Open => format!("WIFI:T:nopass;S:{};;", escape(ssid)),
+            WifiAuth::Wep => format!("WIFI:T:WEP;S:{};P:{};;", escape(ssid), escape(password)),
+            WifiAuth::Wpa => format!("WIFI:T:WPA;S:{};P:{};;", escape(ssid), escape(password)),
+        };
+        Self::new(data)
+    }
+
+    pub fn model(self, model: QrModel) -> Self {
+        QrCode { model, ..self }
+    }
+
+    /// Set the size of one module in dots, `1..=16`.
+    pub fn module_size(self, module_size: u8) -> Result<Self, QrError> {
+        if !(1..=16).contains(&module_size) {
+            return Err(QrError::InvalidModuleSize(module_size));
+        }
+        Ok(QrCode {
+            module_size,
+            ..self
+        })
+    }
+
+    pub fn error_correction(self, error_correction: QrErrorCorrection) -> Self {
+        QrCode {
+            error_correction,
+            ..self
+        }
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Encode the `GS ( k` commands to set up, store and print the code.
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let model = match self.model {
+            QrModel::Model1 => 49,
+            QrModel::Model2 => 50,
+            QrModel::Micro => 51,
+        };
+        let error_correction = match self.error_correction {
+            QrErrorCorrection::L => 48,
+            QrErrorCorrection::M => 49,
+            QrErrorCorrection::Q => 50,
+            QrErrorCorrection::H => 51,
+        };
+        let mut bytes = Vec::with_capacity(self.data.len() + 32);
+        symbol_cmd(&mut bytes, 65, &[model, 0]);
+        symbol_cmd(&mut bytes, 67, &[self.module_size]);
+        symbol_cmd(&mut bytes, 69, &[error_correction]);
+        let mut store = Vec::with_capacity(self.data.len() + 1);
+        store.push(48);
+        store.extend_from_slice(&self.data);
+        symbol_cmd(&mut bytes, 80, &store);
+        symbol_cmd(&mut bytes, 81, &[48]);
+        bytes
+    }
+
+    /// Render the code into a bitmap, including the quiet zone.
+    ///
+    /// This is meant for printers without native QR code support.
+    /// The model setting is ignored, the bitmap always contains
+    /// a model 2 code.
+    pub fn to_bitmap(&self) -> Result<Bitmap, QrError> {
+        self.to_bitmap_within(usize::MAX)
+    }
+
+    /// Render the code like [`QrCode::to_bitmap`], with smaller modules
+    /// if it would be wider than `max_width` dots.
+    pub fn to_bitmap_within(&self, max_width: usize) -> Result<Bitmap, QrError> {
+        use qrcode::{Color, EcLevel};
+        let ec_level = match self.error_correction {
+            QrErrorCorrection::L => EcLevel::L,
+            QrErrorCorrection::M => EcLevel::M,
+            QrErrorCorrection::Q => EcLevel::Q,
+            QrErrorCorrection::H => EcLevel::H,
+        };
+        let code = qrcode::QrCode::with_error_correction_level(&self.data, ec_level)
+            .map_err(|why| QrError::Encoding(why.to_string()))?;
+        let modules = code.width();
+        let colors = code.to_colors();
+        let width = modules + 2 * QUIET_ZONE;
+        let scale = (self.module_size as usize).min(max_width / width);
+        if scale == 0 {
+            return Err(QrError::TooWide { width, max_width });
+        }
+        let size = width * scale;
+        let mut bitmap = Bitmap::new(size, size);
+        for (idx, color) in colors.iter().enumerate() {
+            if *color == Color::Dark {
+                let x0 = (idx % modules + QUIET_ZONE) * scale;
+                let y0 = (idx / modules + QUIET_ZONE) * scale;
+                for y in y0..y0 + scale {
+                    for x in x0..x0 + scale {
+                        bitmap.set(x, y, true);
+                    }
+                }
+            }
+        }
+        Ok(bitmap)
+    }
+}
+
+/// Append `GS ( k pL pH cn fn params` to `bytes`.
+fn symbol_cmd(bytes: &mut Vec<u8>, function: u8, params: &[u8]) {
+    let len = params.len() + 2;
+    bytes.extend_from_slice(&[
+        chars::GS as u8,
+        b'(',
+        b'k',
+        len as u8,
+        (len >> 8) as u8,
+        QR_CN,
+        function,
+    ]);
+    bytes.extend_from_slice(params);
+}
+
+impl fmt::Display for QrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QrError::InvalidLength(len) => write!(
+                f,
+                "QR code data must have 1-{} bytes, got {}",
+                QR_MAX_DATA_LEN, len
+            ),
+            QrError::InvalidModuleSize(size) => {
+                write!(f, "invalid QR code module size {}, expected 1-16", size)
+            }
+            QrError::Encoding(why) => write!(f, "could not encode QR code: {}", why),
+            QrError::TooWide { width, max_width } => write!(
+                f,
+                "QR code is {} dots wide, only {} dots fit",
+                width, max_width
+            ),
+        }
+    }
+}
+
+impl error::Error for QrError {}
@@ -0,0 +1,123 @@
+use std::str::FromStr;
+
+use super::Error;
+
+/// What to do with control characters in untrusted text.
+///
+/// Every control character except LF starts a command on the printer,
+/// e.g. `DLE` for real-time commands, `FS` for NV memory or `FF` for
+/// page mode. Printable characters never do, since the encoder maps
+/// them to bytes of at least `0x20`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum SanitizePolicy {
+    /// Drop control characters.
+    Strip,
+    /// Print the given character instead. Control characters are
+    /// replaced by `?` themselves.
+    Replace(char),
+    /// Print control characters in caret notation, e.g. `^[` for `ESC`.
+    /// C1 controls are prefixed with `M-` like `cat -v` does.
+    Visualize,
+}
+
+impl SanitizePolicy {
+    /// Apply the policy to every control character in `raw`.
+    pub fn sanitize(&self, raw: &str) -> String {
+        let mut safe = String::with_capacity(raw.len());
+        for c in raw.chars() {
+            if c == '\n' || !c.is_control() {
+                safe.push(c);
+                continue;
+            }
+            match *self {
+                SanitizePolicy::Strip => {}
+                SanitizePolicy::Replace(replacement) if replacement.is_control() => safe.push('?'),
+                SanitizePolicy::Replace(replacement) => safe.push(replacement),
+                SanitizePolicy::Visualize => {
+                    let mut byte = c as u8;
+                    if byte >= 0x80 {
+                        safe.push_str("M-");
+                        byte -= 0x80;
+                    }
+                    safe.push('^');
+                    safe.push((byte ^ 0x40) as char);
+                }
+            }
+        }
+        safe
+    }
+}
+
+impl Default for SanitizePolicy {
+    fn default() -> Self {
+        SanitizePolicy::Replace('?')
+    }
+}
+
+impl FromStr for SanitizePolicy {
+    type Err = Error;
+
+    /// Parse `"strip"`, `"replace"` (with `?`) or `"visualize"`.
+    fn from_str(policy: &str) -> Result<Self, Self::Err> {
+        match policy {
+            "strip" => Ok(SanitizePolicy::Strip),
+            "replace" => Ok(SanitizePolicy::default()),
+            "visualize" => Ok(SanitizePolicy::Visualize),
+            _ => Err(Error::InvalidInput(format!(
+                "unknown sanitize policy {:?}, expected strip, replace or visualize",
+                policy
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::{decode, DecodedCmd, Encoder};
+
+    fn policy() -> impl Strategy<Value = SanitizePolicy> {
+        prop_oneof![
+            Just(SanitizePolicy::Strip),
+            any::<char>().prop_map(SanitizePolicy::Replace),
+            Just(SanitizePolicy::Visualize),
+        ]
+    }
+
+    /// Strings full of control characters, besides arbitrary ones.
+    fn untrusted() -> impl Strategy<Value = String> {
+        prop_oneof![
+            any::<String>(),
+            prop::collection::vec(prop_oneof![0u32..0x20, 0x7fu32..0xa0, 0x20u32..0x7f], 0..64)
+                .prop_map(|codes| codes.into_iter().filter_map(std::char::from_u32).collect()),
+        ]
+    }
+
+    proptest! {
+        /// Sanitized text may only print characters and feed lines. The
+        /// encoder switches code tables and user-defined characters on its
+        /// own, which is harmless.
+        #[test]
+        fn sanitized_text_contains_no_commands(policy in policy(), raw in untrusted()) {
+            let bytes = Encoder::new().encode(&policy.sanitize(&raw));
+            for cmd in decode(&bytes) {
+                prop_assert!(
+                    matches!(
+                        cmd,
+                        DecodedCmd::Text(_)
+                            | DecodedCmd::PrintAndLineFeed
+                            | DecodedCmd::SelectCharCodeTable(_)
+                            | DecodedCmd::SelectUserDefinedChars(_)
+                    ),
+                    "{:?} from {:?}",
+                    cmd,
+                    raw
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use bitflags::bitflags;
+
+use std::{
+    io::{self, ErrorKind},
+    time::{Duration, Instant},
+};
+
+use super::{chars, Transport};
+
+/// Time to wait for a status response if nothing else is configured.
+pub const DEFAULT_STATUS_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Real-time status that can be requested with `DLE EOT n`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusKind {
+    Printer,
+    OfflineCause,
+    ErrorCause,
+    PaperSensor,
+}
+
+/// Status that can be requested with `GS r n`.
+///
+/// Unlike real-time requests, these are processed after all
+/// previously sent data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransmitStatusKind {
+    PaperSensor,
+    Drawer,
+}
+
+bitflags! {
+    /// Status changes reported automatically by the printer, see `GS a`.
+    pub struct AsbFlags: u8 {
+        const DRAWER = 0b0000_0001;
+        const ONLINE = 0b0000_0010;
+        const ERROR = 0b0000_0100;
+        const PAPER = 0b0000_1000;
+    }
+}
+
+/// Decoded printer status.
+///
+/// Fields not covered by a particular request are left at their default.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PrinterStatus {
+    pub offline: bool,
+    /// The drawer kick-out connector pin 3 is high.
+    pub drawer_kick: bool,
+    pub cover_open: bool,
+    pub feed_button_pressed: bool,
+    pub paper_near_end: bool,
+    pub paper_out: bool,
+    pub autocutter_error: bool,
+    pub unrecoverable_error: bool,
+    pub recoverable_error: bool,
+    /// Any error occurred, including those listed above.
+    pub error: bool,
+}
+
+impl StatusKind {
+    /// All kinds, in the order [`Printer::status`](crate::Printer::status) requests them.
+    pub const ALL: [StatusKind; 4] = [
+        StatusKind::Printer,
+        StatusKind::OfflineCause,
+        StatusKind::ErrorCause,
+        StatusKind::PaperSensor,
+    ];
+
+    pub(crate) fn encode(self) -> [u8; 3] {
+        let n = match self {
+            StatusKind::Printer => 1,
+            StatusKind::OfflineCause => 2,
+            StatusKind::ErrorCause => 3,
+            StatusKind::PaperSensor => 4,
+        };
+        [chars::DLE as u8, chars::EOT as u8, n]
+    }
+}
+
+impl TransmitStatusKind {
+    pub(crate) fn encode(self) -> [u8; 3] {
+        let n = match self {
+            TransmitStatusKind::PaperSensor => 49,
+            TransmitStatusKind::Drawer => 50,
+        };
+        [chars::GS as u8, b'r', n]
+    }
+}
+
+impl AsbFlags {
+    pub(crate) fn encode(self) -> [u8; 3] {
+        [chars::GS as u8, b'a', self.bits()]
+    }
+}
+
+impl PrinterStatus {
+    /// Request all real-time status bytes and decode them.
+    pub(crate) fn query<T: Transport>(port: &mut T, timeout: Duration) -> io::Result<Self> {
+        let mut status = PrinterStatus::default();
+        for kind in &StatusKind::ALL {
+            let byte = request(port, *kind, timeout)?;
+            status.update(*kind, byte);
+        }
+        Ok(status)
+    }
+
+    /// Merge the response `byte` to a `DLE EOT` request of `kind`.
+    pub fn update(&mut self, kind: StatusKind, byte: u8) {
+        let bit = |n: u8| byte & (1 << n) != 0;
+        match kind {
+            StatusKind::Printer => {
+                self.drawer_kick = bit(2);
+                self.offline = bit(3);
+                self.feed_button_pressed = bit(6);
+            }
+            StatusKind::OfflineCause => {
+                self.cover_open = bit(2);
+                self.feed_button_pressed |= bit(3);
+                self.paper_out |= bit(5);
+                self.error |= bit(6);
+            }
+            StatusKind::ErrorCause => {
+                self.autocutter_error = bit(3);
+                self.unrecoverable_error = bit(5);
+                self.recoverable_error = bit(6);
+                self.error |= bit(3) || bit(5) || bit(6);
+            }
+            StatusKind::PaperSensor => {
+                self.paper_near_end = bit(2) || bit(3);
+                self.paper_out |= bit(5) || bit(6);
+            }
+        }
+    }
+
+    /// Merge the response `byte` to a `GS r` request of `kind`.
+    pub fn update_transmitted(&mut self, kind: TransmitStatusKind, byte: u8) {
+        match kind {
+            TransmitStatusKind::PaperSensor => {
+                self.paper_near_end = byte & 0b0011 != 0;
+                self.paper_out = byte & 0b1100 != 0;
+            }
+            TransmitStatusKind::Drawer => self.drawer_kick = byte & 0b0001 != 0,
+        }
+    }
+
+    /// Decode the four bytes of an automatic status back message.
+    pub fn from_asb(bytes: [u8; 4]) -> Self {
+        let bit = |byte: u8, n: u8| byte & (1 << n) != 0;
+        let autocutter_error = bit(bytes[1], 3);
+        let unrecoverable_error = bit(bytes[1], 5);
+        let recoverable_error = bit(bytes[1], 6);
+        PrinterStatus {
+            drawer_kick: bit(bytes[0], 2),
+            offline: bit(bytes[0], 3),
+            cover_open: bit(bytes[0], 5),
+            feed_button_pressed: bit(bytes[0], 6),
+            paper_near_end: bytes[2] & 0b0011 != 0,
+            paper_out: bytes[2] & 0b1100 != 0,
+            autocutter_error,
+            unrecoverable_error,
+            recoverable_error,
+            error: autocutter_error || unrecoverable_error || recoverable_error,
+        }
+    }
+
+    /// Whether the printer is able to print right now.
+    pub fn is_ready(&self) -> bool {
+        !(self.offline || self.cover_open || self.paper_out || self.error)
+    }
+}
+
+/// Send a real-time status request and wait for its response.
+pub(crate) fn request<T: Transport>(
+    port: &mut T,
+    kind: StatusKind,
+    timeout: Duration,
+) -> io::Result<u8> {
+    port.write_all(&kind.encode())?;
+    port.flush()?;
+    // Real-time responses look like 0xx1xx10, skip anything else
+    // like pending automatic status back messages
+    read_matching(port, timeout, |byte| byte & 0b1001_0011 == 0b0001_0010)
+}
+
+/// Send a `GS r` status request and wait for its response.
+pub(crate) fn request_transmitted<T: Transport>(
+    port: &mut T,
+    kind: TransmitStatusKind,
+    timeout: Duration,
+) -> io::Result<u8> {
+    port.write_all(&kind.encode())?;
+    port.flush()?;
+    // Responses look like 0xx0xxxx
+    read_matching(port, timeout, |byte| byte & 0b1001_0000 == 0)
+}
+
+/// Wait for the next automatic status back message.
+pub(crate) fn read_asb<T: Transport>(port: &mut T, timeout: Duration) -> io::Result<[u8; 4]> {
+    let deadline = Instant::now() + timeout;
+    // The first byte looks like 0xx1xx00
+    let first = read_matching(port, timeout, |byte| byte & 0b1001_0011 == 0b0001_0000)?;
+    let mut bytes = [first, 0, 0, 0];
+    for byte in &mut bytes[1..] {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        *byte = read_matching(port, remaining, |_| true)?;
+    }
+    Ok(bytes)
+}
+
+/// Read single bytes until one satisfies `matches` or the timeout elapses.
+pub(crate) fn read_matching<T, F>(port: &mut T, timeout: Duration, matches: F) -> io::Result<u8>
+where
+    T: Transport,
+    F: Fn(u8) -> bool,
+{
+    let deadline = Instant::now() + timeout;
+    let mut buf = [0_u8];
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining == Duration::from_secs(0) {
+            return Err(io::Error::new(
+                ErrorKind::TimedOut,
+                "printer did not answer status request",
+            ));
+        }
+        match port.read_timeout(&mut buf, remaining)? {
+            0 => {
+                return Err(io::Error::new(
+                    ErrorKind::UnexpectedEof,
+                    "printer closed the connection",
+                ))
+            }
+            _ if matches(buf[0]) => return Ok(buf[0]),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use bytes::BufMut;
+
+use std::fmt;
+
+use super::{
+    cmds::{Font, Justification, UnderlineMode},
+    Encode, Encoder, EscPosCmd, Styled,
+};
+
+/// Text related state of the printer.
+///
+/// Every field is changed by its own command, so switching between two
+/// states never touches unrelated settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrintState {
+    pub font: Font,
+    pub emphasized: bool,
+    pub underline: UnderlineMode,
+    pub reverse: bool,
+    /// Width multiplier, 1 to 8.
+    pub width: u8,
+    /// Height multiplier, 1 to 8.
+    pub height: u8,
+    pub justification: Justification,
+    /// Line spacing in dots, `None` for the default spacing.
+    pub line_spacing: Option<u8>,
+    /// Space right of every character in dots.
+    pub char_spacing: u8,
+    pub upside_down: bool,
+    pub rotated: bool,
+}
+
+/// Writer keeping track of the printer's text state.
+///
+/// Styles are pushed and popped like a stack. Only the commands for
+/// settings that actually differ are written, and popping a style
+/// restores the enclosing one. Text is encoded with `encoder`, commands
+/// are written as they are.
+#[derive(Debug)]
+pub struct StyleWriter<'e, B> {
+    inner: B,
+    encoder: &'e mut Encoder,
+    stack: Vec<PrintState>,
+}
+
+impl PrintState {
+    /// Append the commands to switch the printer from this state to `to`.
+    pub fn encode_diff<B: BufMut>(&self, to: &PrintState, buf: &mut B) {
+        for cmd in self.diff(to) {
+            cmd.encode_to(buf);
+        }
+    }
+
+    fn diff(&self, to: &PrintState) -> Vec<EscPosCmd<'static>> {
+        use EscPosCmd::*;
+        let mut cmds = Vec::new();
+        if self.font != to.font {
+            cmds.push(SelectFont(to.font));
+        }
+        if self.emphasized != to.emphasized {
+            cmds.push(SelectEmphasized(to.emphasized));
+        }
+        if self.underline != to.underline {
+            cmds.push(SelectUnderlineMode(to.underline));
+        }
+        if self.reverse != to.reverse {
+            cmds.push(SelectReversePrinting(to.reverse));
+        }
+        if (self.width, self.height) != (to.width, to.height) {
+            cmds.push(SelectCharacterSize(to.width, to.height));
+        }
+        if self.justification != to.justification {
+            cmds.push(SelectJustification(to.justification));
+        }
+        if self.line_spacing != to.line_spacing {
+            cmds.push(match to.line_spacing {
+                Some(dots) => SetLineSpacing(dots),
+                None => SelectDefaultLineSpacing,
+            });
+        }
+        if self.char_spacing != to.char_spacing {
+            cmds.push(SetRightSideSpacing(to.char_spacing));
+        }
+        if self.upside_down != to.upside_down {
+            cmds.push(SelectUpsideDown(to.upside_down));
+        }
+        if self.rotated != to.rotated {
+            cmds.push(SelectRotation(to.rotated));
+        }
+        cmds
+    }
+}
+
+impl Default for PrintState {
+    /// The state after initializing the printer.
+    fn default() -> Self {
+        PrintState {
+            font: Font::A,
+            emphasized: false,
+            underline: UnderlineMode::Off,
+            reverse: false,
+            width: 1,
+            height: 1,
+            justification: Justification::Left,
+            line_spacing: None,
+            char_spacing: 0,
+            upside_down: false,
+            rotated: false,
+        }
+    }
+}
+
+impl<'e, B: BufMut> StyleWriter<'e, B> {
+    /// Wrap `inner`, assuming the printer is in its initial state.
+    pub fn new(inner: B, encoder: &'e mut Encoder) -> Self {
+        Self::with_state(inner, encoder, PrintState::default())
+    }
+
+    /// Wrap `inner`, assuming the printer is in `state`.
+    pub fn with_state(inner: B, encoder: &'e mut Encoder, state: PrintState) -> Self {
+        StyleWriter {
+            inner,
+            encoder,
+            stack: vec![state],
+        }
+    }
+
+    /// The state the printer is in after everything written so far.
+    pub fn current(&self) -> &PrintState {
+        self.stack.last().expect("style stack is never empty")
+    }
+
+    /// Switch to `state` until the matching [`StyleWriter::pop`].
+    pub fn push(&mut self, state: PrintState) {
+        let current = self.stack.last().expect("style stack is never empty");
+        current.encode_diff(&state, &mut self.inner);
+        self.stack.push(state);
+    }
+
+    /// Restore the state active before the last [`StyleWriter::push`].
+    ///
+    /// The initial state is never popped.
+    pub fn pop(&mut self) {
+        if self.stack.len() > 1 {
+            let state = self.stack.pop().expect("style stack is never empty");
+            let current = self.stack.last().expect("style stack is never empty");
+            state.encode_diff(current, &mut self.inner);
+        }
+    }
+
+    /// Write `text` together with its styles, e.g. a
+    /// [`FormattedStr`](crate::FormattedStr).
+    pub fn write_styled<S: Styled + ?Sized>(&mut self, text: &S) -> &mut Self {
+        text.write_styled(self);
+        self
+    }
+
+    /// Restore the initial state and return the inner buffer.
+    pub fn finish(mut self) -> B {
+        while self.stack.len() > 1 {
+            self.pop();
+        }
+        self.inner
+    }
+}
+
+impl<B: BufMut> fmt::Write for StyleWriter<'_, B> {
+    /// Encode `s` for the printer's code tables.
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.put_slice(&self.encoder.encode(s));
+        Ok(())
+    }
+}
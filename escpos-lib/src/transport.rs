@@ -0,0 +1,365 @@
+use serialport::SerialPort;
+
+use std::{
+    collections::VecDeque,
+    fs::{File, OpenOptions},
+    io::{self, ErrorKind, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    path::Path,
+    time::Duration,
+};
+
+/// Port used by network printers for raw ESC/POS data (JetDirect).
+pub const RAW_TCP_PORT: u16 = 9100;
+/// Capacity of a [`BufferedTransport`] if nothing else is configured.
+pub const DEFAULT_BUFFER_SIZE: usize = 4096;
+
+/// Connection to a printer.
+///
+/// Everything the [`Printer`](crate::Printer) emits goes through this,
+/// so the same commands work for all supported backends.
+pub trait Transport: Write {
+    /// Read bytes sent back by the printer, waiting at most `timeout`.
+    ///
+    /// Fails with [`ErrorKind::TimedOut`] if nothing arrived in time and
+    /// with [`ErrorKind::Unsupported`] if the backend cannot receive data.
+    fn read_timeout(&mut self, buf: &mut [u8], timeout: Duration) -> io::Result<usize>;
+}
+
+/// Printer connected to a serial port.
+pub struct SerialTransport<P: SerialPort> {
+    port: P,
+}
+
+/// Printer reachable via raw TCP, usually on port [`RAW_TCP_PORT`].
+pub struct TcpTransport {
+    stream: TcpStream,
+}
+
+/// Printer behind a file or device node, i.e. `/dev/usb/lp0`.
+pub struct FileTransport {
+    file: File,
+}
+
+/// In-memory capture of everything written to the printer.
+///
+/// Useful for tests and for inspecting the generated command stream.
+/// Responses of the simulated printer can be queued with
+/// [`MemoryTransport::push_response`].
+#[derive(Debug, Default, Clone)]
+pub struct MemoryTransport {
+    buf: Vec<u8>,
+    responses: VecDeque<u8>,
+}
+
+/// Collects small writes and sends them to `T` in large chunks.
+///
+/// The buffer is written when it is full, on [`Write::flush`] and
+/// before reading, so status requests are not held back.
+pub struct BufferedTransport<T: Transport> {
+    inner: T,
+    buf: Vec<u8>,
+}
+
+impl<P: SerialPort> SerialTransport<P> {
+    pub fn new(port: P) -> Self {
+        SerialTransport { port }
+    }
+
+    /// Access the underlying serial port.
+    pub fn port(&self) -> &P {
+        &self.port
+    }
+
+    /// Access the underlying serial port mutably.
+    pub fn port_mut(&mut self) -> &mut P {
+        &mut self.port
+    }
+
+    pub fn into_inner(self) -> P {
+        self.port
+    }
+}
+
+impl TcpTransport {
+    /// Connect to a network printer.
+    ///
+    /// # Arguments
+    /// - `addr`: Address of the printer, i.e. `("192.168.0.10", RAW_TCP_PORT)`.
+    /// - `timeout`: Write timeout, `None` blocks forever.
+    pub fn connect<A: ToSocketAddrs>(addr: A, timeout: Option<Duration>) -> io::Result<Self> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_write_timeout(timeout)?;
+        stream.set_nodelay(true)?;
+        Ok(TcpTransport { stream })
+    }
+
+    pub fn from_stream(stream: TcpStream) -> Self {
+        TcpTransport { stream }
+    }
+
+    pub fn into_inner(self) -> TcpStream {
+        self.stream
+    }
+}
+
+impl FileTransport {
+    /// Open the file or device node at `path` for writing.
+    ///
+    /// Regular files are appended to, so multiple jobs can be captured
+    /// in one file.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(FileTransport { file })
+    }
+
+    pub fn from_file(file: File) -> Self {
+        FileTransport { file }
+    }
+
+    pub fn into_inner(self) -> File {
+        self.file
+    }
+}
+
+impl MemoryTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// All bytes written so far.
+    pub fn bytes(&self) -> &[u8] {
+        &self.buf
+    }
+
+    /// Take all bytes written so far, leaving the buffer empty.
+    pub fn take(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.buf)
+    }
+
+    /// Queue bytes to be read as if they were sent by the printer.
+    pub fn push_response(&mut self, bytes: &[u8]) {
+        self.responses.extend(bytes);
+    }
+
+    pub fn into_inner(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+impl<T: Transport> BufferedTransport<T> {
+    /// Buffer up to [`DEFAULT_BUFFER_SIZE`] bytes.
+    pub fn new(inner: T) -> Self {
+        Self::with_capacity(DEFAULT_BUFFER_SIZE, inner)
+    }
+
+    pub fn with_capacity(capacity: usize, inner: T) -> Self {
+        BufferedTransport {
+            inner,
+            buf: Vec::with_capacity(capacity),
+        }
+    }
+
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    fn write_buf(&mut self) -> io::Result<()> {
+        let res = self.inner.write_all(&self.buf);
+        self.buf.clear();
+        res
+    }
+}
+
+impl<P: SerialPort> Write for SerialTransport<P> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.port.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.port.flush()
+    }
+}
+
+impl Write for TcpTransport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+impl Write for FileTransport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl Write for MemoryTransport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<T: Transport> Write for BufferedTransport<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.buf.len() + buf.len() > self.buf.capacity() {
+            self.write_buf()?;
+        }
+        if buf.len() >= self.buf.capacity() {
+            self.inner.write(buf)
+        } else {
+            self.buf.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_buf()?;
+        self.inner.flush()
+    }
+}
+
+impl<T: Transport> Drop for BufferedTransport<T> {
+    fn drop(&mut self) {
+        // Nobody is left to report the error to
+        let _ = self.flush();
+    }
+}
+
+impl<T: Transport + ?Sized> Transport for Box<T> {
+    fn read_timeout(&mut self, buf: &mut [u8], timeout: Duration) -> io::Result<usize> {
+        (**self).read_timeout(buf, timeout)
+    }
+}
+
+impl<P: SerialPort> Transport for SerialTransport<P> {
+    fn read_timeout(&mut self, buf: &mut [u8], timeout: Duration) -> io::Result<usize> {
+        let previous = self.port.timeout();
+        self.port.set_timeout(timeout)?;
+        let res = self.port.read(buf);
+        self.port.set_timeout(previous)?;
+        res
+    }
+}
+
+impl Transport for TcpTransport {
+    fn read_timeout(&mut self, buf: &mut [u8], timeout: Duration) -> io::Result<usize> {
+        self.stream.set_read_timeout(Some(timeout))?;
+        match self.stream.read(buf) {
+            Err(why) if why.kind() == ErrorKind::WouldBlock => {
+                Err(io::Error::new(ErrorKind::TimedOut, why))
+            }
+            res => res,
+        }
+    }
+}
+
+impl Transport for FileTransport {
+    fn read_timeout(&mut self, _buf: &mut [u8], _timeout: Duration) -> io::Result<usize> {
+        // Reading device nodes blocks without any way to time out
+        Err(io::Error::new(
+            ErrorKind::Unsupported,
+            "file transports cannot read printer responses",
+        ))
+    }
+}
+
+impl<T: Transport> Transport for BufferedTransport<T> {
+    fn read_timeout(&mut self, buf: &mut [u8], timeout: Duration) -> io::Result<usize> {
+        // The printer cannot answer requests it did not receive yet
+        self.flush()?;
+        self.inner.read_timeout(buf, timeout)
+    }
+}
+
+impl Transport for MemoryTransport {
+    fn read_timeout(&mut self, buf: &mut [u8], _timeout: Duration) -> io::Result<usize> {
+        if self.responses.is_empty() && !buf.is_empty() {
+            return Err(io::Error::new(ErrorKind::TimedOut, "no response queued"));
+        }
+        let len = buf.len().min(self.responses.len());
+        for (dst, src) in buf.iter_mut().zip(self.responses.drain(..len)) {
+            *dst = src;
+        }
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Encode, EscPosCmd, Printer, StatusKind};
+
+    #[test]
+    fn printer_output_is_captured() {
+        let mut printer = Printer::new(MemoryTransport::new()).unwrap();
+        assert_eq!(printer.transport_mut().take(), b"\x1b@");
+        printer.write("Grüße\n").unwrap();
+        assert_eq!(printer.transport_mut().take(), b"Gr\x81\xe1e\n");
+
+        printer.cut().unwrap();
+        let profile = printer.profile().clone();
+        let mut expected = EscPosCmd::PrintAndFeedLines(profile.cut_feed_lines).encode();
+        if let Some(mode) = profile.cut_mode() {
+            EscPosCmd::CutPaper(mode).encode_to(&mut expected);
+        }
+        assert_eq!(printer.into_transport().into_inner(), expected);
+    }
+
+    #[test]
+    fn printer_reads_queued_responses() {
+        let mut printer = Printer::new(MemoryTransport::new()).unwrap();
+        printer.transport_mut().take();
+        printer.transport_mut().push_response(&[0x12]);
+        assert_eq!(printer.real_time_status(StatusKind::Printer).unwrap(), 0x12);
+        assert_eq!(printer.transport().bytes(), b"\x10\x04\x01");
+        // Nothing is queued anymore, so the printer seems to be unable to answer
+        assert!(printer.check_ready().is_ok());
+    }
+
+    #[test]
+    fn buffered_writes_are_sent_when_full_or_flushed() {
+        let mut transport = BufferedTransport::with_capacity(8, MemoryTransport::new());
+        transport.write_all(b"abc").unwrap();
+        transport.write_all(b"defgh").unwrap();
+        assert_eq!(transport.get_ref().bytes(), b"");
+        transport.write_all(b"i").unwrap();
+        assert_eq!(transport.get_ref().bytes(), b"abcdefgh");
+        transport.flush().unwrap();
+        assert_eq!(transport.get_ref().bytes(), b"abcdefghi");
+    }
+
+    #[test]
+    fn large_writes_bypass_the_buffer() {
+        let mut transport = BufferedTransport::with_capacity(8, MemoryTransport::new());
+        transport.write_all(b"ab").unwrap();
+        transport.write_all(b"0123456789").unwrap();
+        assert_eq!(transport.get_ref().bytes(), b"ab0123456789");
+    }
+
+    #[test]
+    fn buffered_requests_are_sent_before_reading() {
+        let transport = BufferedTransport::new(MemoryTransport::new());
+        let mut printer = Printer::new(transport).unwrap();
+        assert_eq!(printer.transport().get_ref().bytes(), b"");
+        printer.transport_mut().get_mut().push_response(&[0x12]);
+        assert_eq!(printer.real_time_status(StatusKind::Printer).unwrap(), 0x12);
+        assert_eq!(printer.transport().get_ref().bytes(), b"\x1b@\x10\x04\x01");
+    }
+}
@@ -0,0 +1,171 @@
+use std::{error, fmt};
+
+use super::{chars, cmds::Font, emoji::EMOJI, Bitmap};
+
+/// First character code that can be redefined.
+pub(crate) const FIRST_USER_CHAR: u8 = 0x20;
+/// Last character code that can be redefined.
+const LAST_USER_CHAR: u8 = 0x7e;
+/// Bytes per column in `ESC &`, i.e. 24 dots.
+const COLUMN_BYTES: usize = 3;
+
+/// Glyphs for characters missing from the printer's code tables.
+///
+/// The glyphs are downloaded to the printer with
+/// [`Printer::define_user_chars`](crate::Printer::define_user_chars)
+/// and replace the character codes `0x20` to `0x7e` while user-defined
+/// characters are selected. The [`Encoder`](crate::Encoder) selects them
+/// only around the characters defined here, so regular text is not
+/// affected.
+///
+/// Glyphs are drawn for the 12x24 dots cell of font A, font B gets a
+/// scaled down copy in its 9x17 dots cell.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct UserChars {
+    glyphs: Vec<(char, Bitmap)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UserCharError {
+    /// ASCII characters are always printed from the code tables.
+    Ascii(char),
+    /// Only 95 characters can be defined at the same time.
+    Full(char),
+}
+
+impl UserChars {
+    pub fn new() -> Self {
+        UserChars { glyphs: Vec::new() }
+    }
+
+    /// Define the glyph printed for `c`, replacing any previous one.
+    ///
+    /// Glyphs larger than 12x24 dots are scaled down, all glyphs are
+    /// centered in the character cell.
+    pub fn define(&mut self, c: char, glyph: &Bitmap) -> Result<&mut Self, UserCharError> {
+        if c.is_ascii() {
+            return Err(UserCharError::Ascii(c));
+        }
+        let cell = cell(glyph);
+        let full = self.glyphs.len() > usize::from(LAST_USER_CHAR - FIRST_USER_CHAR);
+        match self.glyphs.iter_mut().find(|(defined, _)| *defined == c) {
+            Some((_, old)) => *old = cell,
+            None if full => return Err(UserCharError::Full(c)),
+            None => self.glyphs.push((c, cell)),
+        }
+        Ok(self)
+    }
+
+    /// Define `c` using the bundled glyph of the emoji.
+    ///
+    /// Returns `false` if there is no such glyph.
+    pub fn emoji(&mut self, c: char) -> Result<bool, UserCharError> {
+        match emoji_glyph(c) {
+            Some(glyph) => self.define(c, &glyph).map(|_| true),
+            None => Ok(false),
+        }
+    }
+
+    /// The defined characters, in the order of their codes.
+    pub fn chars(&self) -> impl Iterator<Item = char> + '_ {
+        self.glyphs.iter().map(|(c, _)| *c)
+    }
+
+    pub fn len(&self) -> usize {
+        self.glyphs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.glyphs.is_empty()
+    }
+
+    /// Encode the `ESC & 3 c1 c2 ...` command defining all glyphs for
+    /// `font`, which must be selected before.
+    ///
+    /// Returns nothing for fonts without a known cell size.
+    pub(crate) fn encode(&self, font: Font) -> Vec<u8> {
+        let (width, height) = match font {
+            Font::A => (12, 24),
+            Font::B => (9, 17),
+            Font::C => return Vec::new(),
+        };
+        if self.glyphs.is_empty() {
+            return Vec::new();
+        }
+        let last = FIRST_USER_CHAR + self.glyphs.len() as u8 - 1;
+        let mut bytes = vec![
+            chars::ESC as u8,
+            b'&',
+            COLUMN_BYTES as u8,
+            FIRST_USER_CHAR,
+            last,
+        ];
+        for (_, glyph) in &self.glyphs {
+            let glyph = glyph.shrink(width, height);
+            bytes.push(width as u8);
+            for x in 0..width {
+                let mut column = [0; COLUMN_BYTES];
+                for y in 0..height {
+                    if glyph.get(x, y) {
+                        column[y / 8] |= 0x80 >> (y % 8);
+                    }
+                }
+                bytes.extend_from_slice(&column);
+            }
+        }
+        bytes
+    }
+}
+
+/// The bundled glyph for emoji `c`, if any.
+///
+/// The set covers common shopping categories like food, drinks and
+/// household goods.
+pub fn emoji_glyph(c: char) -> Option<Bitmap> {
+    let (_, rows) = EMOJI.iter().find(|(emoji, _)| *emoji == c)?;
+    let mut glyph = Bitmap::new(rows[0].len(), rows.len());
+    for (y, row) in rows.iter().enumerate() {
+        for (x, dot) in row.bytes().enumerate() {
+            glyph.set(x, y, dot == b'#');
+        }
+    }
+    Some(glyph)
+}
+
+/// Fit `glyph` into a 12x24 dots cell.
+fn cell(glyph: &Bitmap) -> Bitmap {
+    let mut cell = Bitmap::new(12, 24);
+    let (width, height) = (glyph.width(), glyph.height());
+    if width == 0 || height == 0 {
+        return cell;
+    }
+    // Keep the aspect ratio when scaling down
+    let (width, height) = if width * 24 > height * 12 {
+        (width.min(12), height * width.min(12) / width)
+    } else {
+        (width * height.min(24) / height, height.min(24))
+    };
+    let glyph = glyph.shrink(width.max(1), height.max(1));
+    let (left, top) = ((12 - glyph.width()) / 2, (24 - glyph.height()) / 2);
+    for y in 0..glyph.height() {
+        for x in 0..glyph.width() {
+            cell.set(left + x, top + y, glyph.get(x, y));
+        }
+    }
+    cell
+}
+
+impl fmt::Display for UserCharError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UserCharError::Ascii(c) => write!(f, "cannot redefine ASCII character {:?}", c),
+            UserCharError::Full(c) => write!(
+                f,
+                "cannot define {:?}, all user-defined characters are in use",
+                c
+            ),
+        }
+    }
+}
+
+impl error::Error for UserCharError {}
@@ -1,17 +1,341 @@
-use escpos_lib::Printer;
+use escpos_lib::{
+    decode, Bitmap, DecodedCmd, Dithering, EscPosCmd, Image, NvKey, PixelFormat, PreviewTransport,
+    Printer, PrinterProfile, SerialTransport, Transport, NV_WRITES_PER_DAY, RAW_TCP_PORT,
+};
 use serialport::{DataBits, FlowControl, Parity, StopBits};
+use structopt::StructOpt;
 
-use std::time::Duration;
+use std::{
+    fs::{File, OpenOptions},
+    io::{ErrorKind, Read, Write},
+    net::{TcpListener, TcpStream},
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// Time a client may stay silent before its job is considered complete.
+const JOB_TIMEOUT: Duration = Duration::from_secs(30);
+/// Time a client may take to send a whole job, so a slow client cannot
+/// block the printer forever.
+const JOB_DEADLINE: Duration = Duration::from_secs(300);
+/// Largest command forwarded when sanitizing, commands have to be
+/// received completely before they can be checked.
+const MAX_COMMAND: usize = 1024 * 1024;
+
+#[derive(Debug, StructOpt)]
+struct Opt {
+    /// Render receipts as PNG files into this directory instead of printing
+    #[structopt(long, parse(from_os_str))]
+    preview: Option<PathBuf>,
+    /// Printer model, either a built-in profile name or a TOML file
+    #[structopt(long, default_value = "tm-t88iii")]
+    profile: String,
+    #[structopt(subcommand)]
+    cmd: Option<Command>,
+}
+
+#[derive(Debug, StructOpt)]
+enum Command {
+    /// Print a test page, the default
+    Test,
+    /// Store a PNG image in the printer's NV memory, e.g. a header logo
+    ///
+    /// NV memory only survives a limited number of writes, upload the
+    /// image once and print it with `print-logo` afterwards.
+    UploadLogo {
+        #[structopt(parse(from_os_str))]
+        image: PathBuf,
+        /// Two character key of the graphic. Printers without NV graphics
+        /// store the image as bit image 1 instead
+        #[structopt(long)]
+        key: Option<NvKey>,
+        /// Upload even if the image might already be stored
+        #[structopt(long)]
+        force: bool,
+    },
+    /// List the keys of the NV graphics stored in the printer
+    ListLogos,
+    /// Print an image stored in NV memory
+    PrintLogo {
+        /// Key of the graphic or number of the bit image
+        key: Option<NvKey>,
+    },
+    /// Forward raw ESC/POS jobs received via TCP to the printer, like a
+    /// JetDirect network printer
+    ///
+    /// Connections are handled one after another, a job ends when the
+    /// client closes the connection or stays silent for 30 seconds.
+    Serve {
+        /// Address to listen on
+        #[structopt(long, default_value = "0.0.0.0")]
+        address: String,
+        /// Port to listen on, 9100 by default
+        #[structopt(long)]
+        port: Option<u16>,
+        /// Drop commands that outlast the job or reach other devices: NV
+        /// memory and configuration writes, drawer pulses, real-time and
+        /// status commands, as well as unknown ones. Everything else is
+        /// forwarded unchanged
+        #[structopt(long)]
+        sanitize: bool,
+        /// Append a line for every connection to this file
+        #[structopt(long, parse(from_os_str))]
+        log: Option<PathBuf>,
+    },
+}
 
 fn main() {
-    let port = serialport::new("/dev/serial0", 9600)
-        .data_bits(DataBits::Eight)
-        .parity(Parity::None)
-        .stop_bits(StopBits::One)
-        .flow_control(FlowControl::None)
-        .timeout(Duration::from_secs(10))
-        .open_native()
-        .expect("Init serial failed");
-    let mut printer = Printer::new(port).expect("Init writing failed");
-    printer.print_test_page().expect("Test failed");
+    let opt = Opt::from_args();
+    let profile = PrinterProfile::find(&opt.profile).expect("Loading profile failed");
+    let port: Box<dyn Transport> = match opt.preview {
+        Some(dir) => Box::new(PreviewTransport::new(dir).expect("Init preview failed")),
+        None => {
+            let port = serialport::new("/dev/serial0", 9600)
+                .data_bits(DataBits::Eight)
+                .parity(Parity::None)
+                .stop_bits(StopBits::One)
+                .flow_control(FlowControl::None)
+                .timeout(Duration::from_secs(10))
+                .open_native()
+                .expect("Init serial failed");
+            Box::new(SerialTransport::new(port))
+        }
+    };
+    let mut printer = Printer::with_profile(port, profile).expect("Init writing failed");
+    match opt.cmd.unwrap_or(Command::Test) {
+        Command::Test => printer.print_test_page().expect("Test failed"),
+        Command::UploadLogo { image, key, force } => upload_logo(&mut printer, &image, key, force),
+        Command::ListLogos => {
+            for key in printer.nv_graphic_keys().expect("Listing failed") {
+                println!("{}", key);
+            }
+        }
+        Command::PrintLogo { key } => {
+            let key = key.unwrap_or_else(|| default_key(printer.profile()));
+            printer.print_nv(key).expect("Printing logo failed");
+            printer.cut().expect("Cutting failed");
+        }
+        Command::Serve {
+            address,
+            port,
+            sanitize,
+            log,
+        } => {
+            let listener = TcpListener::bind((address.as_str(), port.unwrap_or(RAW_TCP_PORT)))
+                .expect("Binding failed");
+            serve(&mut printer, listener, sanitize, log.as_deref());
+        }
+    }
+}
+
+/// Print the jobs of all clients connecting to `listener`.
+///
+/// Other clients wait until the current job is finished, so jobs never
+/// interleave.
+fn serve<T: Transport>(
+    printer: &mut Printer<T>,
+    listener: TcpListener,
+    sanitize: bool,
+    log: Option<&Path>,
+) {
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(why) => {
+                eprintln!("Accepting connection failed: {}", why);
+                continue;
+            }
+        };
+        let peer = stream
+            .peer_addr()
+            .map(|addr| addr.to_string())
+            .unwrap_or_else(|_| String::from("unknown"));
+        let start = Instant::now();
+        let mut received = 0;
+        let result = forward_job(printer, stream, sanitize, &mut received);
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+        let entry = format!(
+            "{} {} {} bytes in {:.1?}: {}",
+            timestamp,
+            peer,
+            received,
+            start.elapsed(),
+            result.map_or_else(|why| why.to_string(), |()| String::from("ok"))
+        );
+        println!("{}", entry);
+        if let Some(path) = log {
+            let appended = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut file| writeln!(file, "{}", entry));
+            if let Err(why) = appended {
+                eprintln!("Writing job log failed: {}", why);
+            }
+        }
+    }
+}
+
+/// Print everything received from `stream` until the client is done.
+///
+/// Every job starts with the default settings. Bytes are written to the
+/// printer as they arrive, when sanitizing only the complete commands
+/// that are [local to the job](DecodedCmd::is_job_local).
+fn forward_job<T: Transport>(
+    printer: &mut Printer<T>,
+    mut stream: TcpStream,
+    sanitize: bool,
+    received: &mut usize,
+) -> escpos_lib::Result<()> {
+    let deadline = Instant::now() + JOB_DEADLINE;
+    printer.exec(EscPosCmd::InitializePrinter)?;
+    let mut buf = [0; 4096];
+    let mut pending = Vec::new();
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining == Duration::ZERO {
+            return Err(escpos_lib::Error::InvalidInput(format!(
+                "job took longer than {:?}",
+                JOB_DEADLINE
+            )));
+        }
+        stream.set_read_timeout(Some(remaining.min(JOB_TIMEOUT)))?;
+        let len = match stream.read(&mut buf) {
+            Ok(0) => break,
+            Ok(len) => len,
+            Err(why) if matches!(why.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                // The client stayed silent, so the job is complete. If the
+                // deadline ended the wait instead, the check above fails it.
+                if Instant::now() < deadline {
+                    break;
+                }
+                continue;
+            }
+            Err(why) => return Err(why.into()),
+        };
+        *received += len;
+        if !sanitize {
+            printer.transport_mut().write_all(&buf[..len])?;
+            continue;
+        }
+        pending.extend_from_slice(&buf[..len]);
+        let done = forward_job_local(printer, &pending)?;
+        pending.drain(..done);
+        if pending.len() > MAX_COMMAND {
+            return Err(escpos_lib::Error::InvalidInput(String::from(
+                "command too large",
+            )));
+        }
+    }
+    // A command still pending was cut off and is dropped
+    Ok(printer.transport_mut().flush()?)
+}
+
+/// Write the complete commands in `bytes` that are local to the job and
+/// return the length of those processed.
+fn forward_job_local<T: Transport>(
+    printer: &mut Printer<T>,
+    bytes: &[u8],
+) -> escpos_lib::Result<usize> {
+    let mut decoder = decode(bytes);
+    loop {
+        let start = decoder.position();
+        match decoder.next() {
+            None | Some(DecodedCmd::Incomplete(_)) => return Ok(start),
+            Some(cmd) if cmd.is_job_local() => {
+                let raw = &bytes[start..decoder.position()];
+                printer.transport_mut().write_all(raw)?;
+            }
+            Some(_) => {}
+        }
+    }
+}
+
+/// Store the image at `path` in NV memory, unless it is already there.
+fn upload_logo<T: Transport>(
+    printer: &mut Printer<T>,
+    path: &Path,
+    key: Option<NvKey>,
+    force: bool,
+) {
+    let bitmap = load_bitmap(path, printer.profile().paper_width);
+    let key = key.unwrap_or_else(|| default_key(printer.profile()));
+    eprintln!(
+        "Warning: NV memory survives only a limited number of writes, \
+         do not store images more than {} times a day",
+        NV_WRITES_PER_DAY
+    );
+    match key {
+        NvKey::Graphic(_) => {
+            let keys = printer.nv_graphic_keys().expect("Listing failed");
+            if keys.contains(&key) && !force {
+                eprintln!(
+                    "Graphic {} is already stored, use --force to replace it",
+                    key
+                );
+                return;
+            }
+            printer
+                .define_nv_graphic(key, &bitmap)
+                .expect("Upload failed");
+        }
+        NvKey::BitImage(_) => {
+            if !force {
+                eprintln!("This replaces all NV bit images, use --force to continue");
+                return;
+            }
+            printer
+                .define_nv_bit_images(&[bitmap])
+                .expect("Upload failed");
+        }
+    }
+    println!("Stored image as {}", key);
+}
+
+/// Key used if none is given: graphic `LG` or bit image 1 on printers
+/// without NV graphics.
+fn default_key(profile: &PrinterProfile) -> NvKey {
+    if profile.nv_graphics {
+        NvKey::Graphic(*b"LG")
+    } else {
+        NvKey::BitImage(1)
+    }
+}
+
+/// Load a PNG image, scaled down to the paper width and dithered.
+fn load_bitmap(path: &Path, paper_width: usize) -> Bitmap {
+    let mut decoder = png::Decoder::new(File::open(path).expect("Opening image failed"));
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info().expect("Reading image failed");
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).expect("Decoding image failed");
+    let pixels = &buf[..info.buffer_size()];
+    let rgba: Vec<u8> = match info.color_type {
+        png::ColorType::Rgba => pixels.to_vec(),
+        png::ColorType::Rgb => pixels
+            .chunks_exact(3)
+            .flat_map(|px| [px[0], px[1], px[2], 255])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => pixels
+            .chunks_exact(2)
+            .flat_map(|px| [px[0], px[0], px[0], px[1]])
+            .collect(),
+        _ => pixels
+            .iter()
+            .flat_map(|&luma| [luma, luma, luma, 255])
+            .collect(),
+    };
+    let image = Image::new(
+        info.width as usize,
+        info.height as usize,
+        PixelFormat::Rgba8,
+        &rgba,
+    )
+    .expect("Invalid image");
+    let width = image.width().min(paper_width);
+    image
+        .scale_to_width(width)
+        .dither(Dithering::FloydSteinberg)
 }
@@ -0,0 +1,175 @@
+use escpos_lib::{EscPosCmd, FlowControl, Printer, PrinterConfig, Transport};
+use structopt::StructOpt;
+use tracing::{info, warn};
+
+use std::{
+    fs,
+    io::{self, BufReader, Read, Write},
+    net::TcpListener,
+    os::unix::net::UnixListener,
+    path::{Path, PathBuf},
+    sync::Arc,
+    thread,
+    time::Duration,
+};
+
+mod protocol;
+mod spool;
+
+use spool::{Job, JobKind, Spool};
+
+/// Time between two status requests while the printer is not ready.
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Time to wait for the printer to process a job.
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, StructOpt)]
+struct Opt {
+    /// Serial port of the printer
+    #[structopt(long, default_value = "/dev/serial0")]
+    path: String,
+    #[structopt(long, default_value = "9600")]
+    baud_rate: u32,
+    /// Flow control of the serial port, one of `none`, `hardware` or
+    /// `software`
+    #[structopt(long, default_value = "none")]
+    flow_control: FlowControl,
+    /// Render receipts as PNG files into this directory instead of printing
+    #[structopt(long)]
+    preview: Option<String>,
+    /// Printer model, either a built-in profile name or a TOML file
+    #[structopt(long, default_value = "tm-t88iii")]
+    profile: String,
+    /// Unix socket accepting jobs
+    #[structopt(long, parse(from_os_str), default_value = "/run/print-spooler.sock")]
+    socket: PathBuf,
+    /// Also accept jobs via TCP on this address, e.g. `0.0.0.0:9101`
+    #[structopt(long)]
+    tcp: Option<String>,
+    /// Accept raw ESC/POS jobs via TCP, not only text
+    #[structopt(long)]
+    tcp_raw: bool,
+    /// Directory storing the queued jobs
+    #[structopt(long, parse(from_os_str), default_value = "/var/spool/print-spooler")]
+    spool: PathBuf,
+}
+
+fn main() {
+    tracing_subscriber::fmt().init();
+    let opt = Opt::from_args();
+    let config = PrinterConfig {
+        path: opt.path,
+        baud_rate: opt.baud_rate,
+        preview: opt.preview,
+        profile: Some(opt.profile),
+        flow_control: opt.flow_control,
+    };
+    let profile = config.load_profile().expect("Loading profile failed");
+    let port = config.open_transport().expect("Init printer failed");
+    let printer = Printer::with_profile(port, profile).expect("Init writing failed");
+    let spool = Arc::new(Spool::open(&opt.spool).expect("Opening spool failed"));
+    listen_unix(&opt.socket, &spool);
+    if let Some(ref addr) = opt.tcp {
+        listen_tcp(addr, opt.tcp_raw, &spool);
+    }
+    info!("Started!");
+    run_printer(printer, &spool);
+}
+
+/// Accept connections on the Unix socket at `path` in the background.
+fn listen_unix(path: &Path, spool: &Arc<Spool>) {
+    // Left over from a previous run
+    let _ = fs::remove_file(path);
+    let listener = UnixListener::bind(path).expect("Binding socket failed");
+    let spool = Arc::clone(spool);
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let stream = stream.and_then(|stream| Ok((stream.try_clone()?, stream)));
+            serve_connection(stream, &spool, true);
+        }
+    });
+    info!("Listening on {:?}", path);
+}
+
+/// Accept connections on the TCP address `addr` in the background.
+fn listen_tcp(addr: &str, allow_raw: bool, spool: &Arc<Spool>) {
+    let listener = TcpListener::bind(addr).expect("Binding TCP socket failed");
+    let spool = Arc::clone(spool);
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let stream = stream.and_then(|stream| Ok((stream.try_clone()?, stream)));
+            serve_connection(stream, &spool, allow_raw);
+        }
+    });
+    info!("Listening on {}", addr);
+}
+
+/// Answer the requests of a new client in the background.
+fn serve_connection<S>(stream: io::Result<(S, S)>, spool: &Arc<Spool>, allow_raw: bool)
+where
+    S: Read + Write + Send + 'static,
+{
+    match stream {
+        Ok((reader, writer)) => {
+            let spool = Arc::clone(spool);
+            thread::spawn(move || {
+                let reader = BufReader::new(reader);
+                if let Err(why) = protocol::serve(reader, writer, &spool, allow_raw) {
+                    warn!("Connection failed: {}", why);
+                }
+            });
+        }
+        Err(why) => warn!("Accepting connection failed: {}", why),
+    }
+}
+
+/// Print queued jobs forever, pausing while the printer is not ready.
+fn run_printer<T: Transport>(mut printer: Printer<T>, spool: &Spool) {
+    loop {
+        spool.wait_for_job();
+        wait_until_ready(&mut printer, spool);
+        // The job may have been cancelled while paused
+        if let Some(job) = spool.take() {
+            info!("Printing job {}", job.id);
+            let result = print_job(&mut printer, &job).map_err(|why| {
+                warn!("Job {} failed: {}", job.id, why);
+                why.to_string()
+            });
+            spool.finish(job.id, result);
+        }
+    }
+}
+
+/// Block until the printer reports that it is able to print, e.g. until
+/// the paper has been refilled.
+fn wait_until_ready<T: Transport>(printer: &mut Printer<T>, spool: &Spool) {
+    loop {
+        match printer.check_ready() {
+            Ok(()) => {
+                if spool.set_paused(None) {
+                    info!("Printer ready, resuming");
+                }
+                return;
+            }
+            Err(why) => {
+                if spool.set_paused(Some(why.to_string())) {
+                    warn!("Pausing: {}", why);
+                }
+                thread::sleep(PAUSE_POLL_INTERVAL);
+            }
+        }
+    }
+}
+
+fn print_job<T: Transport>(printer: &mut Printer<T>, job: &Job) -> escpos_lib::Result<()> {
+    // Every job starts with the default settings
+    printer.exec(EscPosCmd::InitializePrinter)?;
+    match job.kind {
+        JobKind::Raw => printer.transport_mut().write_all(&job.data)?,
+        JobKind::Text => {
+            printer.write_untrusted(&String::from_utf8_lossy(&job.data))?;
+            printer.cut()?;
+        }
+    }
+    printer.drain(DRAIN_TIMEOUT)
+}
@@ -0,0 +1,186 @@
+use tracing::info;
+
+use std::io::{self, BufRead, Read, Write};
+
+use crate::spool::{JobKind, Spool};
+
+/// Largest job accepted, in bytes.
+pub const MAX_JOB_SIZE: usize = 16 * 1024 * 1024;
+/// Longest request line accepted, in bytes.
+pub const MAX_LINE: usize = 256;
+
+/// Answer requests read from `reader` until the client disconnects.
+///
+/// Every request is a single line, answered by a single line unless
+/// noted otherwise. Failed requests are answered with `ERR <reason>`.
+///
+/// - `SUBMIT <priority> <length>` followed by `length` bytes of ESC/POS
+///   commands, answered with `OK <id>`
+/// - `TEXT <priority> <length>` followed by `length` bytes of UTF-8 text,
+///   printed with all control characters replaced and cut afterwards,
+///   answered with `OK <id>`
+/// - `STATUS <id>`, answered with `QUEUED <position>`, `PRINTING`,
+///   `DONE`, `FAILED <reason>` or `CANCELLED`
+/// - `CANCEL <id>`, answered with `OK` if the job was still queued
+/// - `QUEUE`, answered with one `<id> <priority> <state>` line per job in
+///   printing order, followed by `END`
+/// - `PRINTER`, answered with `READY` or `PAUSED <reason>`
+///
+/// Raw ESC/POS jobs are rejected unless `allow_raw` is set, since they
+/// can e.g. write the NV memory. Lines longer than [`MAX_LINE`] end the
+/// connection.
+pub fn serve<R: BufRead, W: Write>(
+    mut reader: R,
+    mut writer: W,
+    spool: &Spool,
+    allow_raw: bool,
+) -> io::Result<()> {
+    let mut line = String::new();
+    loop {
+        line.clear();
+        let len = (&mut reader).take(MAX_LINE as u64).read_line(&mut line)?;
+        if len == 0 {
+            return Ok(());
+        }
+        if len == MAX_LINE && !line.ends_with('\n') {
+            // The rest of the line cannot be told apart from the next request
+            writeln!(writer, "ERR request too long")?;
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "request too long",
+            ));
+        }
+        let args: Vec<_> = line.split_whitespace().collect();
+        match args.as_slice() {
+            ["SUBMIT", priority, len] if allow_raw => {
+                submit(&mut reader, &mut writer, spool, JobKind::Raw, priority, len)?
+            }
+            ["SUBMIT", ..] => writeln!(writer, "ERR raw jobs are not allowed")?,
+            ["TEXT", priority, len] => submit(
+                &mut reader,
+                &mut writer,
+                spool,
+                JobKind::Text,
+                priority,
+                len,
+            )?,
+            ["STATUS", id] => match id.parse().ok().and_then(|id| spool.state(id)) {
+                Some(state) => writeln!(writer, "{}", state)?,
+                None => writeln!(writer, "ERR unknown job")?,
+            },
+            ["CANCEL", id] => match id.parse() {
+                Ok(id) if spool.cancel(id)? => {
+                    info!("Cancelled job {}", id);
+                    writeln!(writer, "OK")?
+                }
+                _ => writeln!(writer, "ERR job is not queued")?,
+            },
+            ["QUEUE"] => {
+                for (id, priority, state) in spool.jobs() {
+                    writeln!(writer, "{} {} {}", id, priority, state)?;
+                }
+                writeln!(writer, "END")?
+            }
+            ["PRINTER"] => match spool.paused() {
+                Some(why) => writeln!(writer, "PAUSED {}", why)?,
+                None => writeln!(writer, "READY")?,
+            },
+            _ => writeln!(writer, "ERR invalid request")?,
+        }
+        writer.flush()?;
+    }
+}
+
+/// Read the job data following a submit request and queue it.
+fn submit<R: BufRead, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    spool: &Spool,
+    kind: JobKind,
+    priority: &str,
+    len: &str,
+) -> io::Result<()> {
+    let len = match len.parse() {
+        Ok(len) if len <= MAX_JOB_SIZE => len,
+        // The data following cannot be skipped, so give up on the client
+        _ => {
+            writeln!(writer, "ERR invalid length")?;
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "invalid job length",
+            ));
+        }
+    };
+    let mut data = vec![0; len];
+    reader.read_exact(&mut data)?;
+    let priority = match priority.parse() {
+        Ok(priority) => priority,
+        Err(_) => return writeln!(writer, "ERR invalid priority"),
+    };
+    if kind == JobKind::Text && std::str::from_utf8(&data).is_err() {
+        return writeln!(writer, "ERR text is not UTF-8");
+    }
+    let id = spool.submit(priority, kind, data)?;
+    info!("Queued {} job {} with priority {}", kind, id, priority);
+    writeln!(writer, "OK {}", id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Answers to the requests in `input`, sent to a new spool.
+    fn answers(name: &str, input: &[u8], allow_raw: bool) -> (String, io::Result<()>) {
+        let dir = std::env::temp_dir().join(format!(
+            "print-spooler-{}-protocol-{}",
+            std::process::id(),
+            name
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        let spool = Spool::open(dir).unwrap();
+        let mut output = Vec::new();
+        let res = serve(input, &mut output, &spool, allow_raw);
+        (String::from_utf8(output).unwrap(), res)
+    }
+
+    #[test]
+    fn submit_and_query_jobs() {
+        let input = b"TEXT 1 6\nHello\nSUBMIT 2 2\n\x1b@QUEUE\nSTATUS 0\nCANCEL 0\nCANCEL 0\nPRINTER\n";
+        let (output, res) = answers("submit", input, true);
+        res.unwrap();
+        assert_eq!(
+            output,
+            "OK 0\nOK 1\n1 2 QUEUED 1\n0 1 QUEUED 2\nEND\nQUEUED 2\nOK\n\
+             ERR job is not queued\nREADY\n"
+        );
+    }
+
+    #[test]
+    fn invalid_requests() {
+        let input = b"SUBMIT 0 1\nTEXT x 1\nATEXT 0 1\n\xffSTATUS 7\nHELLO\n";
+        let (output, res) = answers("invalid", input, false);
+        res.unwrap();
+        assert_eq!(
+            output,
+            "ERR raw jobs are not allowed\nERR invalid priority\nERR text is not UTF-8\n\
+             ERR unknown job\nERR invalid request\n"
+        );
+    }
+
+    #[test]
+    fn long_requests_end_the_connection() {
+        let mut input = vec![b'A'; MAX_LINE * 2];
+        input.push(b'\n');
+        let (output, res) = answers("long", &input, false);
+        assert_eq!(output, "ERR request too long\n");
+        assert_eq!(res.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn invalid_length_ends_the_connection() {
+        let input = format!("TEXT 0 {}\n", MAX_JOB_SIZE + 1);
+        let (output, res) = answers("length", input.as_bytes(), false);
+        assert_eq!(output, "ERR invalid length\n");
+        assert!(res.is_err());
+    }
+}
@@ -0,0 +1,397 @@
+use tracing::{info, warn};
+
+use std::{
+    cmp::Reverse,
+    collections::VecDeque,
+    fmt,
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Condvar, Mutex, MutexGuard},
+};
+
+/// Number of finished jobs whose state can still be queried.
+const FINISHED_JOBS: usize = 100;
+
+pub type JobId = u64;
+
+/// How the data of a job is printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobKind {
+    /// ESC/POS commands, written as they are.
+    Raw,
+    /// UTF-8 text, sanitized and cut afterwards.
+    Text,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Job {
+    pub id: JobId,
+    /// Jobs with a higher priority are printed first, jobs with the
+    /// same priority in the order they were submitted.
+    pub priority: u8,
+    pub kind: JobKind,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobState {
+    /// Waiting for the jobs before it, 1 is printed next.
+    Queued(usize),
+    Printing,
+    Done,
+    Failed(String),
+    Cancelled,
+}
+
+/// Queue of print jobs, shared between the connections and the printer.
+///
+/// Every queued job is stored as a file in the spool directory until it
+/// is printed, so jobs survive restarts.
+pub struct Spool {
+    dir: PathBuf,
+    queue: Mutex<Queue>,
+    changed: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct Queue {
+    next_id: JobId,
+    /// Sorted in printing order.
+    jobs: Vec<Job>,
+    /// Id and priority of the job printing right now.
+    printing: Option<(JobId, u8)>,
+    finished: VecDeque<(JobId, JobState)>,
+    /// Why the printer cannot print right now.
+    paused: Option<String>,
+}
+
+impl Spool {
+    /// Open the spool directory `dir` and queue all jobs stored in it.
+    pub fn open<P: AsRef<Path>>(dir: P) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        let mut queue = Queue::default();
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("job") {
+                continue;
+            }
+            match load_job(&path) {
+                Ok(job) => {
+                    queue.next_id = queue.next_id.max(job.id + 1);
+                    queue.insert(job);
+                }
+                Err(why) => warn!("Skipping unreadable job {:?}: {}", path, why),
+            }
+        }
+        if !queue.jobs.is_empty() {
+            info!("Restored {} queued jobs", queue.jobs.len());
+        }
+        Ok(Spool {
+            dir,
+            queue: Mutex::new(queue),
+            changed: Condvar::new(),
+        })
+    }
+
+    /// Queue a new job and return its id.
+    pub fn submit(&self, priority: u8, kind: JobKind, data: Vec<u8>) -> io::Result<JobId> {
+        let id = {
+            let mut queue = self.lock();
+            queue.next_id += 1;
+            queue.next_id - 1
+        };
+        let job = Job {
+            id,
+            priority,
+            kind,
+            data,
+        };
+        // Storing takes a while, the queue stays usable meanwhile
+        self.store(&job)?;
+        self.lock().insert(job);
+        self.changed.notify_all();
+        Ok(id)
+    }
+
+    /// State of the job `id`, `None` if it is unknown or finished long ago.
+    pub fn state(&self, id: JobId) -> Option<JobState> {
+        self.lock().state(id)
+    }
+
+    /// All queued jobs in printing order, including the one printing.
+    pub fn jobs(&self) -> Vec<(JobId, u8, JobState)> {
+        let queue = self.lock();
+        let printing = queue
+            .printing
+            .map(|(id, priority)| (id, priority, JobState::Printing));
+        let queued = queue
+            .jobs
+            .iter()
+            .enumerate()
+            .map(|(idx, job)| (job.id, job.priority, JobState::Queued(idx + 1)));
+        printing.into_iter().chain(queued).collect()
+    }
+
+    /// Remove the job `id` from the queue. Returns `false` if it is not
+    /// queued, i.e. already printing or finished.
+    pub fn cancel(&self, id: JobId) -> io::Result<bool> {
+        let mut queue = self.lock();
+        match queue.jobs.iter().position(|job| job.id == id) {
+            Some(idx) => {
+                // Still queued if this fails, otherwise it is restored on restart
+                fs::remove_file(self.path(id))?;
+                queue.jobs.remove(idx);
+                queue.finish(id, JobState::Cancelled);
+                self.changed.notify_all();
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Block until a job is queued.
+    pub fn wait_for_job(&self) {
+        let mut queue = self.lock();
+        while queue.jobs.is_empty() {
+            queue = self.changed.wait(queue).expect("spool lock poisoned");
+        }
+    }
+
+    /// Start printing the next job, if there is one.
+    pub fn take(&self) -> Option<Job> {
+        let mut queue = self.lock();
+        if queue.jobs.is_empty() {
+            return None;
+        }
+        let job = queue.jobs.remove(0);
+        queue.printing = Some((job.id, job.priority));
+        Some(job)
+    }
+
+    /// Mark the job `id` as printed, or as failed with the given reason.
+    ///
+    /// Failed jobs are not retried, since parts of them may have been
+    /// printed already.
+    pub fn finish(&self, id: JobId, result: Result<(), String>) {
+        if let Err(why) = fs::remove_file(self.path(id)) {
+            warn!("Could not remove job {}: {}", id, why);
+        }
+        let mut queue = self.lock();
+        queue.printing = None;
+        let state = match result {
+            Ok(()) => JobState::Done,
+            Err(why) => JobState::Failed(why),
+        };
+        queue.finish(id, state);
+    }
+
+    /// Why printing is paused, `None` if the printer is ready.
+    pub fn paused(&self) -> Option<String> {
+        self.lock().paused.clone()
+    }
+
+    /// Pause printing for `reason`, `None` resumes it.
+    ///
+    /// Returns whether the reason changed.
+    pub fn set_paused(&self, reason: Option<String>) -> bool {
+        let mut queue = self.lock();
+        let changed = queue.paused != reason;
+        queue.paused = reason;
+        changed
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Queue> {
+        self.queue.lock().expect("spool lock poisoned")
+    }
+
+    fn path(&self, id: JobId) -> PathBuf {
+        self.dir.join(format!("{}.job", id))
+    }
+
+    /// Write `job` to the spool directory.
+    ///
+    /// The file is renamed into place, so a crash never leaves half a job.
+    fn store(&self, job: &Job) -> io::Result<()> {
+        let path = self.path(job.id);
+        let tmp = path.with_extension("tmp");
+        let mut file = File::create(&tmp)?;
+        writeln!(file, "{} {}", job.priority, job.kind)?;
+        file.write_all(&job.data)?;
+        file.sync_all()?;
+        fs::rename(tmp, path)
+    }
+}
+
+impl Queue {
+    fn insert(&mut self, job: Job) {
+        let idx = self
+            .jobs
+            .iter()
+            .position(|queued| {
+                (Reverse(job.priority), job.id) < (Reverse(queued.priority), queued.id)
+            })
+            .unwrap_or(self.jobs.len());
+        self.jobs.insert(idx, job);
+    }
+
+    fn state(&self, id: JobId) -> Option<JobState> {
+        if self.printing.map(|(printing, _)| printing) == Some(id) {
+            return Some(JobState::Printing);
+        }
+        if let Some(idx) = self.jobs.iter().position(|job| job.id == id) {
+            return Some(JobState::Queued(idx + 1));
+        }
+        self.finished
+            .iter()
+            .find(|(finished, _)| *finished == id)
+            .map(|(_, state)| state.clone())
+    }
+
+    fn finish(&mut self, id: JobId, state: JobState) {
+        if self.finished.len() == FINISHED_JOBS {
+            self.finished.pop_front();
+        }
+        self.finished.push_back((id, state));
+    }
+}
+
+/// Read a job stored by [`Spool::store`].
+fn load_job(path: &Path) -> io::Result<Job> {
+    let invalid = |why: &str| io::Error::new(io::ErrorKind::InvalidData, why.to_string());
+    let id = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .and_then(|stem| stem.parse().ok())
+        .ok_or_else(|| invalid("invalid job file name"))?;
+    let content = fs::read(path)?;
+    let newline = content
+        .iter()
+        .position(|&byte| byte == b'\n')
+        .ok_or_else(|| invalid("missing job header"))?;
+    let header = String::from_utf8_lossy(&content[..newline]);
+    let (priority, kind) = header
+        .split_once(' ')
+        .ok_or_else(|| invalid("invalid job header"))?;
+    Ok(Job {
+        id,
+        priority: priority.parse().map_err(|_| invalid("invalid priority"))?,
+        kind: kind.parse().map_err(|_| invalid("invalid job kind"))?,
+        data: content[newline + 1..].to_vec(),
+    })
+}
+
+impl fmt::Display for JobKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JobKind::Raw => write!(f, "raw"),
+            JobKind::Text => write!(f, "text"),
+        }
+    }
+}
+
+impl FromStr for JobKind {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "raw" => Ok(JobKind::Raw),
+            "text" => Ok(JobKind::Text),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for JobState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JobState::Queued(position) => write!(f, "QUEUED {}", position),
+            JobState::Printing => write!(f, "PRINTING"),
+            JobState::Done => write!(f, "DONE"),
+            JobState::Failed(why) => write!(f, "FAILED {}", why),
+            JobState::Cancelled => write!(f, "CANCELLED"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Empty spool directory unique to the test `name`.
+    fn spool_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "print-spooler-{}-{}",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn ids(spool: &Spool) -> Vec<JobId> {
+        spool.jobs().into_iter().map(|(id, _, _)| id).collect()
+    }
+
+    #[test]
+    fn higher_priority_first_then_submission_order() {
+        let spool = Spool::open(spool_dir("order")).unwrap();
+        for priority in [0, 5, 0, 9, 5] {
+            spool.submit(priority, JobKind::Text, Vec::new()).unwrap();
+        }
+        assert_eq!(ids(&spool), [3, 1, 4, 0, 2]);
+        assert_eq!(spool.state(4), Some(JobState::Queued(3)));
+
+        let job = spool.take().unwrap();
+        assert_eq!(job.id, 3);
+        assert_eq!(spool.state(3), Some(JobState::Printing));
+        assert_eq!(ids(&spool), [3, 1, 4, 0, 2]);
+        spool.finish(3, Err(String::from("out of paper")));
+        assert_eq!(
+            spool.state(3),
+            Some(JobState::Failed(String::from("out of paper")))
+        );
+        assert_eq!(ids(&spool), [1, 4, 0, 2]);
+    }
+
+    #[test]
+    fn queued_jobs_survive_restart() {
+        let dir = spool_dir("restart");
+        {
+            let spool = Spool::open(&dir).unwrap();
+            spool.submit(1, JobKind::Raw, vec![0x1b, b'@', b'\n']).unwrap();
+            spool.submit(2, JobKind::Text, b"Hello\n".to_vec()).unwrap();
+            spool.submit(1, JobKind::Text, Vec::new()).unwrap();
+            assert!(spool.cancel(2).unwrap());
+            let printed = spool.take().unwrap();
+            spool.finish(printed.id, Ok(()));
+        }
+        let spool = Spool::open(&dir).unwrap();
+        assert_eq!(
+            spool.take(),
+            Some(Job {
+                id: 0,
+                priority: 1,
+                kind: JobKind::Raw,
+                data: vec![0x1b, b'@', b'\n'],
+            })
+        );
+        assert_eq!(spool.take(), None);
+        assert_eq!(spool.submit(0, JobKind::Text, Vec::new()).unwrap(), 1);
+    }
+
+    #[test]
+    fn cancel_only_queued_jobs() {
+        let spool = Spool::open(spool_dir("cancel")).unwrap();
+        spool.submit(0, JobKind::Text, Vec::new()).unwrap();
+        spool.submit(0, JobKind::Text, Vec::new()).unwrap();
+        spool.take().unwrap();
+        assert!(!spool.cancel(0).unwrap());
+        assert!(spool.cancel(1).unwrap());
+        assert!(!spool.cancel(1).unwrap());
+        assert_eq!(spool.state(1), Some(JobState::Cancelled));
+        assert!(!spool.path(1).exists());
+    }
+}
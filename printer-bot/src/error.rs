@@ -13,5 +13,5 @@ pub enum Error {
     #[error("could not send message")]
     SendingMessage(#[source] telegram_bot::Error),
     #[error("printing error")]
-    Printing(#[source] io::Error),
+    Printing(#[source] escpos_lib::Error),
 }
@@ -1,9 +1,8 @@
-use escpos_lib::{FmtStr, Printer};
+use escpos_lib::{AsyncPrinter, Font, PaperSensors, Printer, Pulse, TextStyle, Transport};
 use futures::StreamExt;
-use serialport::SerialPort;
 use telegram_bot::{
-    Api, CanSendMessage, Error as TelegramError, Message, MessageKind, UpdateKind, UpdatesStream,
-    User as TelegramUser, UserId,
+    Api, CanSendMessage, Error as TelegramError, Message, MessageEntity, MessageEntityKind,
+    MessageKind, UpdateKind, UpdatesStream, User as TelegramUser, UserId,
 };
 use tracing::{info, warn};
 
@@ -18,11 +17,18 @@ mod settings;
 pub use error::Error;
 pub use settings::SETTINGS;
 
+/// Number of print jobs queued before further prints have to wait.
+const PRINT_QUEUE: usize = 8;
+/// How often to ask an unreachable printer again before giving up.
+const PRINT_RETRIES: usize = 3;
+/// Time between two attempts to reach the printer.
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+
 /// All relevant state.
-pub struct TelegramBot<P: SerialPort> {
+pub struct TelegramBot<T: Transport> {
     api: Api,
     stream: UpdatesStream,
-    printer: Printer<P>,
+    printer: AsyncPrinter<T>,
     history: History,
 }
 
@@ -47,8 +53,8 @@ impl History {
 /// Possible commands that can be executed.
 #[derive(Debug, PartialEq, Eq)]
 pub enum CommandKind {
-    /// Print the given string.
-    Print(String),
+    /// Print the given text, split into differently styled parts.
+    Print(Vec<(TextStyle, String)>),
 }
 
 /// Command send via Telegram.
@@ -60,16 +66,33 @@ pub struct Command {
     pub kind: CommandKind,
 }
 
-impl<P: SerialPort> TelegramBot<P> {
+impl<T: Transport + Send + 'static> TelegramBot<T> {
     /// Initialize the bot with all corresponding data.
     ///
     /// # Arguments
-    /// - `port`: The serial port the printer is connected to.
-    pub fn init(port: P) -> Self {
+    /// - `port`: The transport the printer is connected to.
+    pub fn init(port: T) -> Self {
         let token = &SETTINGS.bot.token;
         let api = Api::new(token);
         let stream = api.stream();
-        let printer = Printer::new(port).expect("Failed to initialize printer");
+        let profile = SETTINGS
+            .printer
+            .connection
+            .load_profile()
+            .expect("Failed to load printer profile");
+        let mut printer =
+            Printer::with_profile(port, profile).expect("Failed to initialize printer");
+        if SETTINGS.printer.lock_panel_buttons {
+            printer
+                .enable_panel_buttons(false)
+                .expect("Failed to lock panel buttons");
+        }
+        if SETTINGS.printer.stop_on_near_end {
+            printer
+                .set_paper_stop_sensors(PaperSensors::NEAR_END)
+                .expect("Failed to configure paper sensors");
+        }
+        let printer = AsyncPrinter::spawn(printer, PRINT_QUEUE).expect("Failed to start printer");
         let history = History::default();
         TelegramBot {
             api,
@@ -94,18 +117,32 @@ impl<P: SerialPort> TelegramBot<P> {
     pub async fn handle(&mut self, cmd: &Command) -> Result<(), Error> {
         let Command { source, kind } = cmd;
         match kind {
-            CommandKind::Print(text) => self.handle_print_cmd(source, text).await,
+            CommandKind::Print(spans) => self.handle_print_cmd(source, spans).await,
         }
     }
     /// Handle the print command.
     ///
     /// Prints the data and sends feedback to the user who issued it.
-    async fn handle_print_cmd(&mut self, source: &TelegramUser, text: &str) -> Result<(), Error> {
+    async fn handle_print_cmd(
+        &mut self,
+        source: &TelegramUser,
+        spans: &[(TextStyle, String)],
+    ) -> Result<(), Error> {
+        let text: String = spans.iter().map(|(_, text)| text.as_str()).collect();
         if self.is_printing_allowed(source.id) {
             if self.is_print_length_allowed(source.id, text.len()) {
-                self.print_message(source, text)?;
-                info!("Printed message {:?} from id '{}'", text, source.id);
-                self.send(source.id, "🖨️✅").await
+                match self.print_message(source, spans).await {
+                    Ok(()) => {
+                        info!("Printed message {:?} from id '{}'", text, source.id);
+                        self.send(source.id, "🖨️✅").await
+                    }
+                    Err(Error::Printing(why)) => {
+                        let reply = format!("🖨️❌ {}", why.user_message());
+                        self.send(source.id, &reply).await?;
+                        Err(Error::Printing(why))
+                    }
+                    Err(why) => Err(why),
+                }
             } else {
                 self.send(source.id, "🖨️❌ That message is too long!")
                     .await?;
@@ -122,29 +159,64 @@ impl<P: SerialPort> TelegramBot<P> {
         }
     }
     /// Print a simple startup message to announce that the bot is running
-    pub fn print_startup_message(&mut self) -> Result<(), Error> {
+    pub async fn print_startup_message(&mut self) -> Result<(), Error> {
         self.printer
             .write_and_cut("*** Printer-bot started ***\n")
+            .await
             .map_err(Error::Printing)
     }
-    /// Print the given Message.
+    /// Print the given Message and wait until it is printed.
     ///
     /// Adjusts the history aswell.
-    fn print_message(&mut self, source: &TelegramUser, text: &str) -> Result<(), Error> {
+    async fn print_message(
+        &mut self,
+        source: &TelegramUser,
+        spans: &[(TextStyle, String)],
+    ) -> Result<(), Error> {
         let name = if let Some(ref last_name) = source.last_name {
             format!(" {} {} ", source.first_name, last_name)
         } else {
             format!(" {} ", source.first_name)
         };
-        let text = any_ascii::any_ascii(text);
-        let text = escpos_lib::escape(&text);
-        let formatted = format!("{}: {}\n", name.reverse(), text);
+        let name_style = TextStyle {
+            reverse: true,
+            ..Default::default()
+        };
+        let mut message = vec![(name_style, name), (TextStyle::default(), ": ".to_string())];
+        message.extend_from_slice(spans);
+        let mut document = self.printer.document();
+        document.rich_text(&message);
+        self.wait_until_ready().await.map_err(Error::Printing)?;
         self.printer
-            .write_and_cut(formatted)
+            .run(move |printer| {
+                printer.print_document(&document)?;
+                printer.write_and_cut("")?;
+                if SETTINGS.printer.buzzer {
+                    printer.pulse(&Pulse::default())?;
+                }
+                Ok(())
+            })
+            .await
             .map_err(Error::Printing)?;
         self.history.add_print(&source.id);
         Ok(())
     }
+    /// Make sure the printer is able to print, asking again a few times
+    /// if the connection fails. A printer that does not answer at all is
+    /// printed to anyway.
+    async fn wait_until_ready(&self) -> Result<(), escpos_lib::Error> {
+        let mut retries = 0;
+        loop {
+            match self.printer.run(|printer| printer.check_ready()).await {
+                Err(why) if why.is_retryable() && retries < PRINT_RETRIES => {
+                    warn!("Printer not reachable, retrying: {}", why);
+                    retries += 1;
+                    tokio::time::sleep(RETRY_DELAY).await;
+                }
+                res => return res,
+            }
+        }
+    }
     /// Compares the message length with the permissions.
     fn is_print_length_allowed(&self, id: UserId, len: usize) -> bool {
         if let Some(role) = SETTINGS.get_role(id) {
@@ -177,11 +249,11 @@ impl<P: SerialPort> TelegramBot<P> {
 /// Parse a Telegram message into a [`Command`].
 fn message_to_command(message: Message) -> Option<Command> {
     // We only care about text messages
-    let kind = if let MessageKind::Text { data, .. } = message.kind {
+    let kind = if let MessageKind::Text { data, entities } = message.kind {
         if data == "/start" {
             None
         } else {
-            Some(CommandKind::Print(data))
+            Some(CommandKind::Print(styled_spans(&data, &entities)))
         }
     } else {
         None
@@ -192,12 +264,39 @@ fn message_to_command(message: Message) -> Option<Command> {
     })
 }
 
-/// Initialize the printer serial port.
-fn init_printer_port() -> impl SerialPort {
-    serialport::new(&SETTINGS.printer.path, SETTINGS.printer.baud_rate)
-        .timeout(Duration::from_secs(10))
-        .open_native()
-        .expect("Init serial failed")
+/// Split `text` into escaped parts styled according to the message `entities`.
+///
+/// Bold text is emphasized, italic and underlined text underlined and
+/// code printed in font B.
+fn styled_spans(text: &str, entities: &[MessageEntity]) -> Vec<(TextStyle, String)> {
+    // Entity offsets count UTF-16 code units
+    let mut styles = vec![TextStyle::default(); text.encode_utf16().count()];
+    for entity in entities {
+        let start = entity.offset as usize;
+        let end = start + entity.length as usize;
+        for style in styles.get_mut(start..end).into_iter().flatten() {
+            match entity.kind {
+                MessageEntityKind::Bold => style.emphasized = true,
+                MessageEntityKind::Italic | MessageEntityKind::Underline => style.underline = true,
+                MessageEntityKind::Code | MessageEntityKind::Pre => style.font = Font::B,
+                _ => {}
+            }
+        }
+    }
+    let mut spans: Vec<(TextStyle, String)> = Vec::new();
+    let mut pos = 0;
+    for c in text.chars() {
+        let style = styles[pos];
+        pos += c.len_utf16();
+        match spans.last_mut() {
+            Some((last, part)) if *last == style => part.push(c),
+            _ => spans.push((style, c.to_string())),
+        }
+    }
+    spans
+        .into_iter()
+        .map(|(style, part)| (style, escpos_lib::escape(&part)))
+        .collect()
 }
 
 #[tokio::main(flavor = "current_thread")]
@@ -207,10 +306,17 @@ async fn main() -> Result<(), Error> {
     // Initializer logger
     tracing_subscriber::fmt().pretty().init();
     // Initialize printer
-    let port = init_printer_port();
+    if let Some(ref dir) = SETTINGS.printer.connection.preview {
+        info!("Previewing receipts in {:?}", dir);
+    }
+    let port = SETTINGS
+        .printer
+        .connection
+        .open_transport()
+        .expect("Init printer port failed");
     let mut bot = TelegramBot::init(port);
     info!("Started!");
-    bot.print_startup_message()?;
+    bot.print_startup_message().await?;
     // Start polling messages from telegram
     loop {
         match bot.poll().await {
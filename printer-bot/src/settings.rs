@@ -1,3 +1,4 @@
+use escpos_lib::PrinterConfig;
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
 use telegram_bot::UserId;
@@ -56,8 +57,19 @@ pub struct Bot {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Printer {
-    pub path: String,
-    pub baud_rate: u32,
+    /// Serial port, profile and preview directory.
+    #[serde(flatten)]
+    pub connection: PrinterConfig,
+    /// Pulse the drawer kick-out connector after every print,
+    /// e.g. to sound a buzzer.
+    #[serde(default)]
+    pub buzzer: bool,
+    /// Disable the feed button, e.g. for public installations.
+    #[serde(default)]
+    pub lock_panel_buttons: bool,
+    /// Stop printing when the paper is nearly used up.
+    #[serde(default)]
+    pub stop_on_near_end: bool,
     #[serde(default, skip_serializing)]
     _cannot_create: PhantomData<()>,
 }
@@ -104,8 +116,10 @@ impl Settings {
                         _cannot_create: PhantomData,
                     }],
                     printer: Printer {
-                        path: String::from("/dev/null"),
-                        baud_rate: 9600,
+                        connection: PrinterConfig::new("/dev/null", 9600),
+                        buzzer: false,
+                        lock_panel_buttons: false,
+                        stop_on_near_end: false,
                         _cannot_create: PhantomData,
                     },
                     bot: Bot {
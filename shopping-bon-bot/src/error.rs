@@ -10,4 +10,10 @@ pub enum Error {
     ParsingSettingsFile(#[source] toml::de::Error),
     #[error("could not create settings file")]
     CreatingSettingsFile(#[source] io::Error),
+    #[error("could not load printer profile")]
+    LoadingPrinterProfile(#[source] escpos_lib::ProfileError),
+    #[error("could not open printer port")]
+    OpeningPrinterPort(#[source] io::Error),
+    #[error("could not initialize printer")]
+    InitializingPrinter(#[source] escpos_lib::Error),
 }
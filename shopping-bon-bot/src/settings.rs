@@ -1,3 +1,4 @@
+use escpos_lib::PrinterConfig;
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
 use telegram_bot::UserId;
@@ -57,8 +58,9 @@ pub struct Bot {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Printer {
-    pub path: String,
-    pub baud_rate: u32,
+    /// Serial port, profile and preview directory.
+    #[serde(flatten)]
+    pub connection: PrinterConfig,
     #[serde(default, skip_serializing)]
     _cannot_create: PhantomData<()>,
 }
@@ -98,8 +100,7 @@ impl Settings {
                         _cannot_create: PhantomData,
                     }],
                     printer: Printer {
-                        path: String::from("/dev/null"),
-                        baud_rate: 9600,
+                        connection: PrinterConfig::new("/dev/null", 9600),
                         _cannot_create: PhantomData,
                     },
                     bot: Bot {
@@ -1,28 +1,40 @@
+use escpos_lib::{AsyncPrinter, Justification, Printer, TextStyle, Transport, UserChars};
 use telegram_bot::{CanSendMessage, User};
-use tracing::error;
+use tracing::{error, info};
 
 use std::fmt;
 
 use crate::{
     bot::TelegramBot,
     settings::{Role, SETTINGS},
-    storage::{CategoryDB, ItemDB},
-    Command, CommandKind, ResultExt,
+    storage::{Categories, CategoryDB, Item, ItemDB},
+    Command, CommandKind, Error, ResultExt,
 };
 
+/// Number of print jobs queued before further prints have to wait.
+const PRINT_QUEUE: usize = 4;
+
+type BotPrinter = AsyncPrinter<Box<dyn Transport + Send>>;
+
 /// Assembly of all relevant items
 pub struct State {
     pub bot: TelegramBot,
     pub itemdb: ItemDB,
     pub categorydb: CategoryDB,
+    /// `None` if the printer could not be initialized.
+    pub printer: Option<BotPrinter>,
 }
 
 impl State {
     pub fn init() -> Self {
+        let categorydb = CategoryDB::init();
+        let printer =
+            init_printer(&categorydb).log_err("failed to initialize printer, printing is disabled");
         State {
             bot: TelegramBot::init(),
             itemdb: ItemDB::init(),
-            categorydb: CategoryDB::init(),
+            categorydb,
+            printer,
         }
     }
 
@@ -38,12 +50,13 @@ impl State {
     async fn list_items(&mut self, source: &User) {
         match SETTINGS.get_role(source.id) {
             Some(Role { read, .. }) if *read => {
+                let categories = self.categories();
                 let mut string = self
                     .itemdb
                     .read(|items| {
                         items
                             .iter()
-                            .map(|item| format!("- {}\n", item))
+                            .map(|item| format!("- {}\n", item_line(item, &categories)))
                             .fold(String::new(), |s, item| s + &item)
                     })
                     .log_err("failed to fetch items from database")
@@ -62,9 +75,25 @@ impl State {
     async fn print(&mut self, source: &User) {
         match SETTINGS.get_role(source.id) {
             Some(Role { print, .. }) if *print => {
-                error!("Printing is not implemented yet");
-                let msg = source.text("Printing unimplemented");
-                self.bot.send_or_ignore(msg).await;
+                let items = self
+                    .itemdb
+                    .read(|items| items.clone())
+                    .log_err("failed to fetch items from database");
+                let categories = self.categories();
+                let reply = match (&self.printer, items) {
+                    (None, _) => String::from("*no printer available*"),
+                    (Some(printer), Some(items)) => {
+                        match print_items(printer, &items, &categories).await {
+                            Ok(()) => String::from("🖨️✅"),
+                            Err(why) => {
+                                error!("failed to print: {}", why);
+                                format!("*{}*", why.user_message())
+                            }
+                        }
+                    }
+                    (Some(_), None) => String::from("*failed to print*"),
+                };
+                self.msg(source, &reply).await;
             }
             _ => {
                 self.msg(&source, "*missing permissions*").await;
@@ -72,20 +101,30 @@ impl State {
         }
     }
 
-    async fn add_item(&mut self, item: &str, source: &User) {
+    async fn add_item(&mut self, line: &str, source: &User) {
+        let item = parse_item(line, &self.categories());
+        let name = item.name.clone();
         match self
             .itemdb
-            .add_item(item.to_string())
+            .add_item(item)
             .log_err("failed to add item to database")
         {
             Some(_) => {
-                let msg = format!("Added '{}'.", item);
+                let msg = format!("Added '{}'.", name);
                 self.msg(source, &msg).await
             }
-            None => self.msg(source, &format!("*failed to add {}*", item)).await,
+            None => self.msg(source, &format!("*failed to add {}*", name)).await,
         }
     }
 
+    /// All categories, none if the database cannot be read.
+    fn categories(&self) -> Categories {
+        self.categorydb
+            .read(|cats| cats.clone())
+            .log_err("failed to read categories from database")
+            .unwrap_or_default()
+    }
+
     async fn msg<C: CanSendMessage + fmt::Debug>(&self, chat: &C, msg: &str) {
         if msg.is_empty() {
             error!("Msg should never be empty");
@@ -131,3 +170,103 @@ impl State {
         }
     }
 }
+
+/// Create an item from a line of a message.
+///
+/// A leading category icon, e.g. `🥛 Milch`, assigns the item to that
+/// category.
+fn parse_item(line: &str, categories: &Categories) -> Item {
+    let line = line.trim();
+    let category = line.chars().next().and_then(|first| {
+        categories
+            .iter()
+            .find(|cat| cat.icon == first && !first.is_ascii())
+    });
+    match category {
+        Some(cat) => Item {
+            name: line[cat.icon.len_utf8()..].trim_start().to_string(),
+            category: Some(cat.name.clone()),
+        },
+        None => Item {
+            name: line.to_string(),
+            category: None,
+        },
+    }
+}
+
+/// The item's name, prefixed with the icon of its category.
+fn item_line(item: &Item, categories: &Categories) -> String {
+    let icon = item
+        .category
+        .as_ref()
+        .and_then(|name| categories.iter().find(|cat| cat.name == *name))
+        .map(|cat| cat.icon);
+    match icon {
+        Some(icon) => format!("{} {}", icon, item.name),
+        None => item.name.clone(),
+    }
+}
+
+/// Print the shopping list and wait until it is printed.
+///
+/// Category icons are printed with the glyphs downloaded by
+/// [`define_category_icons`].
+async fn print_items(
+    printer: &BotPrinter,
+    items: &[Item],
+    categories: &Categories,
+) -> escpos_lib::Result<()> {
+    let mut document = printer.document();
+    document
+        .justify(Justification::Center)
+        .styled(TextStyle::large(), "Shopping list")
+        .justify(Justification::Left)
+        .rule();
+    for item in items {
+        let line = item_line(item, categories);
+        document.text(&format!("- {}", escpos_lib::escape(&line)));
+    }
+    printer
+        .run(move |printer| {
+            printer.check_ready()?;
+            printer.print_document(&document)?;
+            printer.write_and_cut("")
+        })
+        .await
+}
+
+/// Open the configured printer and start its worker thread.
+fn init_printer(categorydb: &CategoryDB) -> Result<BotPrinter, Error> {
+    let config = &SETTINGS.printer.connection;
+    if let Some(ref dir) = config.preview {
+        info!("Previewing receipts in {:?}", dir);
+    }
+    let port = config.open_transport().map_err(Error::OpeningPrinterPort)?;
+    let profile = config
+        .load_profile()
+        .map_err(Error::LoadingPrinterProfile)?;
+    let mut printer = Printer::with_profile(port, profile).map_err(Error::InitializingPrinter)?;
+    define_category_icons(&mut printer, categorydb);
+    AsyncPrinter::spawn(printer, PRINT_QUEUE).map_err(Error::InitializingPrinter)
+}
+
+/// Download glyphs for the category icons, so the printer can print
+/// them inline with text.
+fn define_category_icons<T: Transport>(printer: &mut Printer<T>, categorydb: &CategoryDB) {
+    if !printer.profile().user_chars {
+        return;
+    }
+    let icons = categorydb
+        .read(|cats| cats.iter().map(|cat| cat.icon).collect::<Vec<_>>())
+        .log_err("failed to read categories from database")
+        .unwrap_or_default();
+    let mut chars = UserChars::new();
+    for icon in icons.into_iter().filter(|icon| !icon.is_ascii()) {
+        if let Some(false) = chars.emoji(icon).log_warn("failed to define icon") {
+            info!("No glyph for category icon {}", icon);
+        }
+    }
+    printer
+        .define_user_chars(&chars)
+        .log_err("failed to define category icons");
+}
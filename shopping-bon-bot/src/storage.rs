@@ -1,13 +1,20 @@
 use rustbreak::{deser::Ron, error::RustbreakError as DbError, FileDatabase};
 use serde::{Deserialize, Serialize};
 
-type Item = String;
 pub type Items = Vec<Item>;
 pub type Categories = Vec<Category>;
 
 const ITEM_DB_PATH: &str = "shopping-bon-bot/data/items.ron";
 const CATEGORY_DB_PATH: &str = "shopping-bon-bot/data/categories.ron";
 
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Item {
+    pub name: String,
+    /// Name of the category the item belongs to.
+    #[serde(default)]
+    pub category: Option<String>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct Category {
     pub name: String,
@@ -19,10 +26,29 @@ pub struct ItemDB(FileDatabase<Items, Ron>);
 impl ItemDB {
     pub fn init() -> Self {
         FileDatabase::load_from_path_or(ITEM_DB_PATH, vec![])
+            .or_else(|_| Self::migrate())
             .map(ItemDB)
             .expect("Failed to initialize item database!")
     }
 
+    /// Load a database of plain item names, as stored before items had
+    /// categories, and save it in the current format.
+    fn migrate() -> Result<FileDatabase<Items, Ron>, DbError> {
+        let names: FileDatabase<Vec<String>, Ron> = FileDatabase::load_from_path(ITEM_DB_PATH)?;
+        let items = names.read(|names| {
+            names
+                .iter()
+                .map(|name| Item {
+                    name: name.clone(),
+                    category: None,
+                })
+                .collect()
+        })?;
+        let db = FileDatabase::from_path(ITEM_DB_PATH, items)?;
+        db.save()?;
+        Ok(db)
+    }
+
     pub fn read<T, R>(&self, task: T) -> Result<R, DbError>
     where
         T: FnOnce(&Items) -> R,
@@ -39,9 +65,10 @@ impl ItemDB {
         Ok(res)
     }
 
+    /// Add `item`, unless an item with the same name exists.
     pub fn add_item(&self, item: Item) -> Result<(), DbError> {
         self.write(|items| {
-            if !items.contains(&item) {
+            if !items.iter().any(|known| known.name == item.name) {
                 items.push(item)
             }
         })